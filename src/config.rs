@@ -34,6 +34,7 @@ pub enum SupraCommand<'a> {
     NewUserJournalFile,
     NewProject(&'a str, bool),
     ReplaceMake,
+    Rekey(&'a str, &'a str),
 }
 
 /// Output options
@@ -44,30 +45,102 @@ pub enum Output {
     Docx,
 }
 
+/// Citation styles
+///
+/// `Bluepages` is the Bluebook with the practitioners' typeface conventions
+/// used in briefs and memos. `Csl` renders citations with a user-provided CSL
+/// style file.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Style {
+    Bluebook,
+    Bluepages,
+    Oscola,
+    Aglc,
+    Csl,
+}
+
+impl Style {
+    /// Whether the style follows the Bluebook (in either typeface).
+    pub fn is_bluebook(&self) -> bool {
+        *self == Style::Bluebook || *self == Style::Bluepages
+    }
+}
+
+/// Better BibTeX's default JSON-RPC endpoint.
+pub const BBT_URL: &str = "http://127.0.0.1:23119/better-bibtex/json-rpc";
+
+/// Library file formats
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LibraryFormat {
+    CslJson,
+    CslYaml,
+    BibLatex,
+    Ris,
+    Zotero,
+}
+
+/// A loaded library file.
+pub struct LibraryFile {
+    pub name: String,
+    pub contents: String,
+    pub format: LibraryFormat,
+}
+
+impl LibraryFile {
+    pub fn new(name: &str, contents: &str, format: LibraryFormat) -> LibraryFile {
+        LibraryFile {
+            name: name.to_string(),
+            contents: contents.to_string(),
+            format,
+        }
+    }
+}
+
 /// Pre-processor configuration.
 pub struct PreConfig<'a> {
     pub input: &'a str,
-    pub library: &'a str,
+    pub libraries: Vec<&'a str>,
     pub offset: i32,
     pub user_journals: Option<&'a str>,
     pub smallcaps: bool,
+    pub style: Style,
+    pub bibliography: bool,
+    pub csl: Option<&'a str>,
+    pub house_style: Option<&'a str>,
+    pub library_format: Option<LibraryFormat>,
+    pub bbt_rpc: Option<&'a str>,
+    pub aliases: Option<&'a str>,
 }
 
 impl PreConfig<'_> {
     #[allow(clippy::too_many_arguments)]
     pub fn new<'a>(
         input: &'a str,
-        library: &'a str,
+        libraries: Vec<&'a str>,
         offset: i32,
         user_journals: Option<&'a str>,
         smallcaps: bool,
+        style: Style,
+        bibliography: bool,
+        csl: Option<&'a str>,
+        house_style: Option<&'a str>,
+        library_format: Option<LibraryFormat>,
+        bbt_rpc: Option<&'a str>,
+        aliases: Option<&'a str>,
     ) -> PreConfig<'a> {
         PreConfig {
             input,
-            library,
+            libraries,
             offset,
             user_journals,
             smallcaps,
+            style,
+            bibliography,
+            csl,
+            house_style,
+            library_format,
+            bbt_rpc,
+            aliases,
         }
     }
 }
@@ -1,5 +1,11 @@
 //! Functions for interacting with the file system (except .docx).
 
+mod bbt;
+mod zotero;
+
+pub use bbt::load_bbt;
+pub use zotero::load_zotero;
+
 use ansi_term::Color;
 use file_contents::{BLANK_USER_JOURNAL_CONTENTS, MAKEFILE_CONTENTS, MD_CONTENTS};
 use slog::debug;
@@ -0,0 +1,203 @@
+//! Functions for asking a running Better BibTeX for sources.
+//!
+//! Better BibTeX serves a JSON-RPC interface while Zotero is running. Asking it
+//! to export only the cited keys avoids both a stale auto-export and loading
+//! the whole library.
+
+use crate::pre::{aliases, cited_keys};
+use serde_json::{json, Value};
+use slog::debug;
+use std::time::Duration;
+
+/// Load the cited sources from Better BibTeX as a CSL JSON string.
+///
+/// Better BibTeX knows the sources by their current keys, so keys renamed in
+/// the alias file are asked for by their new keys.
+pub fn load_bbt(url: &str, input: &str, aliases: &Option<String>) -> Result<String, String> {
+    debug!(slog_scope::logger(), "Requesting sources from {}...", url);
+
+    let keys = match cited_keys(input) {
+        Ok(k) => k,
+        // The pre-processor reports the problems themselves.
+        Err(_) => return Err("could not find the cited keys in the input".to_string()),
+    };
+    let keys: Vec<String> = match aliases {
+        Some(a) => {
+            let aliases = aliases::build_aliases(a)?;
+            let mut resolved: Vec<String> = keys
+                .iter()
+                .map(|k| aliases::resolve(&aliases, k).to_string())
+                .collect();
+            resolved.sort_unstable();
+            resolved.dedup();
+            resolved
+        }
+        None => keys.iter().map(|k| k.to_string()).collect(),
+    };
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "method": "item.export",
+        "params": [keys, "Better CSL JSON"],
+        "id": 1,
+    });
+
+    let response = match ureq::post(url)
+        .timeout(Duration::from_secs(10))
+        .set("Content-Type", "application/json")
+        .send_string(&request.to_string())
+    {
+        Ok(r) => r,
+        Err(e) => return Err(format!("could not reach Better BibTeX—{}", e)),
+    };
+
+    let response: Value = match response.into_string() {
+        Ok(r) => match serde_json::from_str(&r) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("could not parse Better BibTeX's response—{}", e)),
+        },
+        Err(e) => return Err(format!("could not read Better BibTeX's response—{}", e)),
+    };
+
+    if let Some(error) = response.get("error") {
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(format!("Better BibTeX returned an error—{}", message));
+    }
+
+    // Older versions of Better BibTeX return [status, content type, export].
+    match response.get("result") {
+        Some(Value::String(s)) => {
+            debug!(slog_scope::logger(), "Sources received");
+            Ok(s.to_string())
+        }
+        Some(Value::Array(a)) if a.len() == 3 && a[2].is_string() => {
+            debug!(slog_scope::logger(), "Sources received");
+            Ok(a[2].as_str().unwrap().to_string())
+        }
+        _ => Err("Better BibTeX's response had no export".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    /// Answers one request with the given JSON-RPC response, returning the
+    /// request's body.
+    fn mock_server(response: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/better-bibtex/json-rpc",
+            listener.local_addr().unwrap()
+        );
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+
+            String::from_utf8(body).unwrap()
+        });
+
+        (url, handle)
+    }
+
+    #[test]
+    fn export() {
+        let (url, handle) = mock_server(
+            r#"{"jsonrpc":"2.0","id":1,"result":"[{\"id\":\"authorBookTitleTitle2021\",\"type\":\"book\"}]"}"#,
+        );
+
+        let library = load_bbt(
+            &url,
+            "Text.^[[@authorBookTitleTitle2021] at 1; [@authorBookTitleTitle2021].] More.^[[*see* @authorBookTitleTitle2021, 2; -@JohnsonSmith2023].]",
+            &None,
+        )
+        .unwrap();
+        let request: Value = serde_json::from_str(&handle.join().unwrap()).unwrap();
+
+        assert_eq!(request["method"], "item.export");
+        assert_eq!(
+            request["params"][0],
+            json!(["JohnsonSmith2023", "authorBookTitleTitle2021"])
+        );
+        assert_eq!(
+            library,
+            r#"[{"id":"authorBookTitleTitle2021","type":"book"}]"#
+        );
+    }
+
+    #[test]
+    fn aliased_keys() {
+        let (url, handle) = mock_server(r#"{"jsonrpc":"2.0","id":1,"result":"[]"}"#);
+
+        load_bbt(
+            &url,
+            "Text.^[[@smithOld] at 1.] More.^[[@smith2020]; [@jones2019].]",
+            &Some(r#"{"smithOld": "smith2020"}"#.to_string()),
+        )
+        .unwrap();
+        let request: Value = serde_json::from_str(&handle.join().unwrap()).unwrap();
+
+        assert_eq!(request["params"][0], json!(["jones2019", "smith2020"]));
+    }
+
+    #[test]
+    fn rpc_error() {
+        let (url, handle) = mock_server(
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"no items found"}}"#,
+        );
+
+        let result = load_bbt(&url, "[@missing]", &None);
+        handle.join().unwrap();
+
+        assert_eq!(
+            result,
+            Err("Better BibTeX returned an error—no items found".to_string())
+        );
+    }
+
+    #[test]
+    fn not_running() {
+        // Bind and drop a listener to get a port that nothing is listening on.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        assert!(load_bbt(&format!("http://127.0.0.1:{}/", port), "[@a]", &None).is_err());
+    }
+}
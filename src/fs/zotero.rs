@@ -0,0 +1,546 @@
+//! Functions for reading a Zotero database.
+//!
+//! Reading `zotero.sqlite` directly avoids relying on a Better BibTeX
+//! auto-export, which can lag behind the library. The database is converted
+//! into the same CSL JSON that Better BibTeX would export, so the rest of Supra
+//! doesn't need to know where the library came from.
+//!
+//! Zotero locks its databases while running, so if a database can't be read it
+//! is copied (with its write-ahead log) to a new temporary directory and the
+//! copy is read instead.
+
+use ansi_term::Color;
+use rusqlite::{types::Value as SqlValue, Connection, ErrorCode, OpenFlags};
+use serde_json::{json, Map, Value};
+use slog::{debug, trace, warn};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The number of temporary copies made so far, which keeps their names unique.
+static COPIES: AtomicUsize = AtomicUsize::new(0);
+
+/// The CSL variables that can be set in the Extra field.
+const EXTRA_VARIABLES: [&str; 13] = [
+    "type",
+    "title-short",
+    "container-title",
+    "container-title-short",
+    "collection-title",
+    "authority",
+    "volume",
+    "number",
+    "page",
+    "edition",
+    "publisher",
+    "publisher-place",
+    "status",
+];
+
+/// Load a Zotero database as a CSL JSON string.
+pub fn load_zotero(path: &Path) -> Result<String, String> {
+    debug!(
+        slog_scope::logger(),
+        "Loading Zotero database {}...",
+        path.to_string_lossy()
+    );
+
+    let keys = bbt_keys(path);
+
+    let items = match read_locked(path, |conn| read_database(conn, &keys)) {
+        Ok(i) => i,
+        Err(e) => return Err(format!("could not read the Zotero database—{}", e)),
+    };
+
+    debug!(
+        slog_scope::logger(),
+        "Zotero database {} loaded.",
+        path.to_string_lossy()
+    );
+    Ok(Value::Array(items).to_string())
+}
+
+/// Reads the citation keys from Better BibTeX's database, if there is one.
+///
+/// The keys are returned by Zotero item key.
+fn bbt_keys(path: &Path) -> HashMap<String, String> {
+    let bbt_path = path.with_file_name("better-bibtex.sqlite");
+    let mut keys = HashMap::new();
+
+    if !bbt_path.exists() {
+        return keys;
+    }
+
+    let result = read_locked(&bbt_path, |conn| {
+        let mut statement = conn.prepare("SELECT itemKey, citationKey FROM citationkey")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (item_key, citation_key): (String, String) = row?;
+            keys.insert(item_key, citation_key);
+        }
+        Ok(())
+    });
+
+    // Without these keys, items keyed only by Better BibTeX are skipped.
+    if let Err(e) = result {
+        warn!(
+            slog_scope::logger(),
+            "Could not read the Better BibTeX database: {}", e
+        );
+        eprintln!(
+            "  {} Could not read the Better BibTeX database {}: {}",
+            Color::Yellow.paint("WARN"),
+            Color::Blue.paint(bbt_path.to_string_lossy()),
+            e
+        );
+    }
+
+    keys
+}
+
+/// Reads a database, reading a copy of it if it's locked.
+///
+/// The copy is made in a new temporary directory, along with the database's
+/// write-ahead log (`-wal`) so that changes Zotero hasn't written back yet are
+/// read, too. The directory is removed afterward.
+fn read_locked<T>(
+    path: &Path,
+    mut read: impl FnMut(&Connection) -> rusqlite::Result<T>,
+) -> Result<T, String> {
+    match open(path).and_then(|conn| read(&conn)) {
+        Err(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == ErrorCode::DatabaseBusy || e.code == ErrorCode::DatabaseLocked =>
+        {
+            debug!(
+                slog_scope::logger(),
+                "{} locked; reading a copy",
+                path.to_string_lossy()
+            );
+            let dir =
+                temp_dir().map_err(|e| format!("could not copy the locked database—{}", e))?;
+            let result = copy_database(path, &dir)
+                .map_err(|e| format!("could not copy the locked database—{}", e))
+                .and_then(|copy| {
+                    // The copy is opened for writing so the log can be read
+                    // into it.
+                    Connection::open(copy)
+                        .and_then(|conn| read(&conn))
+                        .map_err(|e| e.to_string())
+                });
+            fs::remove_dir_all(&dir).ok();
+            result
+        }
+        result => result.map_err(|e| e.to_string()),
+    }
+}
+
+/// Creates a new, uniquely named temporary directory.
+fn temp_dir() -> std::io::Result<PathBuf> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+
+    loop {
+        let dir = std::env::temp_dir().join(format!(
+            "supra-zotero-{}-{}-{}",
+            std::process::id(),
+            nanos,
+            COPIES.fetch_add(1, Ordering::Relaxed)
+        ));
+        match fs::create_dir(&dir) {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            result => return result.map(|_| dir),
+        }
+    }
+}
+
+/// Copies a database and its write-ahead log, if it has one, into `dir`.
+///
+/// Returns the path to the copy.
+fn copy_database(path: &Path, dir: &Path) -> std::io::Result<PathBuf> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let copy = dir.join(name.as_ref());
+    fs::copy(path, &copy)?;
+
+    let wal = path.with_file_name(format!("{}-wal", name));
+    if wal.exists() {
+        fs::copy(wal, dir.join(format!("{}-wal", name)))?;
+    }
+
+    Ok(copy)
+}
+
+/// Opens a database read-only.
+fn open(path: &Path) -> rusqlite::Result<Connection> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+}
+
+/// Reads each regular item in the database into a CSL JSON item.
+///
+/// Items without a citation key are skipped, since they can't be cited.
+fn read_database(
+    conn: &Connection,
+    keys: &HashMap<String, String>,
+) -> rusqlite::Result<Vec<Value>> {
+    // The items, excluding attachments, notes, and the trash
+    let mut statement = conn.prepare(
+        "SELECT items.itemID, items.key, itemTypes.typeName
+        FROM items JOIN itemTypes USING (itemTypeID)
+        WHERE itemTypes.typeName NOT IN ('attachment', 'note', 'annotation')
+        AND items.itemID NOT IN (SELECT itemID FROM deletedItems)
+        ORDER BY items.itemID",
+    )?;
+    let items: Vec<(i64, String, String)> = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    // Each item's fields
+    let mut fields: HashMap<i64, Vec<(String, String)>> = HashMap::new();
+    let mut statement = conn.prepare(
+        "SELECT itemData.itemID, fields.fieldName, itemDataValues.value
+        FROM itemData
+        JOIN fields USING (fieldID)
+        JOIN itemDataValues USING (valueID)",
+    )?;
+    let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    for row in rows {
+        let (id, name, value): (i64, String, SqlValue) = row?;
+        // Zotero stores numeric values as integers.
+        let value = match value {
+            SqlValue::Text(t) => t,
+            SqlValue::Integer(i) => i.to_string(),
+            SqlValue::Real(r) => r.to_string(),
+            _ => continue,
+        };
+        fields.entry(id).or_default().push((name, value));
+    }
+
+    // Each item's creators, in order
+    let mut creators: HashMap<i64, Vec<(String, Value)>> = HashMap::new();
+    let mut statement = conn.prepare(
+        "SELECT itemCreators.itemID, creatorTypes.creatorType, creators.firstName,
+        creators.lastName, creators.fieldMode
+        FROM itemCreators
+        JOIN creators USING (creatorID)
+        JOIN creatorTypes USING (creatorTypeID)
+        ORDER BY itemCreators.itemID, itemCreators.orderIndex",
+    )?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get(0)?,
+            row.get(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<i64>>(4)?,
+        ))
+    })?;
+    for row in rows {
+        let (id, creator_type, first, last, mode): (i64, String, _, _, _) = row?;
+        let name = if mode == Some(1) {
+            json!({ "literal": last.unwrap_or_default() })
+        } else {
+            json!({ "family": last.unwrap_or_default(), "given": first.unwrap_or_default() })
+        };
+        creators.entry(id).or_default().push((creator_type, name));
+    }
+
+    let mut library = Vec::with_capacity(items.len());
+    for (id, item_key, type_name) in items {
+        let item_fields = fields.remove(&id).unwrap_or_default();
+        let item_creators = creators.remove(&id).unwrap_or_default();
+
+        match build_item(&item_key, &type_name, item_fields, item_creators, keys) {
+            Some(i) => library.push(i),
+            None => trace!(
+                slog_scope::logger(),
+                "Skipping Zotero item {} without a citation key",
+                item_key
+            ),
+        }
+    }
+
+    Ok(library)
+}
+
+/// Converts an item's fields and creators into a CSL JSON item.
+fn build_item(
+    item_key: &str,
+    type_name: &str,
+    fields: Vec<(String, String)>,
+    creators: Vec<(String, Value)>,
+    keys: &HashMap<String, String>,
+) -> Option<Value> {
+    let mut item = Map::new();
+    let mut citation_key = keys.get(item_key).cloned();
+
+    item.insert("type".to_string(), json!(csl_type(type_name)));
+
+    let mut extra = None;
+    for (name, value) in fields {
+        if name == "citationKey" {
+            citation_key = Some(value);
+            continue;
+        }
+
+        if name == "extra" {
+            extra = Some(value);
+            continue;
+        }
+
+        let csl_name = match name.as_str() {
+            "title" | "caseName" | "nameOfAct" | "subject" => "title",
+            "shortTitle" => "title-short",
+            "publicationTitle" | "bookTitle" | "reporter" | "code" | "websiteTitle"
+            | "blogTitle" | "proceedingsTitle" | "encyclopediaTitle" | "dictionaryTitle" => {
+                "container-title"
+            }
+            "journalAbbreviation" => "container-title-short",
+            "court" | "legislativeBody" | "committee" => "authority",
+            "volume" | "reporterVolume" | "codeVolume" | "codeNumber" => "volume",
+            "issue" => "issue",
+            "pages" | "firstPage" | "codePages" => "page",
+            "edition" => "edition",
+            "publisher" | "institution" | "university" | "label" => "publisher",
+            "place" => "publisher-place",
+            "number" | "billNumber" | "reportNumber" | "publicLawNumber" | "docketNumber"
+            | "documentNumber" => "number",
+            "series" => "collection-title",
+            "section" => "section",
+            "url" => "URL",
+            "DOI" => "DOI",
+            "ISBN" => "ISBN",
+            "ISSN" => "ISSN",
+            "abstractNote" => "abstract",
+            "language" => "language",
+            "date" | "dateDecided" | "dateEnacted" => {
+                if let Some(d) = date(&value) {
+                    item.insert("issued".to_string(), d);
+                }
+                continue;
+            }
+            "accessDate" => {
+                if let Some(d) = date(&value) {
+                    item.insert("accessed".to_string(), d);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        item.entry(csl_name.to_string()).or_insert(json!(value));
+    }
+
+    // Better BibTeX pins keys in the Extra field, and Zotero lets CSL variables
+    // that it has no field for be entered there, one per line.
+    if let Some(extra) = extra {
+        for line in extra.lines() {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            if name == "Citation Key" {
+                if citation_key.is_none() {
+                    citation_key = Some(value.to_string());
+                }
+            } else if EXTRA_VARIABLES.contains(&name) && !value.is_empty() {
+                item.insert(name.to_string(), json!(value));
+            }
+        }
+        item.insert("note".to_string(), json!(extra));
+    }
+
+    let mut names: HashMap<&str, Vec<Value>> = HashMap::new();
+    for (creator_type, name) in creators {
+        let csl_name = match creator_type.as_str() {
+            "author" | "sponsor" | "programmer" | "artist" | "director" | "presenter"
+            | "cartographer" | "inventor" | "podcaster" | "performer" => "author",
+            "editor" => "editor",
+            "seriesEditor" => "collection-editor",
+            "bookAuthor" => "container-author",
+            "translator" => "translator",
+            _ => continue,
+        };
+        names.entry(csl_name).or_default().push(name);
+    }
+    for (csl_name, list) in names {
+        item.insert(csl_name.to_string(), Value::Array(list));
+    }
+
+    let citation_key = citation_key.filter(|k| !k.is_empty())?;
+    item.insert("id".to_string(), json!(citation_key));
+
+    Some(Value::Object(item))
+}
+
+/// Converts a Zotero item type into a CSL type.
+fn csl_type(type_name: &str) -> &'static str {
+    match type_name {
+        "journalArticle" => "article-journal",
+        "magazineArticle" => "article-magazine",
+        "newspaperArticle" => "article-newspaper",
+        "book" => "book",
+        "bookSection" => "chapter",
+        "case" => "legal_case",
+        "statute" => "legislation",
+        "bill" => "bill",
+        "hearing" => "hearing",
+        "thesis" => "thesis",
+        "report" => "report",
+        "manuscript" => "manuscript",
+        "webpage" => "webpage",
+        "blogPost" => "post-weblog",
+        "conferencePaper" => "paper-conference",
+        "encyclopediaArticle" => "entry-encyclopedia",
+        "dictionaryEntry" => "entry-dictionary",
+        "letter" => "personal_communication",
+        "interview" => "interview",
+        "patent" => "patent",
+        "dataset" => "dataset",
+        _ => "document",
+    }
+}
+
+/// Converts a Zotero date into a CSL `date-variable`.
+///
+/// Zotero stores dates as "YYYY-MM-DD" followed by the date as entered, with
+/// zeros for unknown parts.
+fn date(value: &str) -> Option<Value> {
+    let parts: Vec<u32> = value
+        .get(..10)?
+        .split('-')
+        .map_while(|p| p.parse::<u32>().ok())
+        .take_while(|p| *p != 0)
+        .collect();
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(json!({ "date-parts": [parts] }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a small database with Zotero's schema.
+    fn test_database(path: &Path) {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE itemTypes (itemTypeID INTEGER PRIMARY KEY, typeName TEXT);
+            CREATE TABLE items (itemID INTEGER PRIMARY KEY, itemTypeID INT, key TEXT);
+            CREATE TABLE deletedItems (itemID INTEGER PRIMARY KEY);
+            CREATE TABLE fields (fieldID INTEGER PRIMARY KEY, fieldName TEXT);
+            CREATE TABLE itemDataValues (valueID INTEGER PRIMARY KEY, value);
+            CREATE TABLE itemData (itemID INT, fieldID INT, valueID INT);
+            CREATE TABLE creators (creatorID INTEGER PRIMARY KEY, firstName TEXT, lastName TEXT,
+                fieldMode INT);
+            CREATE TABLE creatorTypes (creatorTypeID INTEGER PRIMARY KEY, creatorType TEXT);
+            CREATE TABLE itemCreators (itemID INT, creatorID INT, creatorTypeID INT,
+                orderIndex INT);
+
+            INSERT INTO itemTypes VALUES (1, 'journalArticle'), (2, 'case'), (3, 'attachment');
+            INSERT INTO items VALUES (1, 1, 'AAAA'), (2, 2, 'BBBB'), (3, 3, 'CCCC'),
+                (4, 1, 'DDDD'), (5, 1, 'EEEE');
+            INSERT INTO deletedItems VALUES (4);
+            INSERT INTO fields VALUES (1, 'title'), (2, 'publicationTitle'), (3, 'volume'),
+                (4, 'pages'), (5, 'date'), (6, 'extra'), (7, 'caseName'), (8, 'court'),
+                (9, 'reporter'), (10, 'dateDecided'), (11, 'firstPage');
+            INSERT INTO itemDataValues VALUES
+                (1, 'The Right to Privacy'), (2, 'Harvard Law Review'), (3, 4),
+                (4, '193-220'), (5, '1890-12-15 December 15, 1890'),
+                (6, 'Citation Key: warrenRightPrivacy1890
+container-title-short: Harv. L. Rev.'), (7, 'Doe v. State'),
+                (8, '7th Cir.'), (9, 'F.4th'), (10, '2022-00-00 2022'), (11, '456'),
+                (12, 'Deleted');
+            INSERT INTO itemData VALUES (1, 1, 1), (1, 2, 2), (1, 3, 3), (1, 4, 4), (1, 5, 5),
+                (1, 6, 6), (2, 7, 7), (2, 8, 8), (2, 9, 9), (2, 10, 10), (2, 11, 11),
+                (4, 1, 12), (5, 1, 1);
+            INSERT INTO creators VALUES (1, 'Samuel D.', 'Warren', 0),
+                (2, 'Louis D.', 'Brandeis', 0);
+            INSERT INTO creatorTypes VALUES (1, 'author'), (2, 'contributor');
+            INSERT INTO itemCreators VALUES (1, 2, 1, 1), (1, 1, 1, 0), (1, 1, 2, 2);",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn zotero() {
+        let dir = std::env::temp_dir().join(format!("supra-zotero-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("zotero.sqlite");
+        fs::remove_file(&path).ok();
+        test_database(&path);
+
+        // Better BibTeX's database supplies the case's key.
+        let bbt = Connection::open(dir.join("better-bibtex.sqlite")).unwrap();
+        bbt.execute_batch(
+            "CREATE TABLE IF NOT EXISTS citationkey (itemKey TEXT, citationKey TEXT);
+            DELETE FROM citationkey;
+            INSERT INTO citationkey VALUES ('BBBB', 'DoeState2022');",
+        )
+        .unwrap();
+
+        let library: Value = serde_json::from_str(&load_zotero(&path).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        // The attachment, the trashed item, and the item without a key are left out.
+        let library = library.as_array().unwrap();
+        assert_eq!(library.len(), 2);
+
+        let article = &library[0];
+        assert_eq!(article["id"], "warrenRightPrivacy1890");
+        assert_eq!(article["type"], "article-journal");
+        assert_eq!(article["container-title"], "Harvard Law Review");
+        assert_eq!(article["volume"], "4");
+        assert_eq!(article["container-title-short"], "Harv. L. Rev.");
+        assert_eq!(article["issued"]["date-parts"], json!([[1890, 12, 15]]));
+        assert_eq!(article["author"][0]["family"], "Warren");
+        assert_eq!(article["author"][1]["family"], "Brandeis");
+        assert_eq!(article["author"].as_array().unwrap().len(), 2);
+
+        let case = &library[1];
+        assert_eq!(case["id"], "DoeState2022");
+        assert_eq!(case["type"], "legal_case");
+        assert_eq!(case["title"], "Doe v. State");
+        assert_eq!(case["authority"], "7th Cir.");
+        assert_eq!(case["page"], "456");
+        assert_eq!(case["issued"]["date-parts"], json!([[2022]]));
+    }
+
+    #[test]
+    fn copy_with_log() {
+        let dir = temp_dir().unwrap();
+        let path = dir.join("better-bibtex.sqlite");
+
+        // The row stays in the write-ahead log while the connection is open.
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+            PRAGMA wal_autocheckpoint = 0;
+            CREATE TABLE citationkey (itemKey TEXT, citationKey TEXT);
+            INSERT INTO citationkey VALUES ('BBBB', 'DoeState2022');",
+        )
+        .unwrap();
+
+        let copy_dir = temp_dir().unwrap();
+        assert_ne!(dir, copy_dir);
+        let copy = Connection::open(copy_database(&path, &copy_dir).unwrap()).unwrap();
+        let count: i64 = copy
+            .query_row("SELECT count(*) FROM citationkey", [], |row| row.get(0))
+            .unwrap();
+
+        drop(conn);
+        fs::remove_dir_all(&dir).ok();
+        fs::remove_dir_all(&copy_dir).ok();
+        assert_eq!(count, 1);
+    }
+}
@@ -6,11 +6,11 @@ mod pan;
 mod post;
 pub mod pre;
 
-use crate::config::Output;
+use crate::config::{LibraryFile, LibraryFormat, Output};
 use ansi_term::Color;
 use config::{SupraCommand, SupraConfig};
-use fs::load_file;
-use slog::{debug, error, o};
+use fs::{load_bbt, load_file, load_zotero};
+use slog::{debug, error, o, warn};
 use std::{path::Path, process};
 
 /// The main Supra function.
@@ -32,6 +32,10 @@ pub fn supra(config: SupraConfig) -> Result<(), String> {
             fs::replace_make();
             return Ok(());
         }
+        SupraCommand::Rekey(input, aliases) => {
+            debug!(slog_scope::logger(), "Rekeying {}", input);
+            return rekey(Path::new(input), Path::new(aliases));
+        }
         _ => {}
     }
 
@@ -39,7 +43,14 @@ pub fn supra(config: SupraConfig) -> Result<(), String> {
 
     // Create paths fort the input, library, etc.
     let input = Path::new(config.pre_config.as_ref().unwrap().input);
-    let library = Path::new(config.pre_config.as_ref().unwrap().library);
+    let libraries: Vec<&Path> = config
+        .pre_config
+        .as_ref()
+        .unwrap()
+        .libraries
+        .iter()
+        .map(Path::new)
+        .collect();
     let output = config.pan_config.as_ref().unwrap().output.map(Path::new);
     let pandoc_reference = config
         .pan_config
@@ -61,18 +72,111 @@ pub fn supra(config: SupraConfig) -> Result<(), String> {
             }
         };
 
-    // Load the CSL JSON library
-    let library =
-        match slog_scope::scope(&slog_scope::logger().new(o!("fn" => "load_file()")), || {
-            load_file(library)
-        }) {
-            Ok(l) => l,
+    // Load the aliases, if any. Better BibTeX needs them to find renamed keys.
+    let aliases = match config.pre_config.as_ref().unwrap().aliases {
+        Some(a) => {
+            match slog_scope::scope(&slog_scope::logger().new(o!("fn" => "load_file()")), || {
+                load_file(Path::new(&a))
+            }) {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    error!(slog_scope::logger(), "Alias file load error: {}", e);
+                    eprintln!("{} Alias file load error: {}", Color::Red.paint("ERRO"), e);
+                    process::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
+    // Ask a running Better BibTeX for the cited sources, if requested
+    let bbt_library = match config.pre_config.as_ref().unwrap().bbt_rpc {
+        Some(url) => {
+            match slog_scope::scope(&slog_scope::logger().new(o!("fn" => "load_bbt()")), || {
+                load_bbt(url, &input, &aliases)
+            }) {
+                Ok(l) => Some(l),
+                Err(e) => {
+                    let files = libraries
+                        .iter()
+                        .map(|l| l.to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    warn!(
+                        slog_scope::logger(),
+                        "Using {} instead of Better BibTeX: {}", files, e
+                    );
+                    eprintln!(
+                        "  {} Using {} instead of Better BibTeX: {}",
+                        Color::Yellow.paint("WARN"),
+                        Color::Blue.paint(files),
+                        e
+                    );
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    // Load the libraries, converting a Zotero database into CSL JSON. A Better
+    // BibTeX export takes the place of the first library, and the other files
+    // are still layered on top of it.
+    let mut files = Vec::with_capacity(libraries.len());
+    let layers = match bbt_library {
+        Some(l) => {
+            files.push(LibraryFile::new(
+                "Better BibTeX",
+                &l,
+                LibraryFormat::CslJson,
+            ));
+            libraries.get(1..).unwrap_or_default()
+        }
+        None => &libraries[..],
+    };
+    for library in layers {
+        // Determine the format from the extension. The option gives the format
+        // of files whose extension isn't one of these, so it doesn't change
+        // the other layers.
+        let library_format = match library.extension().and_then(|e| e.to_str()) {
+            Some("json") => LibraryFormat::CslJson,
+            Some("bib") => LibraryFormat::BibLatex,
+            Some("yaml" | "yml") => LibraryFormat::CslYaml,
+            Some("ris") => LibraryFormat::Ris,
+            Some("sqlite") => LibraryFormat::Zotero,
+            _ => config
+                .pre_config
+                .as_ref()
+                .unwrap()
+                .library_format
+                .unwrap_or(LibraryFormat::CslJson),
+        };
+
+        let library_result = if library_format == LibraryFormat::Zotero {
+            slog_scope::scope(
+                &slog_scope::logger().new(o!("fn" => "load_zotero()")),
+                || load_zotero(library),
+            )
+        } else {
+            slog_scope::scope(&slog_scope::logger().new(o!("fn" => "load_file()")), || {
+                load_file(library)
+            })
+        };
+
+        match library_result {
+            Ok(l) => files.push(LibraryFile::new(
+                &library.to_string_lossy(),
+                &l,
+                library_format,
+            )),
             Err(e) => {
-                error!(slog_scope::logger(), "CSL JSON load error: {}", e);
-                eprintln!("{} CSL JSON load error: {}", Color::Red.paint("ERRO"), e);
+                error!(slog_scope::logger(), "Library load error: {}", e);
+                eprintln!("{} Library load error: {}", Color::Red.paint("ERRO"), e);
                 process::exit(1);
             }
-        };
+        }
+    }
+    let libraries = files;
 
     // Load the user journals, if any
     let user_journals = match config.pre_config.as_ref().unwrap().user_journals {
@@ -95,16 +199,55 @@ pub fn supra(config: SupraConfig) -> Result<(), String> {
         None => None,
     };
 
+    // Load the house style, if any
+    let house_style = match config.pre_config.as_ref().unwrap().house_style {
+        Some(h) => {
+            match slog_scope::scope(&slog_scope::logger().new(o!("fn" => "load_file()")), || {
+                load_file(Path::new(&h))
+            }) {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    error!(slog_scope::logger(), "House style load error: {}", e);
+                    eprintln!("{} House style load error: {}", Color::Red.paint("ERRO"), e);
+                    process::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
+    // Load the CSL style, if any
+    let csl = match config.pre_config.as_ref().unwrap().csl {
+        Some(c) => {
+            match slog_scope::scope(&slog_scope::logger().new(o!("fn" => "load_file()")), || {
+                load_file(Path::new(&c))
+            }) {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    error!(slog_scope::logger(), "CSL style load error: {}", e);
+                    eprintln!("{} CSL style load error: {}", Color::Red.paint("ERRO"), e);
+                    process::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
     // Run the pre-processor
     eprintln!("{} Pre-processing...", Color::Green.paint("INFO"));
 
     let pre = match slog_scope::scope(&slog_scope::logger().new(o!("fn" => "pre()")), || {
         pre::pre(
             &input,
-            &library,
+            &libraries,
             &user_journals,
             config.pre_config.as_ref().unwrap().offset,
             config.pre_config.as_ref().unwrap().smallcaps,
+            config.pre_config.as_ref().unwrap().style,
+            config.pre_config.as_ref().unwrap().bibliography,
+            &csl,
+            &house_style,
+            &aliases,
         )
     }) {
         Ok(p) => p,
@@ -174,3 +317,40 @@ pub fn supra(config: SupraConfig) -> Result<(), String> {
     eprintln!("{} Done", Color::Green.paint("INFO"));
     Ok(())
 }
+
+/// Rewrite the aliased citation keys in a Markdown file.
+fn rekey(input: &Path, aliases: &Path) -> Result<(), String> {
+    let (markdown, aliases) = match (load_file(input), load_file(aliases)) {
+        (Ok(m), Ok(a)) => (m, a),
+        (Err(e), _) | (_, Err(e)) => {
+            error!(slog_scope::logger(), "Rekey load error: {}", e);
+            eprintln!("{} Rekey load error: {}", Color::Red.paint("ERRO"), e);
+            process::exit(1);
+        }
+    };
+
+    let (output, count) =
+        match slog_scope::scope(&slog_scope::logger().new(o!("fn" => "rekey()")), || {
+            pre::aliases::rekey(&markdown, &aliases)
+        }) {
+            Ok(r) => r,
+            Err(e) => {
+                error!(slog_scope::logger(), "Rekey error: {}", e);
+                eprintln!("{} Rekey error: {}", Color::Red.paint("ERRO"), e);
+                process::exit(1);
+            }
+        };
+
+    eprintln!(
+        "{} {} citation{} rekeyed",
+        Color::Green.paint("INFO"),
+        count,
+        if count == 1 { "" } else { "s" }
+    );
+
+    if count == 0 {
+        return Ok(());
+    }
+
+    fs::save_file(input, &output)
+}
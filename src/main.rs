@@ -9,7 +9,10 @@ use ansi_term::Color;
 use clap::{crate_version, value_parser, App, Arg, SubCommand};
 use slog::{debug, Drain, Level};
 use std::{fs::OpenOptions, process, sync::Mutex};
-use supra::config::{Output, PanConfig, PostConfig, PreConfig, SupraCommand, SupraConfig};
+use supra::config::{
+    LibraryFormat, Output, PanConfig, PostConfig, PreConfig, Style, SupraCommand, SupraConfig,
+    BBT_URL,
+};
 
 fn main() {
     // Get the command-line arguments and options
@@ -28,8 +31,9 @@ fn main() {
         .arg(
             Arg::with_name("library")
                 .value_name("LIBRARY FILE")
-                .help("The reference library file in CSL JSON format")
+                .help("The reference library file (CSL JSON, CSL YAML, BibLaTeX, RIS, or zotero.sqlite); separate several with commas")
                 .index(2)
+                .use_value_delimiter(true)
                 .required(true),
         )
         .arg(
@@ -70,6 +74,58 @@ fn main() {
                 .value_name("JOURNAL FILE")
                 .help("A RON file containing user-provided short-journal names"),
         )
+        .arg(
+            Arg::with_name("library_format")
+                .long("library_format")
+                .alias("library-format")
+                .value_name("FORMAT")
+                .help("The format of libraries with an unrecognized extension (defaults to CSL JSON)")
+                .possible_values(["csljson", "cslyaml", "biblatex", "ris", "zotero"]),
+        )
+        .arg(
+            Arg::with_name("bbt_rpc")
+                .long("bbt_rpc")
+                .value_name("URL")
+                .min_values(0)
+                .max_values(1)
+                .require_equals(true)
+                .default_missing_value(BBT_URL)
+                .help("Gets the cited sources from a running Better BibTeX, falling back to the library file"),
+        )
+        .arg(
+            Arg::with_name("aliases")
+                .long("aliases")
+                .value_name("ALIAS FILE")
+                .help("A RON file mapping old citation keys to new ones"),
+        )
+        .arg(
+            Arg::with_name("house_style")
+                .long("house_style")
+                .value_name("HOUSE STYLE FILE")
+                .help("A RON file containing a journal's house-style preferences"),
+        )
+        .arg(
+            Arg::with_name("style")
+                .long("style")
+                .value_name("STYLE")
+                .help("The citation style")
+                .possible_values(["bluebook", "bluepages", "oscola", "aglc"])
+                .default_value("bluebook"),
+        )
+        .arg(
+            Arg::with_name("csl")
+                .long("csl")
+                .value_name("CSL FILE")
+                .conflicts_with("style")
+                .help("A CSL style file for rendering citations (overrides --style)"),
+        )
+        .arg(
+            Arg::with_name("bibliography")
+                .short('b')
+                .long("bibliography")
+                .takes_value(false)
+                .help("Adds a bibliography of the cited sources (OSCOLA and CSL styles)"),
+        )
         .arg(
             Arg::with_name("force_overwrite")
                 .short('W')
@@ -161,6 +217,24 @@ fn main() {
         .subcommand(
             SubCommand::with_name("rmake").about("Replace the Makefile in the current directory"),
         )
+        .subcommand(
+            SubCommand::with_name("rekey")
+                .about("Replace old citation keys in a Markdown file with their aliases")
+                .arg(
+                    Arg::with_name("input")
+                        .value_name("INPUT FILE")
+                        .help("The Pandoc markdown file to rekey")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("aliases")
+                        .value_name("ALIAS FILE")
+                        .help("A RON file mapping old citation keys to new ones")
+                        .index(2)
+                        .required(true),
+                ),
+        )
         .get_matches();
 
     // Setup the logger.
@@ -251,6 +325,10 @@ fn main() {
             SupraCommand::NewProject(name, overwrite)
         }
         Some(("rmake", _)) => SupraCommand::ReplaceMake,
+        Some(("rekey", sub_matches)) => SupraCommand::Rekey(
+            sub_matches.value_of("input").unwrap(),
+            sub_matches.value_of("aliases").unwrap(),
+        ),
         _ => SupraCommand::Main,
     };
 
@@ -258,10 +336,11 @@ fn main() {
         SupraCommand::NewUserJournalFile => SupraConfig::new(command, None, None, None, None),
         SupraCommand::NewProject(_, _) => SupraConfig::new(command, None, None, None, None),
         SupraCommand::ReplaceMake => SupraConfig::new(command, None, None, None, None),
+        SupraCommand::Rekey(_, _) => SupraConfig::new(command, None, None, None, None),
         SupraCommand::Main => {
             // Files
             let input = matches.value_of("input").unwrap();
-            let library = matches.value_of("library").unwrap();
+            let libraries: Vec<&str> = matches.values_of("library").unwrap().collect();
             let output = matches.value_of("output");
             let pandoc_reference = matches.value_of("pandoc_reference");
 
@@ -272,7 +351,26 @@ fn main() {
             } else {
                 None
             };
+            let house_style = matches.value_of("house_style");
+            let bbt_rpc = matches.value_of("bbt_rpc");
+            let aliases = matches.value_of("aliases");
+            let library_format = matches.value_of("library_format").map(|f| match f {
+                "cslyaml" => LibraryFormat::CslYaml,
+                "biblatex" => LibraryFormat::BibLatex,
+                "ris" => LibraryFormat::Ris,
+                "zotero" => LibraryFormat::Zotero,
+                _ => LibraryFormat::CslJson,
+            });
             let smallcaps = matches.is_present("smallcaps");
+            let csl = matches.value_of("csl");
+            let style = match matches.value_of("style").unwrap() {
+                _ if csl.is_some() => Style::Csl,
+                "bluepages" => Style::Bluepages,
+                "oscola" => Style::Oscola,
+                "aglc" => Style::Aglc,
+                _ => Style::Bluebook,
+            };
+            let bibliography = matches.is_present("bibliography");
             let force_overwrite = matches.is_present("force_overwrite");
 
             // Post-processing options
@@ -311,7 +409,20 @@ fn main() {
             };
 
             // Create the configuration
-            let pre_config = PreConfig::new(input, library, offset, user_journals, smallcaps);
+            let pre_config = PreConfig::new(
+                input,
+                libraries,
+                offset,
+                user_journals,
+                smallcaps,
+                style,
+                bibliography,
+                csl,
+                house_style,
+                library_format,
+                bbt_rpc,
+                aliases,
+            );
             let pan_config = PanConfig::new(output, pandoc_reference);
             let post_config = PostConfig::new(
                 autocref,
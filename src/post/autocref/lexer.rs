@@ -1,11 +1,20 @@
 //! This module contains the lexer for breaking down the xml files. It
 //! identifies the chunks of potential bookmarks (for `document.xml`) and
-//! cross-references (for `footnotes.xml) as well as chunks containing
-//! everything else.
+//! cross-references (for both files) as well as chunks containing everything
+//! else.
 
-use regex::Regex;
+use lazy_static::lazy_static;
+use regex::{Match, Regex};
 use slog::{debug, o, trace};
 
+lazy_static! {
+    /// Regex for the numbers within a range of cross-references.
+    static ref RANGE: Regex = Regex::new(r#"([0-9]{1,9})(-|–)([0-9]{1,9})"#).unwrap();
+
+    /// Regex for an XML tag.
+    static ref TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+}
+
 /// The lexer that works through an input string.
 ///
 /// This keeps track of the starting index for each chunk.
@@ -45,8 +54,8 @@ impl Token<'_> {
 /// A `FootnoteRef` refers to a chunk containing the markup for a footnote
 /// reference in `document.xml`.
 ///
-/// A `CrossRef` refers to a chunk containing the number referencing another
-/// footnote in `footnotes.xml`.
+/// A `CrossRef` refers to a chunk containing the number referencing a footnote
+/// in either file.
 ///
 /// Everything else is `Other`.
 #[derive(Debug, PartialEq, Eq)]
@@ -86,11 +95,22 @@ pub fn lex<'a>(
     Ok((doc_lex, fn_lex))
 }
 
+/// Regex pattern for a footnote reference in `document.xml`.
+const FOOTNOTE_REF: &str = r#"(<w:r><w:rPr><w:rStyle w:val="FootnoteReference" /></w:rPr><w:footnoteReference w:id=")([0-9]{1,9})(" /></w:r>)"#;
+
+/// Regex pattern for a cross-reference.
+///
+/// The first group `((>note )([0-9]{1,9}))` captures references to single
+/// footnotes. It should have three total capture groups. The second group
+/// `((>notes )([0-9]{1,9})(-|–)([0-9]{1,9}))` captures references to a range
+/// of footnotes. It should have five total capture groups.
+const CROSS_REF: &str = r#"((>note )([0-9]{1,9}))|((>notes )([0-9]{1,9})(-|–)([0-9]{1,9}))"#;
+
 /// Lex the contents of document.xml.
 ///
-/// This function uses regex to identify the footnote references in
-/// `document.xml`. It then uses the index of those points to create tokens of
-/// the [`TokenType`] `FootnoteRef` or `Other`.
+/// This function uses regex to identify the footnote references and
+/// cross-references in `document.xml`. It then uses the index of those points
+/// to create tokens of the [`TokenType`] `FootnoteRef`, `CrossRef`, or `Other`.
 fn lex_doc(doc_input: &str) -> Result<Vec<Token>, String> {
     debug!(slog_scope::logger(), "Lexing document...");
 
@@ -99,10 +119,18 @@ fn lex_doc(doc_input: &str) -> Result<Vec<Token>, String> {
     let mut lex: Vec<Token> = Vec::new();
 
     // Use regex to identify each match
-    let re = Regex::new(
-        r#"(<w:r><w:rPr><w:rStyle w:val="FootnoteReference" /></w:rPr><w:footnoteReference w:id=")([0-9]{1,9})(" /></w:r>)"#
-    ).unwrap();
+    let re = Regex::new(&format!("{}|{}", FOOTNOTE_REF, CROSS_REF)).unwrap();
     for mat in re.find_iter(doc_input) {
+        // Cross-references in the body text are lexed just like those in the
+        // footnotes. Body text can say "note" for other reasons, so only a
+        // reference after a *supra*, *infra*, or *see* counts.
+        if !mat.as_str().starts_with("<w:r>") {
+            if follows_marker(doc_input, mat.start()) {
+                lex_cross_ref(doc_input, &mat, &mut lexer, &mut lex);
+            }
+            continue;
+        }
+
         // The file should always start with an other chunk. And this loop
         // always ends with a new other chunk. So each loop should start by
         // closing off an other chunk. This chunk runs from the starting index
@@ -155,8 +183,7 @@ fn lex_doc(doc_input: &str) -> Result<Vec<Token>, String> {
 ///
 /// This function lexes the `footnotes.xml` contents into `CrossRef` and `Other`
 /// tokens. It is probably a little brittle. It uses regex to find the
-/// cross-references and more regex when there is a range of numbers. It then
-/// relies on index offsets to identify the numbers.
+/// cross-references, which [`lex_cross_ref`] breaks into tokens.
 ///
 /// The file should always start with an "other" chunk. And the loop always ends
 /// with a new "other" chunk. So each loop should start by closing off an
@@ -167,118 +194,11 @@ fn lex_fn(input: &str) -> Result<Vec<Token>, String> {
     let mut lex: Vec<Token> = Vec::new();
 
     // Use regex to identify each match.
-    //
-    // The first group `((>note )([0-9]{1,9}))` captures references to single
-    // footnotes. It should have three total capture groups. The second group
-    // `((>notes )([0-9]{1,9})(-|–)([0-9]{1,9}))` captures references to a range
-    // of footnotes. It should have five total capture groups.
-    let re =
-        Regex::new(r#"((>note )([0-9]{1,9}))|((>notes )([0-9]{1,9})(-|–)([0-9]{1,9}))"#).unwrap();
-
-    // This regex finds numbers within a range.
-    let re_range = Regex::new(r#"([0-9]{1,9})(-|–)([0-9]{1,9})"#).unwrap();
+    let re = Regex::new(CROSS_REF).unwrap();
 
     // Iterate over the matches groups
     for mat in re.find_iter(input) {
-        // Determine whether the match is to a single footnote or a range. Note,
-        // a hyphen is in the first conditional, an en-dash (U+2013) is in the
-        // second.
-        if mat.as_str().contains('-') || mat.as_str().contains('–') {
-            // Push the precedeing "other" chunk, which goes from the lexer's
-            // current starting index to seven spaces after the beginning of the
-            // match.
-            trace!(
-                slog_scope::logger(),
-                "Pushing token type {:?} containing {:?}",
-                TokenType::Other,
-                &input[lexer.start..mat.start() + 7],
-            );
-            lex.push(Token::new(
-                TokenType::Other,
-                &input[lexer.start..mat.start() + 7],
-            ));
-
-            // Find the two numbers in the string.
-            let range = re_range.captures(mat.as_str()).unwrap();
-
-            // Then get their indexes. The first number starts at mat.start() +
-            // 7 and ends at mat.start() + 7 + the length of the number
-            let first_digit = (mat.start() + 7, mat.start() + 7 + range[1].len());
-            // Then the range indicator, which should be an en-dash. I also
-            // account for hyphens.
-            let dash = (first_digit.1, first_digit.1 + range[2].len());
-            // Then the second digit, which follows the range indicator and goes
-            // to the end of that capture.
-            let second_digit = (dash.1, dash.1 + range[3].len());
-
-            // Then push the first number, the range indicator, and the second
-            // number
-            trace!(
-                slog_scope::logger(),
-                "Pushing token type {:?} containing {}",
-                TokenType::CrossRef,
-                &input[first_digit.0..first_digit.1],
-            );
-            lex.push(Token::new(
-                TokenType::CrossRef,
-                &input[first_digit.0..first_digit.1],
-            ));
-
-            trace!(
-                slog_scope::logger(),
-                "Pushing token type {:?} containing {}",
-                TokenType::Other,
-                &input[dash.0..dash.1],
-            );
-            lex.push(Token::new(TokenType::Other, &input[dash.0..dash.1]));
-
-            trace!(
-                slog_scope::logger(),
-                "Pushing token type {:?} containing {}",
-                TokenType::CrossRef,
-                &input[second_digit.0..second_digit.1],
-            );
-            lex.push(Token::new(
-                TokenType::CrossRef,
-                &input[second_digit.0..second_digit.1],
-            ));
-
-            // Set the new starting index
-            lexer.start = mat.end();
-        } else {
-            // Push the precedeing "other" chunk, which goes from the lexer's
-            // current starting index to six spaces after the beginning of the
-            // match.
-            trace!(
-                slog_scope::logger(),
-                "Pushing token type {:?} containing {:?}",
-                TokenType::Other,
-                &input[lexer.start..mat.start() + 6],
-            );
-            lex.push(Token::new(
-                TokenType::Other,
-                &input[lexer.start..mat.start() + 6],
-            ));
-
-            // If there's no range of cross-references, then the "other" chunk
-            // is followed by either a cross-reference or the end of the string.
-            // Unless the "other" chunk ends the string, the next chunk is a
-            // cross reference. It consists only of the number and thus runs
-            // from six after the start of the match to the end of the match.
-            trace!(
-                slog_scope::logger(),
-                "Pushing token type {:?} containing {:?}",
-                TokenType::CrossRef,
-                &input[mat.start() + 6..mat.end()],
-            );
-            lex.push(Token::new(
-                TokenType::CrossRef,
-                &input[mat.start() + 6..mat.end()],
-            ));
-
-            // Set the new starting index
-            lexer.start = mat.end();
-        }
+        lex_cross_ref(input, &mat, &mut lexer, &mut lex);
     }
 
     // After the last cross-reference chunk is processed, there should still be
@@ -295,6 +215,133 @@ fn lex_fn(input: &str) -> Result<Vec<Token>, String> {
     Ok(lex)
 }
 
+/// Lex a cross-reference found by the [`CROSS_REF`] regex.
+///
+/// This pushes the "other" chunk before the cross-reference and then the
+/// cross-reference itself. It uses more regex when there is a range of
+/// numbers, and it relies on index offsets to identify the numbers.
+fn lex_cross_ref<'a>(input: &'a str, mat: &Match, lexer: &mut Lexer, lex: &mut Vec<Token<'a>>) {
+    // Determine whether the match is to a single footnote or a range. Note,
+    // a hyphen is in the first conditional, an en-dash (U+2013) is in the
+    // second.
+    if mat.as_str().contains('-') || mat.as_str().contains('–') {
+        // Push the precedeing "other" chunk, which goes from the lexer's
+        // current starting index to seven spaces after the beginning of the
+        // match.
+        trace!(
+            slog_scope::logger(),
+            "Pushing token type {:?} containing {:?}",
+            TokenType::Other,
+            &input[lexer.start..mat.start() + 7],
+        );
+        lex.push(Token::new(
+            TokenType::Other,
+            &input[lexer.start..mat.start() + 7],
+        ));
+
+        // Find the two numbers in the string.
+        let range = RANGE.captures(mat.as_str()).unwrap();
+
+        // Then get their indexes. The first number starts at mat.start() +
+        // 7 and ends at mat.start() + 7 + the length of the number
+        let first_digit = (mat.start() + 7, mat.start() + 7 + range[1].len());
+        // Then the range indicator, which should be an en-dash. I also
+        // account for hyphens.
+        let dash = (first_digit.1, first_digit.1 + range[2].len());
+        // Then the second digit, which follows the range indicator and goes
+        // to the end of that capture.
+        let second_digit = (dash.1, dash.1 + range[3].len());
+
+        // Then push the first number, the range indicator, and the second
+        // number
+        trace!(
+            slog_scope::logger(),
+            "Pushing token type {:?} containing {}",
+            TokenType::CrossRef,
+            &input[first_digit.0..first_digit.1],
+        );
+        lex.push(Token::new(
+            TokenType::CrossRef,
+            &input[first_digit.0..first_digit.1],
+        ));
+
+        trace!(
+            slog_scope::logger(),
+            "Pushing token type {:?} containing {}",
+            TokenType::Other,
+            &input[dash.0..dash.1],
+        );
+        lex.push(Token::new(TokenType::Other, &input[dash.0..dash.1]));
+
+        trace!(
+            slog_scope::logger(),
+            "Pushing token type {:?} containing {}",
+            TokenType::CrossRef,
+            &input[second_digit.0..second_digit.1],
+        );
+        lex.push(Token::new(
+            TokenType::CrossRef,
+            &input[second_digit.0..second_digit.1],
+        ));
+
+        // Set the new starting index
+        lexer.start = mat.end();
+    } else {
+        // Push the precedeing "other" chunk, which goes from the lexer's
+        // current starting index to six spaces after the beginning of the
+        // match.
+        trace!(
+            slog_scope::logger(),
+            "Pushing token type {:?} containing {:?}",
+            TokenType::Other,
+            &input[lexer.start..mat.start() + 6],
+        );
+        lex.push(Token::new(
+            TokenType::Other,
+            &input[lexer.start..mat.start() + 6],
+        ));
+
+        // If there's no range of cross-references, then the "other" chunk
+        // is followed by either a cross-reference or the end of the string.
+        // Unless the "other" chunk ends the string, the next chunk is a
+        // cross reference. It consists only of the number and thus runs
+        // from six after the start of the match to the end of the match.
+        trace!(
+            slog_scope::logger(),
+            "Pushing token type {:?} containing {:?}",
+            TokenType::CrossRef,
+            &input[mat.start() + 6..mat.end()],
+        );
+        lex.push(Token::new(
+            TokenType::CrossRef,
+            &input[mat.start() + 6..mat.end()],
+        ));
+
+        // Set the new starting index
+        lexer.start = mat.end();
+    }
+}
+
+/// Whether the text before `at` ends with *supra*, *infra*, or *see*.
+///
+/// Only the last few hundred bytes are checked, with the XML tags removed.
+fn follows_marker(input: &str, at: usize) -> bool {
+    let mut from = at.saturating_sub(400);
+    while !input.is_char_boundary(from) {
+        from -= 1;
+    }
+
+    // The match starts with the closing `>` of the tag before "note."
+    let text = TAG.replace_all(&input[from..=at], "");
+    text.split_whitespace()
+        .last()
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphabetic())
+                .to_lowercase()
+        })
+        .is_some_and(|word| ["supra", "infra", "see"].contains(&word.as_str()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -327,6 +374,45 @@ mod tests {
         assert_eq!(tokens[2].contents, r#".</w:t></w:r></w:p></w:footnote>"#);
     }
 
+    #[test]
+    fn doc_refs() {
+        let input = r#"<w:p><w:r><w:t xml:space="preserve">Text.</w:t></w:r><w:r><w:rPr><w:rStyle w:val="FootnoteReference" /></w:rPr><w:footnoteReference w:id="20" /></w:r><w:r><w:t xml:space="preserve"> </w:t></w:r><w:r><w:rPr><w:iCs /><w:i /></w:rPr><w:t xml:space="preserve">See infra</w:t></w:r><w:r><w:t xml:space="preserve"> </w:t></w:r><w:r><w:t xml:space="preserve">notes 2–3.</w:t></w:r></w:p>"#;
+
+        let tokens = lex_doc(input).unwrap();
+        let types: Vec<&TokenType> = tokens.iter().map(|t| &t.token_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                &TokenType::Other,
+                &TokenType::FootnoteRef,
+                &TokenType::Other,
+                &TokenType::CrossRef,
+                &TokenType::Other,
+                &TokenType::CrossRef,
+                &TokenType::Other,
+            ]
+        );
+
+        assert_eq!(
+            tokens[2].contents,
+            r#"<w:r><w:t xml:space="preserve"> </w:t></w:r><w:r><w:rPr><w:iCs /><w:i /></w:rPr><w:t xml:space="preserve">See infra</w:t></w:r><w:r><w:t xml:space="preserve"> </w:t></w:r><w:r><w:t xml:space="preserve">notes "#
+        );
+        assert_eq!(tokens[3].contents, "2");
+        assert_eq!(tokens[4].contents, "–");
+        assert_eq!(tokens[5].contents, "3");
+        assert_eq!(tokens[6].contents, r#".</w:t></w:r></w:p>"#);
+    }
+
+    #[test]
+    fn doc_note_without_marker() {
+        let input = r#"<w:p><w:r><w:t xml:space="preserve">Take</w:t></w:r><w:r><w:t xml:space="preserve"> </w:t></w:r><w:r><w:t xml:space="preserve">note 4 of this.</w:t></w:r></w:p>"#;
+
+        let tokens = lex_doc(input).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].token_type, TokenType::Other);
+        assert_eq!(tokens[0].contents, input);
+    }
+
     #[test]
     fn ref_range() {
         let input = r#"<w:footnote w:id="22"><w:p><w:pPr><w:pStyle w:val="FootnoteText" /></w:pPr><w:r>
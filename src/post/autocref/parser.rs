@@ -61,9 +61,9 @@ impl CrossRef {
 /// The complex type that the [`parser`] returns.
 ///
 /// The parser returns two trees—one for each `.xml` file—and a vector
-/// containing all of the footnotes that are referenced. This vector allows the
-/// program to add bookmark markup only to those footnote references that need
-/// it.
+/// containing all of the footnotes that are referenced (from either file). This
+/// vector allows the program to add bookmark markup only to those footnote
+/// references that need it.
 type ParseResults<'a> = (Vec<Branch<'a>>, Vec<Branch<'a>>, Vec<u32>);
 
 /// The main parser function.
@@ -73,7 +73,7 @@ pub fn parser<'a>(
 ) -> Result<ParseResults<'a>, String> {
     debug!(slog_scope::logger(), "Starting parser...");
 
-    let doc_branches =
+    let (doc_branches, doc_refd_fns) =
         match slog_scope::scope(&slog_scope::logger().new(o!("fn" => "parse_fr()")), || {
             parse_fr(doc_tokens)
         }) {
@@ -81,7 +81,7 @@ pub fn parser<'a>(
             Err(e) => return Err(e),
         };
 
-    let (fn_branches, refd_fns) =
+    let (fn_branches, mut refd_fns) =
         match slog_scope::scope(&slog_scope::logger().new(o!("fn" => "parse_cr()")), || {
             parse_cr(fn_tokens)
         }) {
@@ -89,6 +89,13 @@ pub fn parser<'a>(
             Err(e) => return Err(e),
         };
 
+    // Footnotes referred to only in the body text need bookmarks, too.
+    for number in doc_refd_fns {
+        if !refd_fns.contains(&number) {
+            refd_fns.push(number);
+        }
+    }
+
     debug!(slog_scope::logger(), "Parser finished.");
     Ok((doc_branches, fn_branches, refd_fns))
 }
@@ -97,14 +104,17 @@ pub fn parser<'a>(
 ///
 /// This function parses the tokens produced from the `document.xml` file.
 /// Tokens with the [`TokenType`] `Other` are simply pushed as is. Tokens with
-/// the [`TokenType`] `FootnoteRef` get a footnote number added, too.
+/// the [`TokenType`] `FootnoteRef` get a footnote number added, too. Tokens
+/// with the [`TokenType`] `CrossRef` are parsed like those in the footnotes
+/// (see [`parse_cr`]), and the footnotes they refer to are returned.
 ///
 /// Note, this function assumes that the starting footnote is 1. Use of Supra's
 /// offset functionality will break this.
-fn parse_fr<'a>(tokens: &'a [Token<'a>]) -> Result<Vec<Branch<'a>>, String> {
+fn parse_fr<'a>(tokens: &'a [Token<'a>]) -> Result<(Vec<Branch<'a>>, Vec<u32>), String> {
     debug!(slog_scope::logger(), "Starting document parser...");
 
     let mut parse: Vec<Branch> = Vec::new();
+    let mut referred_fns: Vec<u32> = Vec::new();
     let mut footnote_number = 1;
 
     for token in tokens {
@@ -134,12 +144,14 @@ fn parse_fr<'a>(tokens: &'a [Token<'a>]) -> Result<Vec<Branch<'a>>, String> {
                 // Increment the footnote number for the next footnote.
                 footnote_number += 1;
             }
-            _ => {}
+            TokenType::CrossRef => {
+                parse.push(parse_cross_ref(token, &mut referred_fns)?);
+            }
         }
     }
 
     debug!(slog_scope::logger(), "Document parser finished.");
-    Ok(parse)
+    Ok((parse, referred_fns))
 }
 
 /// Parse the cross-reference.
@@ -168,33 +180,7 @@ fn parse_cr<'a>(tokens: &'a [Token<'a>]) -> Result<(Vec<Branch<'a>>, Vec<u32>),
                 parse.push(Branch::Text(Text::new(token.contents)))
             }
             TokenType::CrossRef => {
-                // Determine the number referred to.
-                let footnote_number = match token.contents.parse::<u32>() {
-                    Ok(n) => n,
-                    Err(e) => {
-                        let err_msg = format!("Error parsing cross references: {}", e);
-                        return Err(err_msg);
-                    }
-                };
-
-                // Determine if that footnote has been referenced before. If it
-                // hasn't, add it to the list of referenced foototes.
-                if !referred_fns.contains(&footnote_number) {
-                    trace!(
-                        slog_scope::logger(),
-                        "Adding footnote {} to used cross-references",
-                        footnote_number
-                    );
-                    referred_fns.push(footnote_number);
-                }
-
-                // Push the new branch.
-                trace!(
-                    slog_scope::logger(),
-                    "Pushing branch type CrossRef for footnote {}",
-                    footnote_number,
-                );
-                parse.push(Branch::CrossRef(CrossRef::new(footnote_number)))
+                parse.push(parse_cross_ref(token, &mut referred_fns)?);
             }
             _ => {}
         }
@@ -203,3 +189,35 @@ fn parse_cr<'a>(tokens: &'a [Token<'a>]) -> Result<(Vec<Branch<'a>>, Vec<u32>),
     debug!(slog_scope::logger(), "Footnote parser finished.");
     Ok((parse, referred_fns))
 }
+
+/// Parse a cross-reference into a u32 number, adding it to the referenced
+/// footnotes if it isn't there yet.
+fn parse_cross_ref<'a>(token: &Token, referred_fns: &mut Vec<u32>) -> Result<Branch<'a>, String> {
+    // Determine the number referred to.
+    let footnote_number = match token.contents.parse::<u32>() {
+        Ok(n) => n,
+        Err(e) => {
+            let err_msg = format!("Error parsing cross references: {}", e);
+            return Err(err_msg);
+        }
+    };
+
+    // Determine if that footnote has been referenced before. If it hasn't, add
+    // it to the list of referenced foototes.
+    if !referred_fns.contains(&footnote_number) {
+        trace!(
+            slog_scope::logger(),
+            "Adding footnote {} to used cross-references",
+            footnote_number
+        );
+        referred_fns.push(footnote_number);
+    }
+
+    // Return the new branch.
+    trace!(
+        slog_scope::logger(),
+        "Pushing branch type CrossRef for footnote {}",
+        footnote_number,
+    );
+    Ok(Branch::CrossRef(CrossRef::new(footnote_number)))
+}
@@ -38,10 +38,11 @@ pub fn render(
 /// Render the `document.xml` contents.
 ///
 /// This function produces the new `document.xml` contents, with bookmark markup
-/// added to footnote references. It also builds a [`HashMap`] in which the keys
-/// are footnote numbers and the values are the Word reference ids (*e.g.*,
-/// "_Ref000000001"). This [`HashMap`] is later used for the cross-reference
-/// markup.
+/// added to footnote references and field markup added to cross-references in
+/// the body text (see [`render_fn`]). It also builds a [`HashMap`] in which the
+/// keys are footnote numbers and the values are the Word reference ids
+/// (*e.g.*, "_Ref000000001"). This [`HashMap`] is later used for the
+/// cross-reference markup.
 ///
 /// **The Markup for Bookmarks**
 ///
@@ -74,6 +75,13 @@ fn render_doc(
     // each cross-referenced footnote
     let mut ref_ids: HashMap<u32, String> = HashMap::new();
 
+    // A cross-reference in the body text can come before the footnote it
+    // refers to, so it can only be checked against the number of footnotes.
+    let footnotes = tree
+        .iter()
+        .filter(|b| matches!(b, Branch::FootnoteRef(_)))
+        .count() as u32;
+
     for branch in tree {
         match branch {
             Branch::Text(text) => doc_output.push_str(text.contents),
@@ -104,7 +112,15 @@ fn render_doc(
                     doc_output.push_str(footnote_ref.contents);
                 }
             }
-            _ => {}
+            Branch::CrossRef(cross_ref) => {
+                // A reference to a footnote that doesn't exist is left as it
+                // is.
+                if (1..=footnotes).contains(&cross_ref.number) {
+                    doc_output.push_str(&field(&create_ref_id(cross_ref.number), cross_ref.number));
+                } else {
+                    doc_output.push_str(&cross_ref.number.to_string());
+                }
+            }
         }
     }
 
@@ -139,11 +155,7 @@ fn render_fn(tree: &[Branch], ref_ids: HashMap<u32, String>) -> Result<String, S
             Branch::Text(text) => fn_output.push_str(text.contents),
             Branch::CrossRef(cross_ref) => {
                 // Add the cross-reference field markup.
-                fn_output.push_str(&format!(
-                    r#"</w:t></w:r><w:fldSimple w:instr=" NOTEREF {} "><w:r><w:t>{}</w:t></w:r></w:fldSimple><w:r><w:t xml:space="preserve">"#,
-                    ref_ids[&cross_ref.number],
-                    cross_ref.number
-                ));
+                fn_output.push_str(&field(&ref_ids[&cross_ref.number], cross_ref.number));
             }
             _ => {}
         }
@@ -153,6 +165,14 @@ fn render_fn(tree: &[Branch], ref_ids: HashMap<u32, String>) -> Result<String, S
     Ok(fn_output)
 }
 
+/// Create the field markup for a cross-reference to a footnote.
+fn field(ref_id: &str, number: u32) -> String {
+    format!(
+        r#"</w:t></w:r><w:fldSimple w:instr=" NOTEREF {} "><w:r><w:t>{}</w:t></w:r></w:fldSimple><w:r><w:t xml:space="preserve">"#,
+        ref_id, number
+    )
+}
+
 /// Create a unique reference id.
 ///
 /// This function creates a unique reference id for a footnote reference. It
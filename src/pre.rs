@@ -1,34 +1,53 @@
 //! The Supra pre-processor.
 
+pub mod aliases;
+mod bibliography;
 mod crossref;
 mod csljson;
+mod cslstyle;
+mod error;
+pub mod housestyle;
 mod lexer;
 mod options;
 mod parser;
+mod pincite;
 mod render;
+mod signal;
 mod sourcemap;
 pub mod userjournals;
 
-use slog::{debug, o};
+use crate::config::{LibraryFile, LibraryFormat, Style};
+use aliases::build_aliases;
+use ansi_term::Color;
+use csljson::Library;
+use housestyle::{build_house_style, HouseStyle};
+use slog::{debug, o, warn};
+use std::collections::HashMap;
 use userjournals::build_user_journals;
 
 /// The pre-processor function.
+#[allow(clippy::too_many_arguments)]
 pub fn pre<'a>(
     input: &'a str,
-    library: &'a str,
+    libraries: &[LibraryFile],
     user_journals: &Option<String>,
     offset: i32,
     smallcaps: bool,
+    style: Style,
+    bibliography: bool,
+    csl_style: &Option<String>,
+    house_style: &Option<String>,
+    aliases: &Option<String>,
 ) -> Result<String, String> {
     debug!(slog_scope::logger(), "Starting pre-processor...");
 
-    // Deserialize the CSL library file.
-    let csl_library = match slog_scope::scope(
-        &slog_scope::logger().new(o!("fn" => "build_csl_lib()")),
-        || csljson::build_csl_lib(library),
-    ) {
-        Ok(l) => l,
-        Err(e) => return Err(e),
+    // If there's an alias file, deserialize it.
+    let aliases = match aliases {
+        Some(a) => Some(slog_scope::scope(
+            &slog_scope::logger().new(o!("fn" => "build_aliases()")),
+            || build_aliases(a),
+        )?),
+        None => None,
     };
 
     // Lex the markdown input
@@ -36,16 +55,142 @@ pub fn pre<'a>(
         lexer::lexer(input)
     }) {
         Ok(t) => t,
-        Err(e) => return Err(e),
+        Err(e) => return Err(error::describe_all(&e, input)),
     };
 
     // Parse the tokens into the syntax tree
-    let tree = match slog_scope::scope(&slog_scope::logger().new(o!("fn" => "parser()")), || {
-        parser::parser(&tokens, offset)
-    }) {
-        Ok(t) => t,
-        Err(e) => return Err(e),
-    };
+    let mut tree =
+        match slog_scope::scope(&slog_scope::logger().new(o!("fn" => "parser()")), || {
+            parser::parser(&tokens, offset)
+        }) {
+            Ok(t) => t,
+            Err(e) => return Err(error::describe_all(&e, input)),
+        };
+
+    // Check the order and punctuation of the signals against the Bluebook.
+    if style.is_bluebook() {
+        slog_scope::scope(
+            &slog_scope::logger().new(o!("fn" => "check_signals()")),
+            || signal::check_signals(&tree, input),
+        );
+    }
+
+    // Collect the cited keys, along with the keys they're aliases for, so that
+    // large libraries only need to deserialize those sources.
+    let mut cited_keys = parser::cited_keys(&tree);
+    if let Some(a) = &aliases {
+        let resolved: Vec<&str> = cited_keys.iter().map(|k| aliases::resolve(a, k)).collect();
+        cited_keys.extend(resolved);
+    }
+
+    // Deserialize the library files, layering each on top of the last.
+    let mut csl_library = Library::default();
+    for file in libraries {
+        let layer = match file.format {
+            LibraryFormat::CslJson | LibraryFormat::Zotero
+                if file.contents.len() >= csljson::FILTER_THRESHOLD =>
+            {
+                slog_scope::scope(
+                    &slog_scope::logger().new(o!("fn" => "build_cited_csl_lib()")),
+                    || csljson::build_cited_csl_lib(&file.contents, &cited_keys),
+                )
+            }
+            LibraryFormat::CslJson | LibraryFormat::Zotero => slog_scope::scope(
+                &slog_scope::logger().new(o!("fn" => "build_csl_lib()")),
+                || csljson::build_csl_lib(&file.contents),
+            ),
+            LibraryFormat::BibLatex => slog_scope::scope(
+                &slog_scope::logger().new(o!("fn" => "build_bib_lib()")),
+                || csljson::build_bib_lib(&file.contents),
+            ),
+            LibraryFormat::CslYaml => slog_scope::scope(
+                &slog_scope::logger().new(o!("fn" => "build_yaml_lib()")),
+                || csljson::build_yaml_lib(&file.contents),
+            ),
+            LibraryFormat::Ris => slog_scope::scope(
+                &slog_scope::logger().new(o!("fn" => "build_ris_lib()")),
+                || csljson::build_ris_lib(&file.contents),
+            ),
+        };
+
+        match layer {
+            Ok(l) => csl_library.add_layer(l, &file.name),
+            Err(e) if libraries.len() > 1 => return Err(format!("{}: {}", file.name, e)),
+            Err(e) => return Err(e),
+        }
+    }
+
+    if let Some(a) = aliases {
+        csl_library.set_aliases(a);
+    }
+
+    // Body-text keys that aren't in the library are left as they are.
+    parser::unknown_narratives_to_text(&mut tree, |key| csl_library.get(key).is_some());
+
+    // A hereinafter given in a citation works like one in the source's note.
+    // The first one given for a source is used.
+    let mut given: HashMap<String, &str> = HashMap::new();
+    for (key, hereinafter) in parser::hereinafters(&tree) {
+        let id = csl_library.resolve(key).to_string();
+        match given.get(&id) {
+            Some(first) if *first != hereinafter => {
+                warn!(
+                    slog_scope::logger(),
+                    "{} is given two hereinafters; using \"{}\"", key, first
+                );
+                eprintln!(
+                    "  {} {} is given two hereinafters; using \"{}\"",
+                    Color::Yellow.paint("WARN"),
+                    key,
+                    first
+                );
+            }
+            Some(_) => {}
+            None => {
+                if let Some(source) = csl_library.get_mut(&id) {
+                    source.supra.hereinafter = Some(hereinafter.to_string());
+                }
+                given.insert(id, hereinafter);
+            }
+        }
+    }
+
+    // A CSL style renders everything itself, so the source map isn't needed.
+    if style == Style::Csl {
+        let csl_style = match csl_style {
+            Some(c) => c,
+            None => return Err("The CSL style requires a CSL style file".to_string()),
+        };
+
+        let crossref_map = slog_scope::scope(
+            &slog_scope::logger().new(o!("fn" => "build_crossref_map()")),
+            || crossref::build_crossref_map(&tree),
+        );
+
+        let mut output = slog_scope::scope(
+            &slog_scope::logger().new(o!("fn" => "render_csl()")),
+            || {
+                cslstyle::render_csl(
+                    &tree,
+                    libraries,
+                    &csl_library,
+                    csl_style,
+                    &crossref_map,
+                    bibliography,
+                )
+            },
+        )?;
+
+        if smallcaps {
+            output =
+                slog_scope::scope(&slog_scope::logger().new(o!("fn" => "smallcaps()")), || {
+                    options::smallcaps(&output)
+                });
+        }
+
+        debug!(slog_scope::logger(), "Pre-processing completed.");
+        return Ok(output);
+    }
 
     // If there's a user journals list, deserialize it into the user journals
     // map.
@@ -61,10 +206,20 @@ pub fn pre<'a>(
         None => None,
     };
 
+    // If there's a house-style file, deserialize it. Otherwise, follow the
+    // Bluebook.
+    let house_style = match house_style {
+        Some(s) => slog_scope::scope(
+            &slog_scope::logger().new(o!("fn" => "build_house_style()")),
+            || build_house_style(s),
+        )?,
+        None => HouseStyle::default(),
+    };
+
     // Build the source_map
     let mut source_map = slog_scope::scope(
         &slog_scope::logger().new(o!("fn" => "build_source_map()")),
-        || sourcemap::build_source_map(&tree, &csl_library, &user_journals),
+        || sourcemap::build_source_map(&tree, &csl_library, &user_journals, style, &house_style),
     );
 
     // Create the crossref_map
@@ -75,9 +230,24 @@ pub fn pre<'a>(
 
     // Render the output
     let mut output = slog_scope::scope(&slog_scope::logger().new(o!("fn" => "render()")), || {
-        render::render(&tree, &mut source_map, &crossref_map)
+        render::render(
+            &tree,
+            input,
+            &mut source_map,
+            &csl_library,
+            &crossref_map,
+            style,
+        )
     });
 
+    // Optionally add a bibliography
+    if bibliography {
+        output.push_str(&slog_scope::scope(
+            &slog_scope::logger().new(o!("fn" => "bibliography()")),
+            || bibliography::bibliography(&source_map, style),
+        ));
+    }
+
     // Optionally add True Small Caps
     if smallcaps {
         output = slog_scope::scope(&slog_scope::logger().new(o!("fn" => "smallcaps()")), || {
@@ -89,6 +259,19 @@ pub fn pre<'a>(
     Ok(output)
 }
 
+/// Collect the keys of every source cited in the input, in sorted order.
+///
+/// The input is lexed and parsed as it is for [`pre`], so the keys are exactly
+/// the ones it will look up.
+pub fn cited_keys(input: &str) -> Result<Vec<&str>, String> {
+    let tokens = lexer::lexer(input).map_err(|e| error::describe_all(&e, input))?;
+    let tree = parser::parser(&tokens, 0).map_err(|e| error::describe_all(&e, input))?;
+
+    let mut keys: Vec<&str> = parser::cited_keys(&tree).into_iter().collect();
+    keys.sort_unstable();
+    Ok(keys)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,7 +305,23 @@ mod tests {
                 5. Multi-volume book.^[10 **Book Nauthor**, **Multi-Volume Book: This Book Is One of Several Volumes** (2021).]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -148,7 +347,23 @@ mod tests {
                 2. Chapter w/ pincite.^[Chapter Author, *Another Book Chapter Title: The Chapter of a Book*, *in* 15 **The Title of the Chapter Book** 101, 101 (5th ed., Book Editor ed., 2021) [hereinafter Author, *Another Book Chapter Title*].]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -186,7 +401,23 @@ mod tests {
                 5. Year-as-volume article.^[Article Cauthor, *Journal Article With a Year Volume: This Journal Uses Years as Volumes*, 2021 **The Other J. J. Articles** 501.]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -216,7 +447,23 @@ mod tests {
                 3. Not-yet-forthcoming manuscript w/ pincite.^[Manuscipt Author, *Another Not Yet Forthcoming Manuscript: This Manuscript Is Not Yet Placed* (forthcoming 2021) (manuscript at 1) [hereinafter Author, *Another Not Yet*].]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -242,7 +489,23 @@ mod tests {
                 2. Case w/ pincite.^[Plaintiff B v. Defendant B, 2 F.3d 200, 201 (2d Cir. 1992).]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -289,7 +552,23 @@ mod tests {
                 8. Case D w/ pin (short form).^[*Plaintiff F*, 600 F.3d at 6.]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -340,7 +619,23 @@ mod tests {
                 9. Case B (short form).^[*Plaintiff I*, 9 F.3d 9.]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -406,7 +701,23 @@ mod tests {
                 12. Manuscript supra w/ pincite.^[Kauthor, *supra* note 4, at 1.]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -441,7 +752,23 @@ mod tests {
                 4. Article A (*Id.*).^[*Id.*]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -468,7 +795,23 @@ mod tests {
                 3. Case B w/ same pin (*Id.*).^[*Id.*]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -487,7 +830,23 @@ mod tests {
                 1. Case C twice.^[Text. Plaintiff A v. Defendant A, 100 F.3d 1, 10 (1st Cir. 1991). Text. *Id.* at 12.]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -507,7 +866,23 @@ mod tests {
                 1. Case C twice.^[Text. Plaintiff A v. Defendant A, 100 F.3d 1, 10 (1st Cir. 1991); *id.* at 12.]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -534,7 +909,23 @@ mod tests {
                 3. Article B.^[Dauthor & Fauthor, *supra* note 2, at 111.]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -576,7 +967,23 @@ mod tests {
 
                 7. Question mark.^[Question mark? *Id.* at 1007.]
                 "#######;
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -596,7 +1003,23 @@ mod tests {
                 1. This footnote has a string cite.^[*See, e.g.*, Plaintiff H v. Defendant H, 888 F.3d 8, 12 (8th Cir. 1998); Article Dauthor, Jr. & Article III Fauthor, *Two-Author Journal Article: This Article Has Two Authors*, 51 **J. J. Articles** 101, 110 (2021).]
                 2. This footnote should have a short cite, since the source was previously cited in a string.^[*Cf.* Dauthor & Fauthor, *supra* note 1, at 112.]
                 "#######;
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -622,7 +1045,23 @@ mod tests {
                 3. Case A.^[*Plaintiff H*, 888 F.3d 8.]
                "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -711,76 +1150,92 @@ mod tests {
             let output = r#######"
                 1. No signal.^[Plaintiff J v. Defendant J, 10 F.3d 1000, 1001 (10th Cir. 2000).]
 
-                2. `*E.g.*` signal.^[*E.g.* *id.* at 1002.]
-                3. `*e.g.*` signal.^[*Id.* at 1003; *e.g.* *id.* at 1004.]
-                4. 'e.g.' signal.^[Lead in, e.g. *id.* at 1005.]
+                2. `*E.g.*` signal.^[*E.g.*, *id.* at 1002.]
+                3. `*e.g.*` signal.^[*Id.* at 1003; *e.g.*, *id.* at 1004.]
+                4. 'e.g.' signal.^[Lead in, *e.g.*, *id.* at 1005.]
 
                 5. `*Accord*` signal.^[*Accord* *id.* at 1002.]
                 6. `*accord*` signal.^[*Id.* at 1003; *accord* *id.* at 1004.]
-                7. `accord` signal.^[Lead in, accord *id.* at 1005.]
+                7. `accord` signal.^[Lead in, *accord* *id.* at 1005.]
 
                 8. `*See*` signal.^[*See* *id.* at 1002.]
                 9. `*see*` signal.^[*Id.* at 1003; *see* *id.* at 1004.]
-                10. `see` signal.^[Lead in, see *id.* at 1005.]
+                10. `see` signal.^[Lead in, *see* *id.* at 1005.]
 
                 11. `*See, e.g.*,` signal.^[*See, e.g.*, *id.* at 1002.]
                 12. `*see, e.g.*,` signal.^[*Id.* at 1003; *see, e.g.*, *id.* at 1004.]
-                13. `see, e.g.,` signal.^[Lead in, see, e.g., *id.* at 1005.]
+                13. `see, e.g.,` signal.^[Lead in, *see, e.g.*, *id.* at 1005.]
 
                 14. `*See also*` signal.^[*See also* *id.* at 1002.]
                 15. `*see also*` signal.^[*Id.* at 1003; *see also* *id.* at 1004.]
-                16. `see also` signal.^[Lead in, see also *id.* at 1005.]
+                16. `see also` signal.^[Lead in, *see also* *id.* at 1005.]
 
                 17. `*See also, e.g.*,` signal.^[*See also, e.g.*, *id.* at 1002.]
                 18. `*see also, e.g.*,` signal.^[*Id.* at 1003; *see also, e.g.*, *id.* at 1004.]
-                19. `see also, e.g.,` signal.^[Lead in, see also, e.g., *id.* at 1005.]
+                19. `see also, e.g.,` signal.^[Lead in, *see also, e.g.*, *id.* at 1005.]
 
                 20. `*Cf.*` signal.^[*Cf.* *id.* at 1002.]
                 21. `*cf.*` signal.^[*Id.* at 1003; *cf.* *id.* at 1004.]
-                22. `cf.` signal.^[Lead in, cf. *id.* at 1005.]
+                22. `cf.` signal.^[Lead in, *cf.* *id.* at 1005.]
 
                 23. `*Cf., e.g.*,` signal.^[*Cf., e.g.*, *id.* at 1002.]
                 24. `*cf., e.g.*,` signal.^[*Id.* at 1003; *cf., e.g.*, *id.* at 1004.]
-                25. `cf., e.g.,` signal.^[Lead in, cf., e.g., *id.* at 1005.]
+                25. `cf., e.g.,` signal.^[Lead in, *cf., e.g.*, *id.* at 1005.]
 
                 26. `*Compare*` signal.^[*Compare* *id.* at 1002, *with* *id.*]
                 27. `*compare*` signal.^[*Id.* at 1003; *compare* *id.* at 1004.]
-                28. `compare` signal.^[Lead in, compare *id.* at 1005.]
+                28. `compare` signal.^[Lead in, *compare* *id.* at 1005.]
 
                 29. `*Contra*` signal.^[*Contra* *id.* at 1002.]
                 30. `*contra*` signal.^[*Id.* at 1003; *contra* *id.* at 1004.]
-                31. `contra` signal.^[Lead in, contra *id.* at 1005.]
+                31. `contra` signal.^[Lead in, *contra* *id.* at 1005.]
 
                 32. `*But see*` signal.^[*But see* *id.* at 1002.]
                 33. `*but see*` signal.^[*Id.* at 1003; *but see* *id.* at 1004.]
-                34. `but see` signal.^[Lead in, but see *id.* at 1005.]
+                34. `but see` signal.^[Lead in, *but see* *id.* at 1005.]
 
                 35. `*But see, e.g.*,` signal.^[*But see, e.g.*, *id.* at 1002.]
                 36. `*but see, e.g.*,` signal.^[*Id.* at 1003; *but see, e.g.*, *id.* at 1004.]
-                37. `but see` signal.^[Lead in, but see *id.* at 1005.]
+                37. `but see` signal.^[Lead in, *but see* *id.* at 1005.]
 
                 38. `*But cf.*` signal.^[*But cf.* *id.* at 1002.]
                 39. `*but cf.*` signal.^[*Id.* at 1003; *but cf.* *id.* at 1004.]
-                40. `but cf.` signal.^[Lead in, but cf. *id.* at 1005.]
+                40. `but cf.` signal.^[Lead in, *but cf.* *id.* at 1005.]
 
                 41. `*But cf., e.g.*,` signal.^[*But cf., e.g.*, *id.* at 1002.]
                 42. `*but cf., e.g.*,` signal.^[*Id.* at 1003; *but cf., e.g.*, *id.* at 1004.]
-                43. `but cf., e.g.,` signal.^[Lead in, but cf., e.g., *id.* at 1005.]
+                43. `but cf., e.g.,` signal.^[Lead in, *but cf., e.g.*, *id.* at 1005.]
 
                 44. `*See generally*` signal.^[*See generally* *id.* at 1002.]
                 45. `*see generally*` signal.^[*Id.* at 1003; *see generally* *id.* at 1004.]
-                46. `see generally` signal.^[Lead in, see generally *id.* at 1005.]
+                46. `see generally` signal.^[Lead in, *see generally* *id.* at 1005.]
 
                 47. `*See generally, e.g.*,` signal.^[*See generally, e.g.*, *id.* at 1002.]
                 48. `*see generally, e.g.*,` signal.^[*Id.* at 1003; *see generally, e.g.*, *id.* at 1004.]
-                49. `see generally, e.g.,` signal.^[Lead in, see generally, e.g., *id.* at 1005.]
+                49. `see generally, e.g.,` signal.^[Lead in, *see generally, e.g.*, *id.* at 1005.]
 
                 50. Signal without a cite.^[See the discussion in Part II.]
 
                 51. `*See*` signal.^[*See* *id.* at 1002.]
                 "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -789,6 +1244,131 @@ mod tests {
         }
     }
 
+    mod signal_normalization {
+        use super::*;
+
+        #[test]
+        fn signal_normalization() {
+            let input = r#######"
+                1. Sentence start.^[see also [@PlaintiffDefendant2000] at 1001.]
+                2. After text.^[A sentence. See [@PlaintiffDefendant1991] at 5.]
+                3. Clause.^[[@PlaintiffDefendant2000] at 1002; *See* [@PlaintiffDefendant1991] at 6.]
+                4. Commas.^[See e.g. [@PlaintiffDefendant2000] at 1003.]
+                5. Not a signal.^[A statement consistent with [@PlaintiffDefendant1991].]
+                6. Out of order.^[*But see* [@PlaintiffDefendant2000] at 1004. *See* [@PlaintiffDefendant1991] at 7.]
+                7. Same sentence.^[*See* [@PlaintiffDefendant2000] at 1005; *but see* [@PlaintiffDefendant1991] at 8.]
+                8. E.g.^[*E.g.*, [@PlaintiffDefendant2000] at 1006. e.g. [@PlaintiffDefendant1991] at 9.]
+                "#######;
+
+            let output = r#######"
+                1. Sentence start.^[*See also* Plaintiff J v. Defendant J, 10 F.3d 1000, 1001 (10th Cir. 2000).]
+                2. After text.^[A sentence. *See* Plaintiff A v. Defendant A, 100 F.3d 1, 5 (1st Cir. 1991).]
+                3. Clause.^[*Plaintiff J*, 10 F.3d at 1002; *see* *Plaintiff A*, 100 F.3d at 6.]
+                4. Commas.^[*See, e.g.*, *Plaintiff J*, 10 F.3d at 1003.]
+                5. Not a signal.^[A statement consistent with *Plaintiff A*, 100 F.3d 1.]
+                6. Out of order.^[*But see* *Plaintiff J*, 10 F.3d at 1004. *See* *Plaintiff A*, 100 F.3d at 7.]
+                7. Same sentence.^[*See* *Plaintiff J*, 10 F.3d at 1005; *but see* *Plaintiff A*, 100 F.3d at 8.]
+                8. E.g.^[*E.g.*, *Plaintiff J*, 10 F.3d at 1006. *E.g.*, *Plaintiff A*, 100 F.3d at 9.]
+                "#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+
+            assert_eq!(pre, output);
+        }
+    }
+
+    mod input_errors {
+        use super::*;
+
+        fn pre_bluebook(input: &str) -> Result<String, String> {
+            pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+        }
+
+        #[test]
+        fn every_error() {
+            let input =
+                "Line one.^[[@PlaintiffDefendant2000] at 5]\nLine two.^[Unclosed [?id note.]";
+
+            assert_eq!(
+                pre_bluebook(input).unwrap_err(),
+                "2 problems found in the input:\n  line 1, column 12: This citation has no ending punctuation (or its brackets or parentheses don't match)\n  line 2, column 10: This footnote is never closed; make sure its brackets match"
+            );
+        }
+
+        #[test]
+        fn unbalanced_brace() {
+            let input = "Text.\n\nMore.^[[@PlaintiffDefendant2000] (quoting [@PlaintiffDefendant1991]{.long).]";
+
+            assert_eq!(
+                pre_bluebook(input).unwrap_err(),
+                "line 3, column 8: This citation has no ending punctuation (or its brackets or parentheses don't match)"
+            );
+        }
+
+        #[test]
+        fn no_panics() {
+            assert_eq!(pre_bluebook("").unwrap(), "");
+            assert_eq!(pre_bluebook("é").unwrap(), "é");
+            assert_eq!(pre_bluebook("§@x [-@").unwrap(), "§@x [-@");
+            assert_eq!(
+                pre_bluebook("Text.^[*See* [@nokey] at 5.]").unwrap(),
+                "Text.^[*See* [@nokey] at 5.]"
+            );
+        }
+
+        #[test]
+        fn unknown_as_written() {
+            // A citation, or a group of citations, is left exactly as it is.
+            for input in [
+                "Text.^[*See* [@nokey] at 5-7, 12 (discussing it).]",
+                "Text.^[[see @nokey, pp. 5-7; @other at 3].]",
+            ] {
+                assert_eq!(pre_bluebook(input).unwrap(), input);
+            }
+
+            // In a group with a known source, the group's brackets go.
+            assert_eq!(
+                pre_bluebook("Text.^[[see @nokey at 5; @authorBookTitleTitle2021, 3].]").unwrap(),
+                "Text.^[see @nokey at 5; **Book Author**, **Book Title: A Title for the Dummy Book** 3 (4th ed. 2021).]"
+            );
+            assert_eq!(
+                pre_bluebook("Text.^[[see @authorBookTitleTitle2021, 3; @nokey at 5].]").unwrap(),
+                "Text.^[*See* **Book Author**, **Book Title: A Title for the Dummy Book** 3 (4th ed. 2021); @nokey at 5.]"
+            );
+        }
+    }
+
     mod cross_refs {
         use super::*;
 
@@ -812,7 +1392,23 @@ mod tests {
                 5. And this sentence refers to the middle three.^[*Contra* *supra* notes 2--4.]
                "#######;
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
@@ -820,23 +1416,1013 @@ mod tests {
             }
         }
 
+        #[test]
+        fn body_text() {
+            let input = "As *infra* note [?remedies] explains, the question is open.^[[?first] A note.]\nMore.^[[?remedies] *See* *supra* note [?first].]\nAnd [?missing].";
+            let output = "As *infra* note 2 explains, the question is open.^[A note.]\nMore.^[*See* *supra* note 1.]\nAnd [?missing].";
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+
+            assert_eq!(pre, output);
+        }
+
         #[test]
         fn ross_refs_error() {
             let input = "Invalid cross-reference.^[*See* *infra* note [?non_existent] and accompanying text.]";
 
             let output = "Invalid cross-reference.^[*See* *infra* note [?non_existent] and accompanying text.]";
 
-            let pre = pre(input, test_inputs::TESTJSON, &None, 0, false).unwrap();
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+            let target_lines: Vec<&str> = output.lines().collect();
+
+            for (i, line) in pre.lines().enumerate() {
+                assert_eq!(line, target_lines[i])
+            }
+        }
+    }
+
+    mod pandoc_citations {
+        use super::*;
+
+        #[test]
+        fn pandoc_citations() {
+            let input = r#######"
+                1. Group.^[*See* [@authorBookTitleTitle2021, p. 5; *see also* @authorJournalArticleTitle2021, 1001 (discussing books)].]
+                2. Old syntax.^[[@authorJournalArticleTitle2021] at 1002.]
+                3. Id.^[[@authorJournalArticleTitle2021, 1003].]
+                4. Supra.^[[@authorBookTitleTitle2021] at 6.]
+                "#######;
+
+            let output = r#######"
+                1. Group.^[*See* **Book Author**, **Book Title: A Title for the Dummy Book** 5 (4th ed. 2021) [hereinafter **Author**, **Book Title**]; *see also* Article Author, *Journal Article Title: A Journal Article*, 99 **J. J. Articles** 1000, 1001 (2021) [hereinafter Author, *Journal Article*] (discussing books).]
+                2. Old syntax.^[Author, *Journal Article*, *supra* note 1, at 1002.]
+                3. Id.^[*Id.* at 1003.]
+                4. Supra.^[**Author**, **Book Title**, *supra* note 1, at 6.]
+                "#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+            let target_lines: Vec<&str> = output.lines().collect();
+
+            for (i, line) in pre.lines().enumerate() {
+                assert_eq!(line, target_lines[i])
+            }
+        }
+    }
+
+    mod narratives {
+        use super::*;
+
+        #[test]
+        fn narratives() {
+            let input = r#######"
+                As @authorBookTitleTitle2021 argues, [-@DoeState2022] was wrong.^[[@DoeState2022] at 460.]
+                2. Book.^[[@authorJournalArticleTitle2021] at 1001.]
+                3. Book.^[[@authorJournalArticleTitle2021] at 1002.]
+                4. Book.^[[@authorBookTitleTitle2021] at 3.]
+                5. Book.^[[@authorJournalArticleTitle2021] at 1003.]
+                6. Book.^[[@authorBookTitleTitle2021] at 4.]
+                The court in [-@DoeState2022] held otherwise.^[[@DoeState2022] at 461.]
+                Ask @support or [see @DoeState2022] instead.
+                "#######;
+
+            let output = r#######"
+                As Author argues, *Doe* was wrong.^[Doe v. State, 123 F.4th 456, 460 (7th Cir. 2022).]
+                2. Book.^[Article Author, *Journal Article Title: A Journal Article*, 99 **J. J. Articles** 1000, 1001 (2021) [hereinafter Author, *Journal Article*].]
+                3. Book.^[*Id.* at 1002.]
+                4. Book.^[**Book Author**, **Book Title: A Title for the Dummy Book** 3 (4th ed. 2021) [hereinafter **Author**, **Book Title**].]
+                5. Book.^[Author, *Journal Article*, *supra* note 2, at 1003.]
+                6. Book.^[**Author**, **Book Title**, *supra* note 4, at 4.]
+                The court in *Doe* held otherwise.^[*Doe*, 123 F.4th at 461.]
+                Ask @support or [see @DoeState2022] instead.
+                "#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+
+            let target_lines: Vec<&str> = output.lines().collect();
+
+            for (i, line) in pre.lines().enumerate() {
+                assert_eq!(line, target_lines[i])
+            }
+        }
+    }
+
+    mod pincites {
+        use super::*;
+
+        #[test]
+        fn pincites() {
+            let input = r#######"
+                1. Range.^[[@authorJournalArticleTitle2021] at 1234-1240.]
+                2. Same range.^[[@authorJournalArticleTitle2021] 1234--1240.]
+                3. Section.^[[@authorBookTitleTitle2021] § 3.]
+                4. Paragraph.^[[@authorJournalArticleTitle2021] at ¶ 4.]
+                5. Note.^[[@authorBookTitleTitle2021] at 5 n.2.]
+                6. Id. section.^[[@authorBookTitleTitle2021] §§ 3--4.]
+                "#######;
+
+            let output = r#######"
+                1. Range.^[Article Author, *Journal Article Title: A Journal Article*, 99 **J. J. Articles** 1000, 1234–40 (2021) [hereinafter Author, *Journal Article*].]
+                2. Same range.^[*Id.*]
+                3. Section.^[**Book Author**, **Book Title: A Title for the Dummy Book** §\ 3 (4th ed. 2021) [hereinafter **Author**, **Book Title**].]
+                4. Paragraph.^[Author, *Journal Article*, *supra* note 1, ¶\ 4.]
+                5. Note.^[**Author**, **Book Title**, *supra* note 3, at 5 n.2.]
+                6. Id. section.^[*Id.* §§\ 3–4.]
+                "#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+
+            assert_eq!(pre, output);
+        }
+    }
+
+    mod modifiers {
+        use super::*;
+
+        #[test]
+        fn modifiers() {
+            let input = r#######"
+                1. Hereinafter.^[[@dauthorTwoAuthorJournalArticle2021]{hereinafter="Two Authors"} at 5.]
+                2. No id.^[[@dauthorTwoAuthorJournalArticle2021]{.noid} at 6.]
+                3. Long.^[[@dauthorTwoAuthorJournalArticle2021]{.long} at 7.]
+                4. Case.^[[@PlaintiffDefendant1991] at 5.]
+                5. Short.^[[@PlaintiffDefendant1991]{.short} at 6.]
+                6. Supra.^[[@dauthorTwoAuthorJournalArticle2021] at 8.]
+                "#######;
+
+            let output = r#######"
+                1. Hereinafter.^[Article Dauthor, Jr. & Article III Fauthor, *Two-Author Journal Article: This Article Has Two Authors*, 51 **J. J. Articles** 101, 5 (2021) [hereinafter Two Authors].]
+                2. No id.^[Two Authors, *supra* note 1, at 6.]
+                3. Long.^[Article Dauthor, Jr. & Article III Fauthor, *Two-Author Journal Article: This Article Has Two Authors*, 51 **J. J. Articles** 101, 7 (2021) [hereinafter Two Authors].]
+                4. Case.^[Plaintiff A v. Defendant A, 100 F.3d 1, 5 (1st Cir. 1991).]
+                5. Short.^[*Plaintiff A*, 100 F.3d at 6.]
+                6. Supra.^[Two Authors, *supra* note 1, at 8.]
+                "#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+
+            assert_eq!(pre, output);
+        }
+    }
+
+    mod nested_citations {
+        use super::*;
+
+        #[test]
+        fn nested_citations() {
+            let input = r#######"
+                1. Nested.^[[@authorBookTitleTitle2021] at 5 (quoting [@authorJournalArticleTitle2021] at 1001).]
+                2. Id.^[[@authorBookTitleTitle2021] at 6.]
+                3. Supra.^[[@authorJournalArticleTitle2021] at 1002.]
+                4. Case.^[*See* [@DoeState2022] at 460 (citing [@PlaintiffDefendant1991] at 5 (explaining); [@PlaintiffDefendant1992]).]
+                5. Short.^[[@PlaintiffDefendant1991] at 6.]
+                "#######;
+
+            let output = r#######"
+                1. Nested.^[**Book Author**, **Book Title: A Title for the Dummy Book** 5 (4th ed. 2021) [hereinafter **Author**, **Book Title**] (quoting Article Author, *Journal Article Title: A Journal Article*, 99 **J. J. Articles** 1000, 1001 (2021) [hereinafter Author, *Journal Article*]).]
+                2. Id.^[*Id.* at 6.]
+                3. Supra.^[Author, *Journal Article*, *supra* note 1, at 1002.]
+                4. Case.^[*See* Doe v. State, 123 F.4th 456, 460 (7th Cir. 2022) (citing Plaintiff A v. Defendant A, 100 F.3d 1, 5 (1st Cir. 1991) (explaining); Plaintiff B v. Defendant B, 2 F.3d 200 (2d Cir. 1992)).]
+                5. Short.^[*Plaintiff A*, 100 F.3d at 6.]
+                "#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+
+            assert_eq!(pre, output);
+        }
+    }
+
+    mod verbatim {
+        use super::*;
+
+        #[test]
+        fn verbatim() {
+            let input = r#######"---
+title: "Footnotes^[Like this one.]"
+---
+
+Text.^[[@authorBookTitleTitle2021] at 1.] Code like `^[[@authorBookTitleTitle2021].]`, \^[escapes], and <!-- ^[comments] --> are untouched.
+
+```markdown
+Text.^[[@authorBookTitleTitle2021].]
+```
+
+---
+
+After a rule.^[See [@authorBookTitleTitle2021] `[@code]`.]
+
+---
+"#######;
+
+            let output = r#######"---
+title: "Footnotes^[Like this one.]"
+---
+
+Text.^[**Book Author**, **Book Title: A Title for the Dummy Book** 1 (4th ed. 2021).] Code like `^[[@authorBookTitleTitle2021].]`, \^[escapes], and <!-- ^[comments] --> are untouched.
+
+```markdown
+Text.^[[@authorBookTitleTitle2021].]
+```
+
+---
+
+After a rule.^[*See* *id.* `[@code]`.]
+
+---
+"#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+
+            assert_eq!(pre, output);
+        }
+    }
+
+    mod reference_notes {
+        use super::*;
+
+        #[test]
+        fn reference_notes() {
+            let input = r#######"
+                Inline note.^[[@authorBookTitleTitle2021] at 1.] Reference note.[^ref]
+
+[^ref]: *See* *id.* at 2.
+
+    And a second paragraph with [@authorAnotherBookTitle2021].
+
+Body text continues.
+"#######;
+
+            let output = r#######"
+                Inline note.^[**Book Author**, **Book Title: A Title for the Dummy Book** 1 (4th ed. 2021) [hereinafter **Author**, **Book Title**].] Reference note.[^ref]
+
+
+Body text continues.
+
+[^ref]: *See* *id.* at 2.
+
+    And a second paragraph with **Book Author**, **Another Book Title: A Title for the Dummy Book** (2021) [hereinafter **Author**, **Another Book Title**].
+"#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+
+            assert_eq!(pre, output);
+        }
+
+        /// A note referred to twice gets a definition for each reference.
+        #[test]
+        fn repeated_reference() {
+            let input = r#######"
+Text.[^b] More text.[^b-2] Again.[^b]
+
+[^b]: [@authorJournalArticleTitle2021] at 5.
+
+[^b-2]: A note.
+"#######;
+
+            let output = r#######"
+Text.[^b] More text.[^b-2] Again.[^b-3]
+
+[^b]: Article Author, *Journal Article Title: A Journal Article*, 99 **J. J. Articles** 1000, 5 (2021).
+
+[^b-2]: A note.
+
+[^b-3]: *Id.*
+"#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+
+            assert_eq!(pre, output);
+        }
+    }
+
+    mod aliases {
+        use super::*;
+
+        /// An old key and its new one are the same source.
+        #[test]
+        fn old_and_new_keys() {
+            let input = r#######"
+                1. Old key.^[[@oldArticle] at 5.]
+
+                2. New key (*Id.*).^[[@authorJournalArticleTitle2021] at 6.]
+
+                3. Case.^[[@PlaintiffDefendant2000].]
+
+                4. Old key (*supra*).^[[@oldArticle] at 7.]
+                "#######;
+
+            let output = r#######"
+                1. Old key.^[Article Author, *Journal Article Title: A Journal Article*, 99 **J. J. Articles** 1000, 5 (2021).]
+
+                2. New key (*Id.*).^[*Id.* at 6.]
+
+                3. Case.^[Plaintiff J v. Defendant J, 10 F.3d 1000 (10th Cir. 2000).]
+
+                4. Old key (*supra*).^[Author, *supra* note 1, at 7.]
+                "#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &Some(r#"{"oldArticle": "authorJournalArticleTitle2021"}"#.to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(pre, output);
+        }
+    }
+
+    mod bluepages {
+        use super::*;
+
+        #[test]
+        fn bluepages() {
+            let input = r#######"
+                1. Book.^[[@authorBookTitleTitle2021] at 12.]
+
+                2. Article.^[[@authorJournalArticleTitle2021] at 1001.]
+
+                3. Chapter.^[[@authorBookChapterTitle2021].]
+
+                4. Case.^[[@DoeState2022] at 457.]
+
+                5. Short forms.^[[@authorBookTitleTitle2021] at 15; [@DoeState2022] at 458.]
+
+                6. Case short form.^[[@DoeState2022] at 459.]
+
+                7. Id.^[[@DoeState2022] at 460.]
+                "#######;
+
+            let output = r#######"
+                1. Book.^[Book Author, *Book Title: A Title for the Dummy Book* 12 (4th ed. 2021) [hereinafter Author, *Book Title*].]
+
+                2. Article.^[Article Author, *Journal Article Title: A Journal Article*, 99 J. J. Articles 1000, 1001 (2021) [hereinafter Author, *Journal Article*].]
+
+                3. Chapter.^[Chapter Author, *Book Chapter Title: The Chapter of a Book*, *in* 15 *The Title of the Chapter Book* 101 (5th ed., Book Editor ed., 2021) [hereinafter Author, *Book Chapter Title*].]
+
+                4. Case.^[*Doe v. State*, 123 F.4th 456, 457 (7th Cir. 2022).]
+
+                5. Short forms.^[Author, *Book Title*, *supra* note 1, at 15; *Doe*, 123 F.4th at 458.]
+
+                6. Case short form.^[*Doe*, 123 F.4th at 459.]
+
+                7. Id.^[*Id.* at 460.]
+                "#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluepages,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
             let target_lines: Vec<&str> = output.lines().collect();
 
             for (i, line) in pre.lines().enumerate() {
                 assert_eq!(line, target_lines[i])
             }
         }
+
+        #[test]
+        fn keeps_user_bold() {
+            // Bold the user wrote stays, even though the Bluepages don't bold
+            // authors or journal names.
+            let input = r#"Text.^[[@authorBookTitleTitle2021]{hereinafter="**The** Book"} at 12.]"#;
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluepages,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+
+            assert_eq!(
+                pre,
+                "Text.^[Book Author, *Book Title: A Title for the Dummy Book* 12 (4th ed. 2021) [hereinafter **The** Book].]"
+            );
+        }
+    }
+
+    mod oscola {
+        use super::*;
+
+        #[test]
+        fn oscola() {
+            let input = r#######"
+                1. Book.^[[@authorBookTitleTitle2021] 12.]
+
+                2. Article.^[[@authorJournalArticleTitle2021] 1001.]
+
+                3. Ibid.^[[@authorJournalArticleTitle2021] 1002.]
+
+                4. Case.^[[@PlaintiffDefendant1992] 201; [@DoeState2022].]
+
+                5. Short forms.^[[@authorBookTitleTitle2021] 15; [@PlaintiffDefendant1992] 202.]
+
+                6. Chapter.^[[@authorBookChapterTitle2021] 101.]
+
+                7. Manuscript.^[[@kauthorManuscriptTitleNot2021].]
+
+                8. Two authors.^[[@dauthorTwoAuthorJournalArticle2021].]
+
+                9. Year volume.^[[@cauthorJournalArticleYear2021] 502.]
+
+                10. Ibid.^[[@cauthorJournalArticleYear2021].]
+                "#######;
+
+            let output = r#######"
+                1. Book.^[Book Author, *Book Title: A Title for the Dummy Book* (4th edn, 2021) 12.]
+
+                2. Article.^[Article Author, 'Journal Article Title: A Journal Article' (2021) 99 J J Articles 1000, 1001.]
+
+                3. Ibid.^[Ibid 1002.]
+
+                4. Case.^[*Plaintiff B v Defendant B* (1992) 2 F3d 200, 201 (2d Cir); *Doe v State* (2022) 123 F4th 456 (7th Cir).]
+
+                5. Short forms.^[Author, *Book Title* (n 1) 15; *Plaintiff B* (n 4) 202.]
+
+                6. Chapter.^[Chapter Author, 'Book Chapter Title: The Chapter of a Book' in Book Editor (ed), *The Title of the Chapter Book*, vol 15 (5th edn, 2021) 101.]
+
+                7. Manuscript.^[Manuscript Kauthor, 'Manuscript Title: Not Yet a Journal Article' (2021) 99 U Manuscripts L Rev (forthcoming) <www.manuscripts.manuscript/manuscript>.]
+
+                8. Two authors.^[Article Dauthor, Jr. and Article III Fauthor, 'Two-Author Journal Article: This Article Has Two Authors' (2021) 51 J J Articles 101.]
+
+                9. Year volume.^[Article Cauthor, 'Journal Article With a Year Volume: This Journal Uses Years as Volumes' [2021] The Other J J Articles 501, 502.]
+
+                10. Ibid.^[Ibid.]
+                "#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Oscola,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+            let target_lines: Vec<&str> = output.lines().collect();
+
+            for (i, line) in pre.lines().enumerate() {
+                assert_eq!(line, target_lines[i])
+            }
+        }
+
+        #[test]
+        fn bibliography() {
+            let input = r#######"
+                1. Sources.^[[@authorBookTitleTitle2021]; [@PlaintiffDefendant1992]; [@dauthorTwoAuthorJournalArticle2021]; [@DoeState2022].]
+                "#######;
+
+            let output = r#######"
+                1. Sources.^[Book Author, *Book Title: A Title for the Dummy Book* (4th edn, 2021); *Plaintiff B v Defendant B* (1992) 2 F3d 200 (2d Cir); Article Dauthor, Jr. and Article III Fauthor, 'Two-Author Journal Article: This Article Has Two Authors' (2021) 51 J J Articles 101; *Doe v State* (2022) 123 F4th 456 (7th Cir).]
+                
+
+# Table of Cases {.unnumbered}
+
+Doe v State (2022) 123 F4th 456 (7th Cir)
+
+Plaintiff B v Defendant B (1992) 2 F3d 200 (2d Cir)
+
+# Bibliography {.unnumbered}
+
+Author B, *Book Title: A Title for the Dummy Book* (4th edn, 2021)
+
+Dauthor A and Fauthor A, 'Two-Author Journal Article: This Article Has Two Authors' (2021) 51 J J Articles 101"#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Oscola,
+                true,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+            let target_lines: Vec<&str> = output.lines().collect();
+
+            assert_eq!(pre.lines().count(), target_lines.len());
+            for (i, line) in pre.lines().enumerate() {
+                assert_eq!(line, target_lines[i])
+            }
+        }
+    }
+
+    mod aglc {
+        use super::*;
+
+        #[test]
+        fn aglc() {
+            let input = r#######"
+                1. Book.^[[@authorBookTitleTitle2021] 12.]
+
+                2. Article.^[[@authorJournalArticleTitle2021] 1001.]
+
+                3. Ibid.^[[@authorJournalArticleTitle2021] 1002.]
+
+                4. Case.^[[@MaboQueensland1992] 15; [@PlaintiffDefendant1992].]
+
+                5. No ibid after two sources.^[[@PlaintiffDefendant1992] 201.]
+
+                6. Ibid.^[[@PlaintiffDefendant1992] 201.]
+
+                7. No ibid without a pin.^[[@PlaintiffDefendant1992].]
+
+                8. Short forms.^[[@authorBookTitleTitle2021] 15; [@MaboQueensland1992] 20.]
+
+                9. Chapter.^[[@authorBookChapterTitle2021] 105.]
+
+                10. Manuscript.^[[@authorNotForthcomingManuscript2021] 3.]
+                "#######;
+
+            let output = r#######"
+                1. Book.^[Book Author, *Book Title: A Title for the Dummy Book* (4th ed, 2021) 12.]
+
+                2. Article.^[Article Author, 'Journal Article Title: A Journal Article' (2021) 99 *Journal of Journal Articles* 1000, 1001.]
+
+                3. Ibid.^[Ibid 1002.]
+
+                4. Case.^[*Mabo v Queensland (No 2)* [1992] HCA 23; (1992) 175 CLR 1, 15; *Plaintiff B v Defendant B* (1992) 2 F3d 200 (2d Cir).]
+
+                5. No ibid after two sources.^[*Plaintiff B* (n 4) 201.]
+
+                6. Ibid.^[Ibid.]
+
+                7. No ibid without a pin.^[*Plaintiff B* (n 4).]
+
+                8. Short forms.^[Author, *Book Title* (n 1) 15; *Mabo* (n 4) 20.]
+
+                9. Chapter.^[Chapter Author, 'Book Chapter Title: The Chapter of a Book' in Book Editor (ed), *The Title of the Chapter Book* (vol 15) (5th ed, 2021) 101, 105.]
+
+                10. Manuscript.^[Manuscipt Author, 'Not Yet Forthcoming Manuscript: This Manuscript Is Not Yet Placed' (Unpublished manuscript, 2021) 3.]
+                "#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Aglc,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+            let target_lines: Vec<&str> = output.lines().collect();
+
+            for (i, line) in pre.lines().enumerate() {
+                assert_eq!(line, target_lines[i])
+            }
+        }
+    }
+
+    mod house_style {
+        use super::*;
+
+        #[test]
+        fn house_style() {
+            let input = r#######"
+                1. Book.^[[@authorBookTitleTitle2021].]
+
+                2. Another book.^[[@authorAnotherBookTitle2021] at 1.]
+
+                3. Two authors.^[[@dauthorTwoAuthorJournalArticle2021].]
+
+                4. Three authors.^[[@gauthorThreeAuthorJournalArticle2021].]
+
+                5. Short forms.^[[@authorBookTitleTitle2021] at 5; [@dauthorTwoAuthorJournalArticle2021] at 102; [@gauthorThreeAuthorJournalArticle2021] at 202.]
+                "#######;
+
+            let output = r#######"
+                1. Book.^[**Book Author**, **Book Title: A Title for the Dummy Book** (4th ed. 2021) (hereinafter **Author**, **Book Title**).]
+
+                2. Another book.^[**Book Author**, **Another Book Title: A Title for the Dummy Book** 1 (2021) (hereinafter **Author**, **Another Book Title**).]
+
+                3. Two authors.^[Article Dauthor, Jr. and Article III Fauthor, *Two-Author Journal Article: This Article Has Two Authors*, 51 **J. J. Articles** 101 (2021).]
+
+                4. Three authors.^[Article Gauthor, Sr., Article Hauthor, Jr. and Article III Jauthor, *Three-Author Journal Article: This Article Has Three Authors*, 50 **J. J. Articles** 201 (2021).]
+
+                5. Short forms.^[**Author**, **Book Title**, *supra* n. 1, at 5; Dauthor and Fauthor, *supra* n. 3, at 102; Gauthor, Hauthor and Jauthor, *supra* n. 4, at 202.]
+                "#######;
+
+            let house_style = r#"
+                (
+                    supra_note: "n.",
+                    hereinafter_parentheses: true,
+                    author_conjunction: "and",
+                    short_et_al: 4,
+                )
+                "#;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &Some(house_style.to_string()),
+                &None,
+            )
+            .unwrap();
+            let target_lines: Vec<&str> = output.lines().collect();
+
+            for (i, line) in pre.lines().enumerate() {
+                assert_eq!(line, target_lines[i])
+            }
+        }
+    }
+
+    mod biblatex {
+        use super::*;
+
+        #[test]
+        fn biblatex() {
+            let input = r#######"
+                1. Sources.^[[@authorBookTitleTitle2021] at 5; [@dauthorTwoAuthorJournalArticle2021] at 105; [@DoeState2022] at 460.]
+
+                2. Short form.^[[@authorBookTitleTitle2021] at 6.]
+                "#######;
+
+            let output = r#######"
+                1. Sources.^[**Book Author**, **Book Title: A Title for the Dummy Book** 5 (4th ed. 2021); Article Dauthor, Jr. & Article III Fauthor, *Two-Author Journal Article: This Article Has Two Authors*, 51 **J. J. Articles** 101, 105 (2021); Doe v. State, 123 F.4th 456, 460 (7th Cir. 2022).]
+
+                2. Short form.^[**Author**, *supra* note 1, at 6.]
+                "#######;
+
+            let library = r#"
+                @book{authorBookTitleTitle2021,
+                  author = {Author, Book},
+                  title = {Book Title: A Title for the Dummy Book},
+                  shorttitle = {Book Title},
+                  edition = 4,
+                  date = {2021},
+                }
+
+                @article{dauthorTwoAuthorJournalArticle2021,
+                  author = {Dauthor, Jr., Article and Fauthor, Article III},
+                  title = {Two-Author Journal Article: This Article Has Two Authors},
+                  journaltitle = {Journal of Journal Articles},
+                  volume = {51},
+                  pages = {101--130},
+                  date = {2021},
+                }
+
+                @jurisdiction{DoeState2022,
+                  title = {Doe v. State},
+                  shorttitle = {Doe},
+                  court = {7th Cir.},
+                  journaltitle = {F.4th},
+                  volume = {123},
+                  pages = {456},
+                  date = {2022-07-25},
+                }
+                "#;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.bib",
+                    library,
+                    LibraryFormat::BibLatex,
+                )],
+                &None,
+                0,
+                false,
+                Style::Bluebook,
+                false,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+            let target_lines: Vec<&str> = output.lines().collect();
+
+            for (i, line) in pre.lines().enumerate() {
+                assert_eq!(line, target_lines[i])
+            }
+        }
+    }
+
+    mod csl {
+        use super::*;
+
+        #[test]
+        fn csl() {
+            let input = r#######"
+                1. Book.^[[@authorBookTitleTitle2021] at 12.]
+
+                2. Ibid.^[*See* [@authorBookTitleTitle2021] at 15 (explaining things); [@authorJournalArticleTitle2021] at 1001.]
+
+                3. Short form.^[[@authorBookTitleTitle2021] at 20.]
+                "#######;
+
+            let output = r#######"
+                1. Book.^[Book Author, *Book Title: A Title for the Dummy Book*, 2021, 12.]
+
+                2. Ibid.^[*See* ibid., 15 (explaining things); Article Author, *Journal Article Title: A Journal Article*, 2021, 1001.]
+
+                3. Short form.^[Author, 20.]
+                
+
+# References {.unnumbered}
+
+Author, Article. *Journal Article Title: A Journal Article*. 2021.
+
+Author, Book. *Book Title: A Title for the Dummy Book*. 2021."#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Csl,
+                true,
+                &Some(test_inputs::TESTCSL.to_string()),
+                &None,
+                &None,
+            )
+            .unwrap();
+            let target_lines: Vec<&str> = output.lines().collect();
+
+            assert_eq!(pre.lines().count(), target_lines.len());
+            for (i, line) in pre.lines().enumerate() {
+                assert_eq!(line, target_lines[i])
+            }
+        }
+
+        #[test]
+        fn nested() {
+            let input = r#######"Text.^[[@authorBookTitleTitle2021] at 12 (quoting [@authorJournalArticleTitle2021] at 1001).]"#######;
+
+            let output = r#######"Text.^[Book Author, *Book Title: A Title for the Dummy Book*, 2021, 12 (quoting Article Author, *Journal Article Title: A Journal Article*, 2021, 1001).]"#######;
+
+            let pre = pre(
+                input,
+                &[LibraryFile::new(
+                    "test.json",
+                    test_inputs::TESTJSON,
+                    LibraryFormat::CslJson,
+                )],
+                &None,
+                0,
+                false,
+                Style::Csl,
+                false,
+                &Some(test_inputs::TESTCSL.to_string()),
+                &None,
+                &None,
+            )
+            .unwrap();
+
+            assert_eq!(pre, output);
+        }
     }
 
     pub mod test_inputs {
 
-        pub const TESTJSON: &str = r#"[{"id": "authorAnotherBookChapter2021","author": [{"family": "Author","given": "Chapter"}],"citation-key": "authorAnotherBookChapter2021","container-title": "The Title of the Chapter Book","edition": "5th","editor": [{"family": "Editor","given": "Book"}],"issued": {"date-parts": [[2021]]},"page": "101","title": "Another Book Chapter Title: The Chapter of a Book","title-short": "Another Book Chapter Title","type": "chapter","volume": "15"},{"id": "authorAnotherBookTitle2021","author": [{"family": "Author","given": "Book"}],"citation-key": "authorAnotherBookTitle2021","issued": {"date-parts": [[2021]]},"title": "Another Book Title: A Title for the Dummy Book","title-short": "Another Book Title","type": "book"},{"id": "authorAnotherJournalArticle2021","author": [{"family": "Author","given": "Article"}],"citation-key": "authorAnotherJournalArticle2021","container-title": "Journal of Good Journal Articles","container-title-short": "J. Good J. Articles","issued": {"date-parts": [[2021]]},"page": "1","title": "Another Journal Article Title: A Journal Article","title-short": "Another Journal Article","type": "article-journal","volume": "1"},{"id": "authorAnotherNotForthcoming2021","author": [{"family": "Author","given": "Manuscipt"}],"citation-key": "authorAnotherNotForthcoming2021","issued": {"date-parts": [[2021]]},"title": "Another Not Yet Forthcoming Manuscript: This Manuscript Is Not Yet Placed","title-short": "Another Not Yet","type": "manuscript"},{"id": "authorBookChapterTitle2021","author": [{"family": "Author","given": "Chapter"}],"citation-key": "authorBookChapterTitle2021","container-title": "The Title of the Chapter Book","edition": "5th","editor": [{"family": "Editor","given": "Book"}],"issued": {"date-parts": [[2021]]},"page": "101","title": "Book Chapter Title: The Chapter of a Book","title-short": "Book Chapter Title","type": "chapter","volume": "15"},{"id": "authorBookTitleTitle2021","author": [{"family": "Author","given": "Book"}],"citation-key": "authorBookTitleTitle2021","edition": "4th","issued": {"date-parts": [[2021]]},"title": "Book Title: A Title for the Dummy Book","title-short": "Book Title","type": "book"},{"id": "authorJournalArticleTitle2021","author": [{"family": "Author","given": "Article"}],"citation-key": "authorJournalArticleTitle2021","container-title": "Journal of Journal Articles","container-title-short": "J. J. Articles","issued": {"date-parts": [[2021]]},"page": "1000","title": "Journal Article Title: A Journal Article","title-short": "Journal Article","type": "article-journal","volume": "99"},{"id": "authorNotForthcomingManuscript2021","author": [{"family": "Author","given": "Manuscipt"}],"citation-key": "authorNotForthcomingManuscript2021","issued": {"date-parts": [[2021]]},"title": "Not Yet Forthcoming Manuscript: This Manuscript Is Not Yet Placed","title-short": "Not Yet","type": "manuscript"},{"id": "cauthorJournalArticleYear2021","author": [{"family": "Cauthor","given": "Article"}],"citation-key": "cauthorJournalArticleYear2021","container-title": "The Other Journal of Journal Articles","issued": {"date-parts": [[2021]]},"page": "501","title": "Journal Article With a Year Volume: This Journal Uses Years as Volumes","title-short": "Year Volume","type": "article-journal","volume": "2021"},{"id": "dauthorTwoAuthorJournalArticle2021","author": [{"family": "Dauthor","given": "Article","suffix": "Jr."},{"family": "Fauthor","given": "Article III"}],"citation-key": "dauthorTwoAuthorJournalArticle2021","container-title": "Journal of Journal Articles","issued": {"date-parts": [[2021]]},"page": "101","title": "Two-Author Journal Article: This Article Has Two Authors","title-short": "Two-Author","type": "article-journal","volume": "51"},{"id": "DoeState2022","authority": "7th Cir.","citation-key": "DoeState2022","container-title": "F.4th","issued": {"date-parts": [[2022,7,25]]},"number": "21-12345","page": "456","title": "Doe v. State","title-short": "Doe","type": "legal_case","URL": "www.fakeurl.edu","volume": "123"},{"id": "gauthorThreeAuthorJournalArticle2021","author": [{"family": "Gauthor","given": "Article","suffix": "Sr."},{"family": "Hauthor","given": "Article","suffix": "Jr."},{"family": "Jauthor","given": "Article III"}],"citation-key": "gauthorThreeAuthorJournalArticle2021","container-title": "Journal of Journal Articles","issued": {"date-parts": [[2021]]},"page": "201","title": "Three-Author Journal Article: This Article Has Three Authors","title-short": "Three-Authors","type": "article-journal","volume": "50"},{"id": "JohnsonSmith2023","authority": "U.S. Supreme Court","citation-key": "JohnsonSmith2023","container-title": "U.S.","issued": {"date-parts": [[2023]]},"page": "456","title": "Johnson v. Smith","title-short": "Johnson","type": "legal_case","volume": "123"},{"id": "kauthorManuscriptTitleNot2021","author": [{"family": "Kauthor","given": "Manuscript"}],"citation-key": "kauthorManuscriptTitleNot2021","container-title": "University of Manuscripts Law Review","issued": {"date-parts": [[2021]]},"title": "Manuscript Title: Not Yet a Journal Article","title-short": "Manuscript Title","type": "manuscript","URL": "www.manuscripts.manuscript/manuscript","volume": "99"},{"id": "lauthorBookTranslatorThis2021","author": [{"family": "Lauthor","given": "Book"}],"citation-key": "lauthorBookTranslatorThis2021","issued": {"date-parts": [[2021]]},"title": "Book With a Translator: This Book Has a Translator","title-short": "Book With a Translator","translator": [{"family": "Translator","given": "Book"}],"type": "book"},{"id": "mauthorBookEditorThis2021","author": [{"family": "Mauthor","given": "Book"}],"citation-key": "mauthorBookEditorThis2021","edition": "2d","editor": [{"family": "Editor","given": "Book"}],"issued": {"date-parts": [[2021]]},"title": "Book With an Editor: This Book Has an Editor","title-short": "Book With an Editor","type": "book"},{"id": "nauthorMultiVolumeBookThis2021","author": [{"family": "Nauthor","given": "Book"}],"citation-key": "nauthorMultiVolumeBookThis2021","issued": {"date-parts": [[2021]]},"title": "Multi-Volume Book: This Book Is One of Several Volumes","title-short": "Multi-Volume Book","type": "book","volume": "10"},{"id": "PlaintiffDefendant1991","authority": "1st Cir.","citation-key": "PlaintiffDefendant1991","container-title": "F.3d","issued": {"date-parts": [[1991]]},"page": "1","title": "Plaintiff A v. Defendant A","title-short": "Plaintiff A","type": "legal_case","volume": "100"},{"id": "PlaintiffDefendant1992","authority": "2d Cir.","citation-key": "PlaintiffDefendant1992","container-title": "F.3d","issued": {"date-parts": [[1992]]},"page": "200","title": "Plaintiff B v. Defendant B","title-short": "Plaintiff B","type": "legal_case","volume": "2"},{"id": "PlaintiffDefendant1993","authority": "3d Cir.","citation-key": "PlaintiffDefendant1993","container-title": "F.3d","issued": {"date-parts": [[1993]]},"page": "33","title": "Plaintiff C v. Defendant C","title-short": "Plaintiff C","type": "legal_case","volume": "333"},{"id": "PlaintiffDefendant1994","authority": "4th Cir.","citation-key": "PlaintiffDefendant1994","container-title": "F.3d","issued": {"date-parts": [[1994]]},"page": "444","title": "Plaintiff D v. Defendant D","title-short": "Plaintiff D","type": "legal_case","volume": "44"},{"id": "PlaintiffDefendant1995","authority": "5th Cir.","citation-key": "PlaintiffDefendant1995","container-title": "F.3d","issued": {"date-parts": [[1995]]},"page": "555","title": "Plaintiff E v. Defendant E","title-short": "Plaintiff E","type": "legal_case","volume": "5"},{"id": "PlaintiffDefendant1996","authority": "6th Cir.","citation-key": "PlaintiffDefendant1996","container-title": "F.3d","issued": {"date-parts": [[1996]]},"page": "6","title": "Plaintiff F v. Defendant F","title-short": "Plaintiff F","type": "legal_case","volume": "600"},{"id": "PlaintiffDefendant1997","authority": "7th Cir.","citation-key": "PlaintiffDefendant1997","container-title": "F.3d","issued": {"date-parts": [[1997]]},"page": "77","title": "Plaintiff G v. Defendant G","title-short": "Plaintiff G","type": "legal_case","volume": "77"},{"id": "PlaintiffDefendant1998","authority": "8th Cir.","citation-key": "PlaintiffDefendant1998","container-title": "F.3d","issued": {"date-parts": [[1998]]},"page": "8","title": "Plaintiff H v. Defendant H","title-short": "Plaintiff H","type": "legal_case","volume": "888"},{"id": "PlaintiffDefendant1999","authority": "9th Cir.","citation-key": "PlaintiffDefendant1999","container-title": "F.3d","issued": {"date-parts": [[1999]]},"page": "9","title": "Plaintiff I v. Defendant I","title-short": "Plaintiff I","type": "legal_case","volume": "9"},{"id": "PlaintiffDefendant2000","authority": "10th Cir.","citation-key": "PlaintiffDefendant2000","container-title": "F.3d","issued": {"date-parts": [[2000]]},"page": "1000","title": "Plaintiff J v. Defendant J","title-short": "Plaintiff J","type": "legal_case","volume": "10"},{"id": "PlaintiffDefendant2001","authority": "11th Cir.","citation-key": "PlaintiffDefendant2001","container-title": "F.3d","issued": {"date-parts": [[2001]]},"page": "1111","title": "Plaintiff K v. Defendant K","title-short": "Plaintiff K","type": "legal_case","volume": "111"},{"id": "PlaintiffDefendant2020","authority": "D.C. Cir.","citation-key": "PlaintiffDefendant2020","container-title": "F.4th","issued": {"date-parts": [[2020]]},"page": "434","title": "Plaintiff L v. Defendant L","title-short": "Plaintiff L","type": "legal_case","volume": "43"},{"id": "PlaintiffDefendant2023","authority": "Fed. Cir.","citation-key": "PlaintiffDefendant2023","container-title": "F.4th","issued": {"date-parts": [[2023]]},"page": "345","title": "Plaintiff M v. Defendant M","title-short": "Plaintiff M","type": "legal_case","volume": "543"},{"id": "UnitedStatesJones2022","authority": "1st Cir.","citation-key": "UnitedStatesJones2022","container-title": "F.4th","issued": {"date-parts": [[2022]]},"page": "5309","references":"(per curiam)","title": "United States v. Jones","title-short": "Jones","type": "legal_case","volume": "867"}]"#;
+        pub const TESTJSON: &str = r#"[{"id": "authorAnotherBookChapter2021","author": [{"family": "Author","given": "Chapter"}],"citation-key": "authorAnotherBookChapter2021","container-title": "The Title of the Chapter Book","edition": "5th","editor": [{"family": "Editor","given": "Book"}],"issued": {"date-parts": [[2021]]},"page": "101","title": "Another Book Chapter Title: The Chapter of a Book","title-short": "Another Book Chapter Title","type": "chapter","volume": "15"},{"id": "authorAnotherBookTitle2021","author": [{"family": "Author","given": "Book"}],"citation-key": "authorAnotherBookTitle2021","issued": {"date-parts": [[2021]]},"title": "Another Book Title: A Title for the Dummy Book","title-short": "Another Book Title","type": "book"},{"id": "authorAnotherJournalArticle2021","author": [{"family": "Author","given": "Article"}],"citation-key": "authorAnotherJournalArticle2021","container-title": "Journal of Good Journal Articles","container-title-short": "J. Good J. Articles","issued": {"date-parts": [[2021]]},"page": "1","title": "Another Journal Article Title: A Journal Article","title-short": "Another Journal Article","type": "article-journal","volume": "1"},{"id": "authorAnotherNotForthcoming2021","author": [{"family": "Author","given": "Manuscipt"}],"citation-key": "authorAnotherNotForthcoming2021","issued": {"date-parts": [[2021]]},"title": "Another Not Yet Forthcoming Manuscript: This Manuscript Is Not Yet Placed","title-short": "Another Not Yet","type": "manuscript"},{"id": "authorBookChapterTitle2021","author": [{"family": "Author","given": "Chapter"}],"citation-key": "authorBookChapterTitle2021","container-title": "The Title of the Chapter Book","edition": "5th","editor": [{"family": "Editor","given": "Book"}],"issued": {"date-parts": [[2021]]},"page": "101","title": "Book Chapter Title: The Chapter of a Book","title-short": "Book Chapter Title","type": "chapter","volume": "15"},{"id": "authorBookTitleTitle2021","author": [{"family": "Author","given": "Book"}],"citation-key": "authorBookTitleTitle2021","edition": "4th","issued": {"date-parts": [[2021]]},"title": "Book Title: A Title for the Dummy Book","title-short": "Book Title","type": "book"},{"id": "authorJournalArticleTitle2021","author": [{"family": "Author","given": "Article"}],"citation-key": "authorJournalArticleTitle2021","container-title": "Journal of Journal Articles","container-title-short": "J. J. Articles","issued": {"date-parts": [[2021]]},"page": "1000","title": "Journal Article Title: A Journal Article","title-short": "Journal Article","type": "article-journal","volume": "99"},{"id": "authorNotForthcomingManuscript2021","author": [{"family": "Author","given": "Manuscipt"}],"citation-key": "authorNotForthcomingManuscript2021","issued": {"date-parts": [[2021]]},"title": "Not Yet Forthcoming Manuscript: This Manuscript Is Not Yet Placed","title-short": "Not Yet","type": "manuscript"},{"id": "cauthorJournalArticleYear2021","author": [{"family": "Cauthor","given": "Article"}],"citation-key": "cauthorJournalArticleYear2021","container-title": "The Other Journal of Journal Articles","issued": {"date-parts": [[2021]]},"page": "501","title": "Journal Article With a Year Volume: This Journal Uses Years as Volumes","title-short": "Year Volume","type": "article-journal","volume": "2021"},{"id": "dauthorTwoAuthorJournalArticle2021","author": [{"family": "Dauthor","given": "Article","suffix": "Jr."},{"family": "Fauthor","given": "Article III"}],"citation-key": "dauthorTwoAuthorJournalArticle2021","container-title": "Journal of Journal Articles","issued": {"date-parts": [[2021]]},"page": "101","title": "Two-Author Journal Article: This Article Has Two Authors","title-short": "Two-Author","type": "article-journal","volume": "51"},{"id": "DoeState2022","authority": "7th Cir.","citation-key": "DoeState2022","container-title": "F.4th","issued": {"date-parts": [[2022,7,25]]},"number": "21-12345","page": "456","title": "Doe v. State","title-short": "Doe","type": "legal_case","URL": "www.fakeurl.edu","volume": "123"},{"id": "gauthorThreeAuthorJournalArticle2021","author": [{"family": "Gauthor","given": "Article","suffix": "Sr."},{"family": "Hauthor","given": "Article","suffix": "Jr."},{"family": "Jauthor","given": "Article III"}],"citation-key": "gauthorThreeAuthorJournalArticle2021","container-title": "Journal of Journal Articles","issued": {"date-parts": [[2021]]},"page": "201","title": "Three-Author Journal Article: This Article Has Three Authors","title-short": "Three-Authors","type": "article-journal","volume": "50"},{"id": "JohnsonSmith2023","authority": "U.S. Supreme Court","citation-key": "JohnsonSmith2023","container-title": "U.S.","issued": {"date-parts": [[2023]]},"page": "456","title": "Johnson v. Smith","title-short": "Johnson","type": "legal_case","volume": "123"},{"id": "kauthorManuscriptTitleNot2021","author": [{"family": "Kauthor","given": "Manuscript"}],"citation-key": "kauthorManuscriptTitleNot2021","container-title": "University of Manuscripts Law Review","issued": {"date-parts": [[2021]]},"title": "Manuscript Title: Not Yet a Journal Article","title-short": "Manuscript Title","type": "manuscript","URL": "www.manuscripts.manuscript/manuscript","volume": "99"},{"id": "lauthorBookTranslatorThis2021","author": [{"family": "Lauthor","given": "Book"}],"citation-key": "lauthorBookTranslatorThis2021","issued": {"date-parts": [[2021]]},"title": "Book With a Translator: This Book Has a Translator","title-short": "Book With a Translator","translator": [{"family": "Translator","given": "Book"}],"type": "book"},{"id": "mauthorBookEditorThis2021","author": [{"family": "Mauthor","given": "Book"}],"citation-key": "mauthorBookEditorThis2021","edition": "2d","editor": [{"family": "Editor","given": "Book"}],"issued": {"date-parts": [[2021]]},"title": "Book With an Editor: This Book Has an Editor","title-short": "Book With an Editor","type": "book"},{"id": "nauthorMultiVolumeBookThis2021","author": [{"family": "Nauthor","given": "Book"}],"citation-key": "nauthorMultiVolumeBookThis2021","issued": {"date-parts": [[2021]]},"title": "Multi-Volume Book: This Book Is One of Several Volumes","title-short": "Multi-Volume Book","type": "book","volume": "10"},{"id": "MaboQueensland1992","authority": "High Court of Australia","citation-key": "MaboQueensland1992","container-title": "CLR","issued": {"date-parts": [[1992]]},"number": "[1992] HCA 23","page": "1","title": "Mabo v. Queensland (No 2)","title-short": "Mabo","type": "legal_case","volume": "175"},{"id": "PlaintiffDefendant1991","authority": "1st Cir.","citation-key": "PlaintiffDefendant1991","container-title": "F.3d","issued": {"date-parts": [[1991]]},"page": "1","title": "Plaintiff A v. Defendant A","title-short": "Plaintiff A","type": "legal_case","volume": "100"},{"id": "PlaintiffDefendant1992","authority": "2d Cir.","citation-key": "PlaintiffDefendant1992","container-title": "F.3d","issued": {"date-parts": [[1992]]},"page": "200","title": "Plaintiff B v. Defendant B","title-short": "Plaintiff B","type": "legal_case","volume": "2"},{"id": "PlaintiffDefendant1993","authority": "3d Cir.","citation-key": "PlaintiffDefendant1993","container-title": "F.3d","issued": {"date-parts": [[1993]]},"page": "33","title": "Plaintiff C v. Defendant C","title-short": "Plaintiff C","type": "legal_case","volume": "333"},{"id": "PlaintiffDefendant1994","authority": "4th Cir.","citation-key": "PlaintiffDefendant1994","container-title": "F.3d","issued": {"date-parts": [[1994]]},"page": "444","title": "Plaintiff D v. Defendant D","title-short": "Plaintiff D","type": "legal_case","volume": "44"},{"id": "PlaintiffDefendant1995","authority": "5th Cir.","citation-key": "PlaintiffDefendant1995","container-title": "F.3d","issued": {"date-parts": [[1995]]},"page": "555","title": "Plaintiff E v. Defendant E","title-short": "Plaintiff E","type": "legal_case","volume": "5"},{"id": "PlaintiffDefendant1996","authority": "6th Cir.","citation-key": "PlaintiffDefendant1996","container-title": "F.3d","issued": {"date-parts": [[1996]]},"page": "6","title": "Plaintiff F v. Defendant F","title-short": "Plaintiff F","type": "legal_case","volume": "600"},{"id": "PlaintiffDefendant1997","authority": "7th Cir.","citation-key": "PlaintiffDefendant1997","container-title": "F.3d","issued": {"date-parts": [[1997]]},"page": "77","title": "Plaintiff G v. Defendant G","title-short": "Plaintiff G","type": "legal_case","volume": "77"},{"id": "PlaintiffDefendant1998","authority": "8th Cir.","citation-key": "PlaintiffDefendant1998","container-title": "F.3d","issued": {"date-parts": [[1998]]},"page": "8","title": "Plaintiff H v. Defendant H","title-short": "Plaintiff H","type": "legal_case","volume": "888"},{"id": "PlaintiffDefendant1999","authority": "9th Cir.","citation-key": "PlaintiffDefendant1999","container-title": "F.3d","issued": {"date-parts": [[1999]]},"page": "9","title": "Plaintiff I v. Defendant I","title-short": "Plaintiff I","type": "legal_case","volume": "9"},{"id": "PlaintiffDefendant2000","authority": "10th Cir.","citation-key": "PlaintiffDefendant2000","container-title": "F.3d","issued": {"date-parts": [[2000]]},"page": "1000","title": "Plaintiff J v. Defendant J","title-short": "Plaintiff J","type": "legal_case","volume": "10"},{"id": "PlaintiffDefendant2001","authority": "11th Cir.","citation-key": "PlaintiffDefendant2001","container-title": "F.3d","issued": {"date-parts": [[2001]]},"page": "1111","title": "Plaintiff K v. Defendant K","title-short": "Plaintiff K","type": "legal_case","volume": "111"},{"id": "PlaintiffDefendant2020","authority": "D.C. Cir.","citation-key": "PlaintiffDefendant2020","container-title": "F.4th","issued": {"date-parts": [[2020]]},"page": "434","title": "Plaintiff L v. Defendant L","title-short": "Plaintiff L","type": "legal_case","volume": "43"},{"id": "PlaintiffDefendant2023","authority": "Fed. Cir.","citation-key": "PlaintiffDefendant2023","container-title": "F.4th","issued": {"date-parts": [[2023]]},"page": "345","title": "Plaintiff M v. Defendant M","title-short": "Plaintiff M","type": "legal_case","volume": "543"},{"id": "UnitedStatesJones2022","authority": "1st Cir.","citation-key": "UnitedStatesJones2022","container-title": "F.4th","issued": {"date-parts": [[2022]]},"page": "5309","references":"(per curiam)","title": "United States v. Jones","title-short": "Jones","type": "legal_case","volume": "867"}]"#;
+
+        pub const TESTCSL: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<style xmlns="http://purl.org/net/xbiblio/csl" class="note" version="1.0">
+  <info><title>Test Note</title><id>test-note</id><updated>2024-01-01T00:00:00+00:00</updated></info>
+  <citation>
+    <layout suffix=".">
+      <choose>
+        <if position="ibid-with-locator"><group delimiter=", "><text term="ibid"/><text variable="locator"/></group></if>
+        <else-if position="ibid"><text term="ibid"/></else-if>
+        <else-if position="subsequent"><group delimiter=", "><names variable="author"><name form="short"/></names><text variable="locator"/></group></else-if>
+        <else><group delimiter=", "><names variable="author"/><text variable="title" font-style="italic"/><date variable="issued"><date-part name="year"/></date><text variable="locator"/></group></else>
+      </choose>
+    </layout>
+  </citation>
+  <bibliography>
+    <sort><key variable="author"/></sort>
+    <layout suffix="."><group delimiter=". "><names variable="author"><name name-as-sort-order="first"/></names><text variable="title" font-style="italic"/><date variable="issued"><date-part name="year"/></date></group></layout>
+  </bibliography>
+</style>"#;
     }
 }
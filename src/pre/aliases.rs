@@ -0,0 +1,151 @@
+//! This module contains functionality related to citation-key alias files.
+//!
+//! Better BibTeX regenerates citation keys when a source's metadata changes,
+//! which breaks documents that cite the old keys. An alias file maps old keys
+//! to new ones, so those documents still build and can be rekeyed.
+
+use super::{
+    error::{self, SourceError},
+    lexer::{self, Token, TokenType},
+    parser,
+};
+use ron::de::from_str;
+use slog::{debug, o};
+use std::collections::HashMap;
+
+pub type Aliases = HashMap<String, String>;
+
+/// Create the aliases map.
+pub fn build_aliases(input: &str) -> Result<Aliases, String> {
+    match from_str(input) {
+        Ok(a) => {
+            debug!(slog_scope::logger(), "Alias file parsed");
+            Ok(a)
+        }
+        Err(e) => {
+            let err_msg = format!("error deserializing the alias file—{}", e);
+            Err(err_msg)
+        }
+    }
+}
+
+/// Follow a key's aliases to its current key.
+///
+/// Keys can be regenerated more than once, so aliases are followed until a key
+/// without an alias is reached. A cycle stops at the last new key.
+pub fn resolve<'a>(aliases: &'a Aliases, key: &'a str) -> &'a str {
+    let mut current = key;
+    for _ in 0..aliases.len() {
+        match aliases.get(current) {
+            Some(next) if next != current => current = next,
+            _ => break,
+        }
+    }
+    current
+}
+
+/// Replace aliased citation keys in a Markdown document.
+///
+/// Only the references the lexer finds in citations (including those in the
+/// body text and in parentheticals) are rewritten, so text outside citations
+/// is never touched. Returns the new document and the number of references
+/// changed.
+pub fn rekey(input: &str, aliases: &str) -> Result<(String, usize), String> {
+    let aliases = slog_scope::scope(
+        &slog_scope::logger().new(o!("fn" => "build_aliases()")),
+        || build_aliases(aliases),
+    )?;
+
+    let tokens = slog_scope::scope(&slog_scope::logger().new(o!("fn" => "lexer()")), || {
+        lexer::lexer(input)
+    })
+    .map_err(|e| error::describe_all(&e, input))?;
+
+    let mut references = Vec::new();
+    collect_references(tokens, &mut references).map_err(|e| error::describe_all(&e, input))?;
+
+    let mut output = String::with_capacity(input.len());
+    let mut last = 0;
+    let mut count = 0;
+
+    for token in &references {
+        let old = parser::reference_key(token.contents);
+        let new = resolve(&aliases, old);
+        if new == old {
+            continue;
+        }
+
+        // Only the key is replaced, so a reference keeps its syntax.
+        let (Some(token_span), Some(key)) =
+            (token.span(input), error::span_of(old, token.contents))
+        else {
+            continue;
+        };
+        let start = token_span.start + key.start;
+        output.push_str(&input[last..start]);
+        output.push_str(new);
+        last = start + key.len();
+        count += 1;
+    }
+    output.push_str(&input[last..]);
+
+    debug!(slog_scope::logger(), "{} references rekeyed", count);
+    Ok((output, count))
+}
+
+/// Collect the reference tokens, including those in parentheticals, in order.
+fn collect_references<'a>(
+    tokens: Vec<Token<'a>>,
+    references: &mut Vec<Token<'a>>,
+) -> Result<(), Vec<SourceError<'a>>> {
+    for token in tokens {
+        match token.token_type {
+            TokenType::Reference | TokenType::Narrative => references.push(token),
+            TokenType::Parenthetical => {
+                collect_references(lexer::parenthetical_lexer(token.contents)?, references)?
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_build() {
+        let ron_string = r#"
+{
+    "smith2020":"smithTitle2020",
+    "jones2019":"jonesOther2019",
+}
+"#;
+        let output = build_aliases(ron_string).unwrap();
+
+        assert_eq!(&output["smith2020"], "smithTitle2020");
+        assert_eq!(&output["jones2019"], "jonesOther2019");
+    }
+
+    #[test]
+    fn chains() {
+        let aliases = build_aliases(r#"{"a":"b", "b":"c", "x":"y", "y":"x"}"#).unwrap();
+
+        assert_eq!(resolve(&aliases, "a"), "c");
+        assert_eq!(resolve(&aliases, "c"), "c");
+        assert!(["x", "y"].contains(&resolve(&aliases, "x")));
+    }
+
+    #[test]
+    fn rekeys_citations_only() {
+        let input = "Text [@old] stays.^[See [@old] at 5; [@other].]\n^[[see @old, 2; @other].]\n^[[@other] (quoting [@old] at 3).]\n";
+        let (output, count) = rekey(input, r#"{"old":"new"}"#).unwrap();
+
+        assert_eq!(
+            output,
+            "Text [@old] stays.^[See [@new] at 5; [@other].]\n^[[see @new, 2; @other].]\n^[[@other] (quoting [@new] at 3).]\n"
+        );
+        assert_eq!(count, 3);
+    }
+}
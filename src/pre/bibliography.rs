@@ -0,0 +1,138 @@
+//! This module contains functionality for building a bibliography.
+//!
+//! Only OSCOLA uses a bibliography. It is split into a table of cases and a
+//! bibliography of secondary sources, each sorted alphabetically.
+
+use super::{
+    csljson::NameVariable,
+    sourcemap::{buildsource, SourceMap, SourceType},
+};
+use crate::config::Style;
+use ansi_term::Color;
+use slog::{debug, warn};
+
+/// Build the bibliography.
+///
+/// Returns the markdown for the table of cases and the bibliography, to be
+/// added to the end of the output. Any style other than OSCOLA returns an empty
+/// string and warns the user.
+pub fn bibliography(source_map: &SourceMap, style: Style) -> String {
+    debug!(slog_scope::logger(), "Building bibliography...");
+
+    if style != Style::Oscola {
+        warn!(
+            slog_scope::logger(),
+            "Bibliographies are only available for OSCOLA and CSL styles; skipping the bibliography"
+        );
+        eprintln!(
+            "  {} Bibliographies are only available for OSCOLA and CSL styles; skipping the bibliography",
+            Color::Yellow.paint("WARN"),
+        );
+        return String::new();
+    }
+
+    let mut cases: Vec<String> = Vec::new();
+    let mut secondary: Vec<String> = Vec::new();
+
+    for source in source_map.values() {
+        let long_cite = source.long_cite_no_pin();
+
+        if source.source_type == SourceType::Case {
+            // Case names are not italicized in the table of cases.
+            let title = source
+                .csl_source
+                .title
+                .as_ref()
+                .unwrap()
+                .replace(" v. ", " v ");
+            cases.push(long_cite.replacen(&format!("*{}*", title), &title, 1));
+        } else if source.csl_source.author.is_some() {
+            // Authors are listed surname first, followed by their initials.
+            let author = source.csl_source.author.as_ref().unwrap();
+            let long_author = buildsource::build_oscola_authors(author);
+            secondary.push(long_cite.replacen(
+                &long_author,
+                &build_bibliography_authors(author),
+                1,
+            ));
+        } else {
+            secondary.push(long_cite);
+        }
+    }
+
+    cases.sort_by_key(|e| sort_key(e));
+    secondary.sort_by_key(|e| sort_key(e));
+
+    let mut output = String::new();
+
+    if !cases.is_empty() {
+        output.push_str("\n\n# Table of Cases {.unnumbered}\n\n");
+        output.push_str(&cases.join("\n\n"));
+    }
+
+    if !secondary.is_empty() {
+        output.push_str("\n\n# Bibliography {.unnumbered}\n\n");
+        output.push_str(&secondary.join("\n\n"));
+    }
+
+    if !output.is_empty() {
+        output.push('\n');
+    }
+
+    debug!(slog_scope::logger(), "Bibliography built");
+    output
+}
+
+/// Builds the authors for a bibliography entry.
+///
+/// Each author is listed as surname followed by initials (without periods),
+/// e.g., "Smith JA." Authors are joined like they are in footnotes.
+fn build_bibliography_authors(name_vector: &[NameVariable]) -> String {
+    let names: Vec<String> = name_vector
+        .iter()
+        .map(|name| {
+            if let Some(literal) = &name.literal {
+                return literal.to_string();
+            }
+
+            let mut bib_name = String::new();
+            if let Some(particle) = &name.non_dropping_particle {
+                bib_name.push_str(particle);
+                bib_name.push(' ');
+            }
+            if let Some(family) = &name.family {
+                bib_name.push_str(family);
+            }
+            if let Some(given) = &name.given {
+                let initials: String = given
+                    .split(|c: char| c.is_whitespace() || c == '-')
+                    .filter(|part| !is_roman_numeral(part))
+                    .filter_map(|part| part.chars().next())
+                    .filter(|c| c.is_uppercase())
+                    .collect();
+                if !initials.is_empty() {
+                    bib_name.push(' ');
+                    bib_name.push_str(&initials);
+                }
+            }
+            bib_name
+        })
+        .collect();
+
+    buildsource::join_oscola_names(&names)
+}
+
+/// Whether part of a name is a roman numeral (e.g., the "III" in "Article
+/// III"), which should not become an initial.
+fn is_roman_numeral(part: &str) -> bool {
+    part.len() > 1 && part.chars().all(|c| "IVX".contains(c))
+}
+
+/// The key for sorting entries alphabetically, ignoring markup.
+fn sort_key(entry: &str) -> String {
+    entry
+        .chars()
+        .filter(|c| *c != '*' && *c != '\'')
+        .collect::<String>()
+        .to_lowercase()
+}
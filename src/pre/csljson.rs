@@ -1,79 +1,634 @@
 //! The module contains functionality related to creating the CSL libary.
 
-use serde::{de, Deserialize, Deserializer};
-use serde_json::Value;
-use slog::debug;
+mod biblatex;
+mod ris;
+mod yaml;
+
+pub use biblatex::build_bib_lib;
+pub use ris::build_ris_lib;
+pub use yaml::build_yaml_lib;
+
+use super::aliases::{self, Aliases};
+use ansi_term::Color;
+use serde::{
+    de::{self, DeserializeSeed, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::{value::RawValue, Value};
+use slog::{debug, warn};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+/// The size (in bytes) at which a CSL JSON library is filtered down to the
+/// cited sources while it's read. Smaller libraries are read in full.
+pub const FILTER_THRESHOLD: usize = 1 << 20;
+
+/// The CSL string variables that can be set in the `note` field and that
+/// Supra keeps in `extra`.
+const NOTE_STRING_VARIABLES: [&str; 45] = [
+    "abstract",
+    "annote",
+    "archive",
+    "archive_collection",
+    "archive_location",
+    "archive-place",
+    "call-number",
+    "chapter-number",
+    "collection-number",
+    "collection-title",
+    "dimensions",
+    "DOI",
+    "event-place",
+    "event-title",
+    "genre",
+    "ISBN",
+    "ISSN",
+    "issue",
+    "keyword",
+    "language",
+    "license",
+    "medium",
+    "number-of-pages",
+    "number-of-volumes",
+    "original-publisher",
+    "original-publisher-place",
+    "original-title",
+    "page-first",
+    "part-number",
+    "part-title",
+    "PMCID",
+    "PMID",
+    "printing-number",
+    "publisher-place",
+    "reviewed-genre",
+    "reviewed-title",
+    "scale",
+    "section",
+    "source",
+    "status",
+    "supplement-number",
+    "version",
+    "volume-title",
+    "year-suffix",
+    "event",
+];
+
+/// The CSL date variables, other than `issued`, that can be set in the `note`
+/// field.
+const NOTE_DATE_VARIABLES: [&str; 5] = [
+    "accessed",
+    "available-date",
+    "event-date",
+    "original-date",
+    "submitted",
+];
+
+/// The CSL name variables, other than those Supra uses, that can be set in the
+/// `note` field.
+const NOTE_NAME_VARIABLES: [&str; 23] = [
+    "chair",
+    "collection-editor",
+    "compiler",
+    "composer",
+    "container-author",
+    "contributor",
+    "curator",
+    "director",
+    "editorial-director",
+    "executive-producer",
+    "guest",
+    "host",
+    "illustrator",
+    "interviewer",
+    "narrator",
+    "organizer",
+    "original-author",
+    "performer",
+    "producer",
+    "recipient",
+    "reviewed-author",
+    "script-writer",
+    "series-creator",
+];
 
 /// Struct holding metadata for a source in a CSL JSON file.
 ///
 /// This struct holds the data for each source deserialized from a CSL JSON
-/// library. Note, this struct holds only the data that Supra currently uses.
-/// Any other data is discarded.
+/// library. The fields Supra uses have their own members; every other field is
+/// kept in `extra`, so that CSL styles still get it.
 ///
 /// Based on the [JSON schema for CSL data].
 ///
-/// Note, some fields in the CSL JSON schema take multiple types. This is not
-/// yet supported. Instead, each field's type is based on the type that Zotero
-/// and Better BibTeX output.
+/// Many fields in the schema can be either strings or numbers (Zotero outputs
+/// strings, but other tools don't). Every string field here accepts both.
 ///
 /// [JSON schema for CSL data]:
 /// (https://github.com/citation-style-language/schema/blob/master/schemas/input/csl-data.json).
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, PartialEq)]
 pub struct CSLSource {
-    #[serde(rename(deserialize = "type"))]
+    #[serde(rename(deserialize = "type"), default, deserialize_with = "de_string")]
     pub source_type: Option<String>,
+    #[serde(deserialize_with = "de_id")]
     pub id: String, // The only non-optional field
     pub author: Option<Vec<NameVariable>>,
     pub editor: Option<Vec<NameVariable>>,
     pub translator: Option<Vec<NameVariable>>,
     pub issued: Option<DateVariable>,
-    #[serde(rename(deserialize = "container-title"))]
+    #[serde(
+        rename(deserialize = "container-title"),
+        default,
+        deserialize_with = "de_string"
+    )]
     pub container_title: Option<String>,
-    #[serde(rename(deserialize = "container-title-short"))]
+    #[serde(
+        rename(deserialize = "container-title-short"),
+        default,
+        deserialize_with = "de_string"
+    )]
     pub container_title_short: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
     pub authority: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
+    pub jurisdiction: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
+    pub division: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
     pub edition: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
+    pub number: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
     pub page: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
+    pub publisher: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
     pub title: Option<String>,
-    #[serde(rename(deserialize = "title-short"))]
+    #[serde(
+        rename(deserialize = "title-short"),
+        default,
+        deserialize_with = "de_string"
+    )]
     pub title_short: Option<String>,
-    #[serde(rename(deserialize = "URL"))]
+    #[serde(rename(deserialize = "URL"), default, deserialize_with = "de_string")]
     pub url: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
     pub volume: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
     pub references: Option<String>,
+    /// Every other field, as it appeared in the library.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+    /// Supra-specific overrides from the `note` field.
+    #[serde(skip)]
+    pub supra: SupraOverrides,
+}
+
+/// Struct holding the supra-specific keys from a source's `note` field.
+#[derive(Debug, Default, PartialEq)]
+pub struct SupraOverrides {
+    /// Replaces the name of the source in short cites (`supra-short-form`).
+    pub short_form: Option<String>,
+    /// Forces a hereinafter, with this text (`supra-hereinafter`).
+    pub hereinafter: Option<String>,
+    /// Treats the source as this type (`supra-type`).
+    pub source_type: Option<String>,
 }
 
 /// Struct holding the CSL JSON `name-variable` data.
-#[derive(Debug, Deserialize)]
+///
+/// Institutions and other names that can't be split into parts are given as a
+/// `literal`. Other fields, like `comma-suffix` and `parse-names`, are kept in
+/// `extra`.
+#[derive(Debug, Default, Deserialize, PartialEq)]
 pub struct NameVariable {
+    #[serde(default, deserialize_with = "de_string")]
     pub family: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
     pub given: Option<String>,
-    #[serde(rename(deserialize = "non-dropping-particle"))]
+    #[serde(
+        rename(deserialize = "dropping-particle"),
+        default,
+        deserialize_with = "de_string"
+    )]
+    pub dropping_particle: Option<String>,
+    #[serde(
+        rename(deserialize = "non-dropping-particle"),
+        default,
+        deserialize_with = "de_string"
+    )]
     pub non_dropping_particle: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
     pub suffix: Option<String>,
+    #[serde(default, deserialize_with = "de_string")]
+    pub literal: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 /// Struct holding the CSL JSON `date-variable` data.
 ///
-/// Note, `date-parts` data is a collection of collections of one-to-three
-/// values indicating the year, month, and day.
-#[derive(Debug, Deserialize)]
+/// Note, `date-parts` data is a collection of one or two (for a range)
+/// collections of one-to-three values indicating the year, month, and day. A
+/// date given only as `raw` is parsed into `date_parts` if possible. A date
+/// given only as `literal` has no `date_parts`.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(try_from = "Value")]
 pub struct DateVariable {
-    // It seems like BetterBibTex is outputting date parts as strings or
-    // integers, so the deserializer needs to deal with both.
-    #[serde(rename(deserialize = "date-parts"), deserialize_with = "de_date")]
     pub date_parts: Option<Vec<Vec<u32>>>,
-    //pub season: Option<u32>,
+    pub season: Option<String>,
+    pub circa: bool,
+    pub literal: Option<String>,
+    pub raw: Option<String>,
+}
+
+impl TryFrom<Value> for DateVariable {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let mut date = DateVariable::default();
+
+        let object = match value {
+            // Some tools give the date as a plain string or year.
+            Value::String(s) => {
+                date.date_parts = parse_raw_date(&s);
+                date.raw = Some(s);
+                return Ok(date);
+            }
+            Value::Number(n) => {
+                date.date_parts = parse_raw_date(&n.to_string());
+                return Ok(date);
+            }
+            Value::Object(o) => o,
+            _ => return Err("a date must be an object, string, or number".to_string()),
+        };
+
+        if let Some(parts) = object.get("date-parts") {
+            date.date_parts = de_date_parts(parts)?;
+        }
+
+        date.season = object.get("season").and_then(value_to_string);
+        date.literal = object.get("literal").and_then(value_to_string);
+        date.raw = object.get("raw").and_then(value_to_string);
+        date.circa = match object.get("circa") {
+            Some(Value::Bool(b)) => *b,
+            Some(Value::Number(n)) => n.as_u64() != Some(0),
+            Some(Value::String(s)) => !s.is_empty() && s != "0" && s != "false",
+            _ => false,
+        };
+
+        if date.date_parts.is_none() {
+            date.date_parts = date.raw.as_deref().and_then(parse_raw_date);
+        }
+
+        Ok(date)
+    }
+}
+
+impl CSLSource {
+    /// Apply the `key: value` lines in the `note` field.
+    ///
+    /// Better BibTeX and Zotero leave CSL variables that have no field of their
+    /// own in the note, one per line, *e.g.*, `original-date: 1890` or
+    /// `Container Title Short: Harv. L. Rev.` Those lines override the
+    /// source's variables; names are given as `Family || Given`. Supra's own
+    /// keys (`supra-short-form`, `supra-hereinafter`, and `supra-type`) are
+    /// read the same way. The lines that are used are removed from the note.
+    pub fn apply_note_overrides(&mut self) {
+        let note = match self.extra.get("note") {
+            Some(Value::String(n)) => n.to_string(),
+            _ => return,
+        };
+
+        let mut remaining = Vec::new();
+        for line in note.lines() {
+            let applied = match line.split_once(':') {
+                Some((name, value)) => self.apply_override(name.trim(), value.trim()),
+                None => false,
+            };
+            if !applied {
+                remaining.push(line);
+            }
+        }
+
+        let remaining = remaining.join("\n");
+        if remaining.trim().is_empty() {
+            self.extra.remove("note");
+        } else {
+            self.extra.insert(
+                "note".to_string(),
+                Value::String(remaining.trim().to_string()),
+            );
+        }
+    }
+
+    /// Apply one override, returning whether the name was recognized.
+    fn apply_override(&mut self, name: &str, value: &str) -> bool {
+        if value.is_empty() || name.is_empty() {
+            return false;
+        }
+
+        // Zotero writes variables either as-is or title-cased with spaces.
+        let name = name.to_lowercase().replace(' ', "-");
+        let value = value.to_string();
+
+        let field = match name.as_str() {
+            "supra-short-form" => &mut self.supra.short_form,
+            "supra-hereinafter" => &mut self.supra.hereinafter,
+            "supra-type" => &mut self.supra.source_type,
+            "type" => &mut self.source_type,
+            "container-title" => &mut self.container_title,
+            "container-title-short" | "journal-abbreviation" => &mut self.container_title_short,
+            "authority" => &mut self.authority,
+            "jurisdiction" => &mut self.jurisdiction,
+            "division" => &mut self.division,
+            "edition" => &mut self.edition,
+            "number" => &mut self.number,
+            "page" => &mut self.page,
+            "publisher" => &mut self.publisher,
+            "title" => &mut self.title,
+            "title-short" => &mut self.title_short,
+            "url" => &mut self.url,
+            "volume" => &mut self.volume,
+            "issued" => {
+                self.issued = DateVariable::try_from(Value::String(value)).ok();
+                return true;
+            }
+            "author" | "editor" | "translator" => {
+                let names = match name.as_str() {
+                    "author" => &mut self.author,
+                    "editor" => &mut self.editor,
+                    _ => &mut self.translator,
+                };
+                let (family, given) = split_note_name(&value);
+                names.get_or_insert_with(Vec::new).push(match given {
+                    Some(g) => NameVariable {
+                        family: Some(family),
+                        given: Some(g),
+                        ..Default::default()
+                    },
+                    None => NameVariable {
+                        literal: Some(family),
+                        ..Default::default()
+                    },
+                });
+                return true;
+            }
+            _ => {
+                if let Some(variable) = NOTE_STRING_VARIABLES
+                    .iter()
+                    .find(|v| v.eq_ignore_ascii_case(&name))
+                {
+                    self.extra
+                        .insert(variable.to_string(), Value::String(value));
+                } else if NOTE_DATE_VARIABLES.contains(&name.as_str()) {
+                    let mut date = serde_json::Map::new();
+                    date.insert("raw".to_string(), Value::String(value));
+                    self.extra.insert(name, Value::Object(date));
+                } else if NOTE_NAME_VARIABLES.contains(&name.as_str()) {
+                    let (family, given) = split_note_name(&value);
+                    let mut person = serde_json::Map::new();
+                    match given {
+                        Some(g) => {
+                            person.insert("family".to_string(), Value::String(family));
+                            person.insert("given".to_string(), Value::String(g));
+                        }
+                        None => {
+                            person.insert("literal".to_string(), Value::String(family));
+                        }
+                    }
+                    match self.extra.get_mut(&name) {
+                        Some(Value::Array(a)) => a.push(Value::Object(person)),
+                        _ => {
+                            self.extra
+                                .insert(name, Value::Array(vec![Value::Object(person)]));
+                        }
+                    }
+                } else {
+                    return false;
+                }
+                return true;
+            }
+        };
+
+        *field = Some(value);
+        true
+    }
+
+    /// Use the English variants of titles from Juris-M's `multi` field.
+    ///
+    /// Juris-M exports multilingual variants as `"multi": {"_keys": {"title":
+    /// {"en": ...}}}`. Supra's styles cite in English, so an English variant of
+    /// the title, short title, or container title replaces the original. The
+    /// original title is kept as `original-title`.
+    pub fn apply_english_variants(&mut self) {
+        let Some(Value::Object(keys)) = self.extra.get("multi").and_then(|m| m.get("_keys")) else {
+            return;
+        };
+
+        let english = |variable: &str| {
+            let Some(Value::Object(variants)) = keys.get(variable) else {
+                return None;
+            };
+            variants
+                .iter()
+                .find(|(lang, _)| *lang == "en" || lang.starts_with("en-"))
+                .and_then(|(_, v)| value_to_string(v))
+        };
+
+        let title = english("title");
+        let title_short = english("title-short");
+        let container_title = english("container-title");
+        let container_title_short = english("container-title-short");
+
+        if let Some(title) = title {
+            if self.title.as_ref() != Some(&title) {
+                if let Some(original) = self.title.take() {
+                    self.extra
+                        .entry("original-title".to_string())
+                        .or_insert(Value::String(original));
+                }
+                self.title = Some(title);
+            }
+        }
+        if title_short.is_some() {
+            self.title_short = title_short;
+        }
+        if container_title.is_some() {
+            self.container_title = container_title;
+        }
+        if container_title_short.is_some() {
+            self.container_title_short = container_title_short;
+        }
+    }
+}
+
+/// Split a name from the `note` field, given as `Family || Given`.
+///
+/// A name without `||` is a literal.
+fn split_note_name(value: &str) -> (String, Option<String>) {
+    match value.split_once("||") {
+        Some((family, given)) => (family.trim().to_string(), Some(given.trim().to_string())),
+        None => (value.to_string(), None),
+    }
+}
+
+/// A library of sources, keyed by id.
+///
+/// A library is built from one or more layers, one for each library file. A
+/// source in a later layer replaces a source with the same id in an earlier
+/// layer, so a personal library can override a shared one.
+#[derive(Debug, Default)]
+pub struct Library {
+    layers: Vec<String>,
+    sources: HashMap<String, (CSLSource, usize)>,
+    aliases: Aliases,
+}
+
+impl Library {
+    /// Add a layer of sources on top of the library.
+    ///
+    /// Duplicate ids within the layer, and ids that replace a different source
+    /// in an earlier layer, are reported.
+    pub fn add_layer(&mut self, sources: Vec<CSLSource>, name: &str) {
+        let layer = self.layers.len();
+        self.layers.push(name.to_string());
+
+        for mut source in sources {
+            source.apply_note_overrides();
+            source.apply_english_variants();
+
+            if let Some((existing, existing_layer)) = self.sources.get(&source.id) {
+                let message = if *existing_layer == layer {
+                    Some(format!(
+                        "{} appears more than once in {}; using the last",
+                        source.id, name
+                    ))
+                } else if *existing != source {
+                    Some(format!(
+                        "{} in {} overrides a different entry in {}",
+                        source.id, name, self.layers[*existing_layer]
+                    ))
+                } else {
+                    None
+                };
+
+                if let Some(m) = message {
+                    warn!(slog_scope::logger(), "{}", m);
+                    eprintln!("  {} {}", Color::Yellow.paint("WARN"), m);
+                }
+            }
+
+            self.sources.insert(source.id.to_string(), (source, layer));
+        }
+    }
+
+    /// Set the aliases for keys that have changed since they were cited.
+    pub fn set_aliases(&mut self, aliases: Aliases) {
+        self.aliases = aliases;
+    }
+
+    /// Get the current id for an id, following any aliases.
+    ///
+    /// An id in the library is never treated as an alias.
+    pub fn resolve<'a>(&'a self, id: &'a str) -> &'a str {
+        if self.sources.contains_key(id) {
+            id
+        } else {
+            aliases::resolve(&self.aliases, id)
+        }
+    }
+
+    /// Get a source by its id or an alias.
+    pub fn get(&self, id: &str) -> Option<&CSLSource> {
+        self.sources.get(self.resolve(id)).map(|(s, _)| s)
+    }
+
+    /// Get a mutable source by its id or an alias.
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut CSLSource> {
+        let id = self.resolve(id).to_string();
+        self.sources.get_mut(&id).map(|(s, _)| s)
+    }
+
+    /// Get the index of the layer a source came from.
+    pub fn layer(&self, id: &str) -> Option<usize> {
+        self.sources.get(self.resolve(id)).map(|(_, l)| *l)
+    }
+
+    /// Iterate over the sources, in no particular order.
+    pub fn sources(&self) -> impl Iterator<Item = &CSLSource> {
+        self.sources.values().map(|(s, _)| s)
+    }
 }
 
 /// Deserialize the CSL JSON library.
 ///
-/// Deserialize a string of JSON into a collection of [`CSLSource`]s.
+/// Deserialize a string of JSON into a collection of [`CSLSource`]s. An entry
+/// that can't be deserialized is skipped with a warning giving its id, rather
+/// than failing the whole library.
 pub fn build_csl_lib(csl_string: &str) -> Result<Vec<CSLSource>, String> {
     debug!(slog_scope::logger(), "Starting CSL JSON parsing...");
-    match serde_json::from_str(csl_string) {
+    let entries: Vec<&RawValue> = match serde_json::from_str(csl_string) {
+        Ok(e) => e,
+        Err(e) => return Err(format!("error deserializing the CSL JSON—{}", e)),
+    };
+
+    let library = entries
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, raw)| deserialize_entry(raw, index))
+        .collect();
+
+    debug!(slog_scope::logger(), "CSL JSON parsed");
+    Ok(library)
+}
+
+/// Deserialize one entry of a CSL JSON library.
+///
+/// An entry that can't be deserialized is reported and skipped. An entry
+/// without an id is identified by its place in the library.
+fn deserialize_entry(raw: &RawValue, index: usize) -> Option<CSLSource> {
+    match serde_json::from_str(raw.get()) {
+        Ok(s) => Some(s),
+        Err(e) => {
+            let name = raw_id(raw).unwrap_or_else(|_| format!("number {}", index + 1));
+            warn!(
+                slog_scope::logger(),
+                "Skipping CSL JSON entry {}: {}", name, e
+            );
+            eprintln!(
+                "  {} Skipping CSL JSON entry {}: {}",
+                Color::Yellow.paint("WARN"),
+                Color::Blue.paint(&name),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Deserialize only the cited sources from a CSL JSON library.
+///
+/// Very large libraries take a long time to deserialize in full. So the library
+/// is streamed one entry at a time: each entry is read as raw JSON and only its
+/// id is parsed. Entries that aren't among the `keys` are skipped without being
+/// deserialized.
+pub fn build_cited_csl_lib(
+    csl_string: &str,
+    keys: &HashSet<&str>,
+) -> Result<Vec<CSLSource>, String> {
+    debug!(
+        slog_scope::logger(),
+        "Starting filtered CSL JSON parsing for {} keys...",
+        keys.len()
+    );
+    let mut deserializer = serde_json::Deserializer::from_str(csl_string);
+    match CitedSources(keys)
+        .deserialize(&mut deserializer)
+        .and_then(|r| deserializer.end().map(|_| r))
+    {
         Ok(r) => {
-            debug!(slog_scope::logger(), "CSL JSON parsed");
+            debug!(slog_scope::logger(), "CSL JSON parsed; kept {}", r.len());
             Ok(r)
         }
         Err(e) => {
@@ -83,44 +638,151 @@ pub fn build_csl_lib(csl_string: &str) -> Result<Vec<CSLSource>, String> {
     }
 }
 
+/// Get the id of a raw CSL JSON entry without deserializing the rest of it.
+pub fn raw_id(raw: &RawValue) -> Result<String, serde_json::Error> {
+    /// Just the id of an entry; every other field is skipped.
+    #[derive(Deserialize)]
+    struct IdOnly {
+        #[serde(deserialize_with = "de_id")]
+        id: String,
+    }
+
+    serde_json::from_str::<IdOnly>(raw.get()).map(|i| i.id)
+}
+
+/// A seed that deserializes the cited entries of a CSL JSON array.
+struct CitedSources<'a>(&'a HashSet<&'a str>);
+
+impl<'de> DeserializeSeed<'de> for CitedSources<'_> {
+    type Value = Vec<CSLSource>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for CitedSources<'_> {
+    type Value = Vec<CSLSource>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of CSL JSON entries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut sources = Vec::with_capacity(self.0.len());
+
+        let mut index = 0;
+        while let Some(raw) = seq.next_element::<&RawValue>()? {
+            // An entry without an id can't be cited.
+            if raw_id(raw).is_ok_and(|id| self.0.contains(id.as_str())) {
+                sources.extend(deserialize_entry(raw, index));
+            }
+            index += 1;
+        }
+
+        Ok(sources)
+    }
+}
+
+/// Converts a string, number, or boolean into a string.
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Deserialize a field that can be a string or a number.
+fn de_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(None),
+        Value::Array(_) | Value::Object(_) => {
+            Err(de::Error::custom("expected a string or a number"))
+        }
+        v => Ok(value_to_string(&v)),
+    }
+}
+
+/// Deserialize an id, which can be a string or a number.
+fn de_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    de_string(deserializer)?.ok_or_else(|| de::Error::custom("the id can't be null"))
+}
+
 /// Deserialize `date_parts`, which can be strings or u32s.
 ///
 /// By April 2024, Supra stopped working because the years in the CSL JSON were
 /// strings. Sometime before that, the years were u32s.
 ///
-/// This function is necessary to deserialize either a string or integer
-fn de_date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<Vec<u32>>>, D::Error> {
-    let mut new_inner_vec: Vec<u32> = Vec::new();
+/// An empty `date-parts` is treated as no date.
+fn de_date_parts(value: &Value) -> Result<Option<Vec<Vec<u32>>>, String> {
+    let Value::Array(outer) = value else {
+        return Err("date-parts must be an array".to_string());
+    };
 
-    match Value::deserialize(deserializer)? {
-        Value::Array(old_outer_vec) => {
-            match Value::deserialize(old_outer_vec[0].clone()).unwrap() {
-                Value::Array(old_inner_vec) => {
-                    for v in old_inner_vec {
-                        match Value::deserialize(v).unwrap() {
-                            Value::String(s) => {
-                                let u = s.parse::<u32>().map_err(de::Error::custom)?;
-                                new_inner_vec.push(u);
-                            }
-                            Value::Number(num) => {
-                                let u =
-                                    num.as_u64().ok_or(de::Error::custom("Invalid number"))? as u32;
-                                new_inner_vec.push(u);
-                            }
-                            _ => return Err(de::Error::custom("Wrong type")),
-                        }
-                    }
+    let mut date_parts = Vec::with_capacity(outer.len());
+    for inner in outer.iter().take(2) {
+        let Value::Array(inner) = inner else {
+            return Err("date-parts must be an array of arrays".to_string());
+        };
 
-                    let new_outer_vec = vec![new_inner_vec];
-                    Ok(Some(new_outer_vec))
-                }
-                _ => return Err(de::Error::custom("Wrong type")),
-            }
+        let mut parts = Vec::with_capacity(inner.len());
+        for v in inner.iter().take(3) {
+            let part = match v {
+                Value::String(s) => s
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|e| format!("invalid date part \"{}\": {}", s, e))?,
+                Value::Number(n) => n
+                    .as_u64()
+                    .and_then(|u| u32::try_from(u).ok())
+                    .ok_or(format!("invalid date part {}", n))?,
+                _ => return Err("date parts must be strings or numbers".to_string()),
+            };
+            parts.push(part);
         }
-        _ => return Err(de::Error::custom("Wrong type")),
+
+        if !parts.is_empty() {
+            date_parts.push(parts);
+        }
+    }
+
+    if date_parts.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(date_parts))
     }
 }
 
+/// Parse a raw date like "2021-05-03" or a range like "2020/2021".
+///
+/// If the date isn't in that form, the first four-digit number is taken as the
+/// year.
+fn parse_raw_date(raw: &str) -> Option<Vec<Vec<u32>>> {
+    let date_parts: Vec<Vec<u32>> = raw
+        .split('/')
+        .take(2)
+        .map(|d| {
+            d.trim()
+                .split('-')
+                .take(3)
+                .map_while(|p| p.parse::<u32>().ok())
+                .collect::<Vec<u32>>()
+        })
+        .filter(|d| !d.is_empty())
+        .collect();
+
+    if !date_parts.is_empty() {
+        return Some(date_parts);
+    }
+
+    raw.split(|c: char| !c.is_ascii_digit())
+        .find(|w| w.len() == 4)
+        .and_then(|y| y.parse().ok())
+        .map(|y| vec![vec![y]])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -417,9 +1079,10 @@ mod tests {
     }
 
     #[test]
-    /// Ensure an error for no id with an appropriate error message.
+    /// An entry without an id is skipped, and the rest of the library is kept.
     fn no_id() {
         let json_string = r#"[
+                {"id": "kept", "title": "Kept", "type": "book"},
                 {
                     "author": [
                         {
@@ -440,9 +1103,14 @@ mod tests {
                 }
             ]"#;
 
-        let result = build_csl_lib(json_string);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("missing field `id`"));
+        let result = build_csl_lib(json_string).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].id, "kept");
+
+        // A malformed entry is skipped too, but malformed JSON isn't.
+        let result = build_csl_lib(r#"[{"id": "bad", "author": "Smith"}, {"id": "good"}]"#);
+        assert_eq!(result.unwrap()[0].id, "good");
+        assert!(build_csl_lib(r#"[{"id": "a"},]"#).is_err());
     }
 
     #[test]
@@ -690,13 +1358,97 @@ mod tests {
     #[test]
     /// Test all fields for [`NameVariable`].
     fn names() {
-        // TODO
+        let result = build_csl_lib(
+            r#"[
+                {
+                    "id": "names",
+                    "author": [
+                        {
+                            "family": "Beethoven",
+                            "given": "Ludwig",
+                            "dropping-particle": "van",
+                            "suffix": "Jr.",
+                            "comma-suffix": true,
+                            "static-ordering": false
+                        },
+                        {
+                            "family": "Gogh",
+                            "given": "Vincent",
+                            "non-dropping-particle": "van"
+                        },
+                        {
+                            "literal": "American Law Institute"
+                        }
+                    ]
+                }
+            ]"#,
+        )
+        .unwrap();
+        let author = result[0].author.as_ref().unwrap();
+
+        assert_eq!(author[0].dropping_particle.as_deref(), Some("van"));
+        assert_eq!(author[0].suffix.as_deref(), Some("Jr."));
+        assert_eq!(author[0].extra["comma-suffix"], Value::Bool(true));
+        assert_eq!(author[0].extra["static-ordering"], Value::Bool(false));
+        assert_eq!(author[1].non_dropping_particle.as_deref(), Some("van"));
+        assert_eq!(author[2].family, None);
+        assert_eq!(author[2].literal.as_deref(), Some("American Law Institute"));
     }
 
     #[test]
     /// Test all fields for [`DateVariable`].
     fn dates() {
-        // TODO
+        let result = build_csl_lib(
+            r#"[
+                {"id": "range", "issued": {"date-parts": [[2020, 5], [2021, "6"]]}},
+                {"id": "season", "issued": {"date-parts": [[2021]], "season": 3, "circa": true}},
+                {"id": "raw", "issued": {"raw": "2021-05-03"}},
+                {"id": "rawRange", "issued": {"raw": "2020/2021"}},
+                {"id": "literal", "issued": {"literal": "Michaelmas Term"}},
+                {"id": "empty", "issued": {"date-parts": []}},
+                {"id": "emptyInner", "issued": {"date-parts": [[]]}},
+                {"id": "string", "issued": "2021"}
+            ]"#,
+        )
+        .unwrap();
+        let date = |i: usize| result[i].issued.as_ref().unwrap();
+
+        assert_eq!(date(0).date_parts, Some(vec![vec![2020, 5], vec![2021, 6]]));
+        assert_eq!(date(1).season.as_deref(), Some("3"));
+        assert!(date(1).circa);
+        assert_eq!(date(2).date_parts, Some(vec![vec![2021, 5, 3]]));
+        assert_eq!(date(3).date_parts, Some(vec![vec![2020], vec![2021]]));
+        assert_eq!(date(4).date_parts, None);
+        assert_eq!(date(4).literal.as_deref(), Some("Michaelmas Term"));
+        assert_eq!(date(5).date_parts, None);
+        assert_eq!(date(6).date_parts, None);
+        assert_eq!(date(7).date_parts, Some(vec![vec![2021]]));
+    }
+
+    #[test]
+    /// Numbers are accepted wherever strings are, and unknown fields are kept.
+    fn mixed_types() {
+        let result = build_csl_lib(
+            r#"[
+                {
+                    "id": 12345,
+                    "type": "article-journal",
+                    "volume": 4,
+                    "page": 193,
+                    "edition": 2,
+                    "DOI": "10.2307/1321160",
+                    "accessed": {"date-parts": [[2024, 1, 1]]}
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(result[0].id, "12345");
+        assert_eq!(result[0].volume.as_deref(), Some("4"));
+        assert_eq!(result[0].page.as_deref(), Some("193"));
+        assert_eq!(result[0].edition.as_deref(), Some("2"));
+        assert_eq!(result[0].extra["DOI"], "10.2307/1321160");
+        assert!(result[0].extra.contains_key("accessed"));
     }
 
     #[test]
@@ -747,4 +1499,143 @@ mod tests {
             2021
         );
     }
+
+    #[test]
+    /// Later layers override earlier ones by id.
+    fn layers() {
+        let team = build_csl_lib(
+            r#"[
+                {"id": "a", "title": "Team A"},
+                {"id": "b", "title": "Team B"}
+            ]"#,
+        )
+        .unwrap();
+        let personal = build_csl_lib(r#"[{"id": "b", "title": "Personal B"}]"#).unwrap();
+
+        let mut library = Library::default();
+        library.add_layer(team, "team.json");
+        library.add_layer(personal, "personal.json");
+
+        assert_eq!(library.get("a").unwrap().title.as_deref(), Some("Team A"));
+        assert_eq!(library.layer("a"), Some(0));
+        assert_eq!(
+            library.get("b").unwrap().title.as_deref(),
+            Some("Personal B")
+        );
+        assert_eq!(library.layer("b"), Some(1));
+        assert!(library.get("c").is_none());
+        assert_eq!(library.sources().count(), 2);
+    }
+
+    #[test]
+    /// `key: value` lines in the note override variables and set Supra's keys.
+    fn note_overrides() {
+        let mut result = build_csl_lib(
+            r#"[
+                {
+                    "id": "note",
+                    "type": "article-journal",
+                    "title": "Title",
+                    "note": "Container Title Short: Harv. L. Rev.\noriginal-date: 1890\nEditor: Editor || Given\nsupra-short-form: Privacy\nsupra-type: book\nA line that stays."
+                }
+            ]"#,
+        )
+        .unwrap();
+        let source = &mut result[0];
+        source.apply_note_overrides();
+
+        assert_eq!(
+            source.container_title_short.as_deref(),
+            Some("Harv. L. Rev.")
+        );
+        assert_eq!(
+            source.extra["original-date"],
+            serde_json::json!({"raw": "1890"})
+        );
+        let editor = &source.editor.as_ref().unwrap()[0];
+        assert_eq!(editor.family.as_deref(), Some("Editor"));
+        assert_eq!(editor.given.as_deref(), Some("Given"));
+        assert_eq!(source.supra.short_form.as_deref(), Some("Privacy"));
+        assert_eq!(source.supra.source_type.as_deref(), Some("book"));
+        assert_eq!(source.supra.hereinafter, None);
+        assert_eq!(source.extra["note"], "A line that stays.");
+    }
+
+    #[test]
+    /// Juris-M's jurisdiction, division, and English title variants.
+    fn juris_m() {
+        let mut result = build_csl_lib(
+            r#"[
+                {
+                    "id": "case",
+                    "type": "legal_case",
+                    "title": "Affaire",
+                    "authority": "court.appeals",
+                    "jurisdiction": "us:c9",
+                    "division": "En Banc",
+                    "multi": {
+                        "main": {"title": "fr"},
+                        "_keys": {"title": {"en": "Case"}}
+                    }
+                }
+            ]"#,
+        )
+        .unwrap();
+        let source = &mut result[0];
+        source.apply_english_variants();
+
+        assert_eq!(source.jurisdiction.as_deref(), Some("us:c9"));
+        assert_eq!(source.division.as_deref(), Some("En Banc"));
+        assert_eq!(source.title.as_deref(), Some("Case"));
+        assert_eq!(source.extra["original-title"], "Affaire");
+    }
+
+    #[test]
+    /// Old ids resolve through the aliases, but ids in the library never do.
+    fn aliases() {
+        let sources = build_csl_lib(
+            r#"[
+                {"id": "new", "title": "New"},
+                {"id": "kept", "title": "Kept"}
+            ]"#,
+        )
+        .unwrap();
+
+        let mut library = Library::default();
+        library.add_layer(sources, "library.json");
+        library.set_aliases(HashMap::from([
+            ("old".to_string(), "new".to_string()),
+            ("kept".to_string(), "new".to_string()),
+        ]));
+
+        assert_eq!(library.get("old").unwrap().title.as_deref(), Some("New"));
+        assert_eq!(library.get("kept").unwrap().title.as_deref(), Some("Kept"));
+        assert_eq!(library.layer("old"), Some(0));
+    }
+
+    #[test]
+    /// Filtering keeps only the cited entries, in any id format.
+    fn cited_only() {
+        let json = r#"[
+            {"id": "a", "title": "A"},
+            {"id": 2, "title": "Two"},
+            {"id": "c", "title": "C", "author": [{"family": "Skipped"}]}
+        ]"#;
+        let keys = HashSet::from(["a", "2", "missing"]);
+        let result = build_cited_csl_lib(json, &keys).unwrap();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].id, "a");
+        assert_eq!(result[1].title.as_deref(), Some("Two"));
+
+        assert!(build_cited_csl_lib(r#"[{"title": "No id"}]"#, &keys)
+            .unwrap()
+            .is_empty());
+        assert!(
+            build_cited_csl_lib(r#"[{"id": "a", "author": "A"}]"#, &keys)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(build_cited_csl_lib("[] trailing", &keys).is_err());
+    }
 }
@@ -0,0 +1,841 @@
+//! Functions for reading BibTeX and BibLaTeX libraries.
+//!
+//! Entries are mapped onto [`CSLSource`]s, so the rest of Supra never knows the
+//! library wasn't CSL JSON. Entry types and fields follow BibLaTeX, with the
+//! older BibTeX names (`journal`, `inproceedings`, etc.) accepted as well.
+//! Cases use the `@jurisdiction` type and fields from `biblatex-juridica`.
+
+use super::{CSLSource, DateVariable, NameVariable};
+use crate::pre::sourcemap::buildsource::bluebook_ordinal;
+use slog::{debug, trace};
+use std::collections::HashMap;
+
+/// A raw entry, before its fields are converted.
+struct Entry {
+    entry_type: String,
+    key: String,
+    line: usize,
+    fields: HashMap<String, String>,
+}
+
+/// Parse a BibTeX or BibLaTeX library into a collection of [`CSLSource`]s.
+pub fn build_bib_lib(bib_string: &str) -> Result<Vec<CSLSource>, String> {
+    debug!(slog_scope::logger(), "Starting BibLaTeX parsing...");
+
+    let entries = match Parser::new(bib_string).entries() {
+        Ok(e) => e,
+        Err(e) => return Err(format!("error parsing the BibLaTeX library—{}", e)),
+    };
+
+    let library = entries.into_iter().map(build_source).collect();
+
+    debug!(slog_scope::logger(), "BibLaTeX parsed");
+    Ok(library)
+}
+
+/// Convert a raw entry into a [`CSLSource`].
+fn build_source(entry: Entry) -> CSLSource {
+    trace!(
+        slog_scope::logger(),
+        "Converting entry {} (line {})",
+        entry.key,
+        entry.line
+    );
+
+    let field = |names: &[&str]| -> Option<String> {
+        names
+            .iter()
+            .find_map(|n| entry.fields.get(*n))
+            .map(|v| latex_to_text(v))
+            .filter(|v| !v.is_empty())
+    };
+    let names = |name: &str| -> Option<Vec<NameVariable>> {
+        entry
+            .fields
+            .get(name)
+            .map(|v| parse_names(v))
+            .filter(|v| !v.is_empty())
+    };
+
+    let source_type = match entry.entry_type.as_str() {
+        "article" => "article-journal",
+        "book" | "mvbook" | "collection" | "mvcollection" => "book",
+        "inbook" | "incollection" | "bookinbook" | "inproceedings" | "suppbook" => "chapter",
+        "unpublished" => "manuscript",
+        "jurisdiction" | "case" => "legal_case",
+        "legislation" => "legislation",
+        "report" | "techreport" => "report",
+        "thesis" | "phdthesis" | "mastersthesis" => "thesis",
+        "online" | "www" => "webpage",
+        _ => "document",
+    };
+
+    let container_title = match source_type {
+        "chapter" => field(&["booktitle"]),
+        "legal_case" => field(&["reporter", "journaltitle", "journal"]),
+        _ => field(&["journaltitle", "journal"]),
+    };
+
+    CSLSource {
+        source_type: Some(source_type.to_string()),
+        author: names("author"),
+        editor: names("editor"),
+        translator: names("translator"),
+        issued: parse_date(&entry.fields),
+        container_title,
+        container_title_short: field(&["shortjournal"]),
+        authority: field(&["court", "institution"]),
+        edition: field(&["edition"]).map(|e| edition(&e)),
+        number: field(&["number"]),
+        page: field(&["pages"]).map(|p| first_page(&p)),
+        publisher: field(&["publisher"]),
+        title: field(&["title"]),
+        title_short: field(&["shorttitle"]),
+        url: field(&["url"]),
+        volume: field(&["volume"]),
+        references: None,
+        id: entry.key,
+        ..Default::default()
+    }
+}
+
+/// Converts a numeric BibLaTeX edition to the Bluebook's ordinals, e.g., "2"
+/// to "2d."
+fn edition(edition: &str) -> String {
+    match edition.parse::<u32>() {
+        Ok(n) => bluebook_ordinal(n),
+        Err(_) => edition.to_string(),
+    }
+}
+
+/// Supra only uses the first page of a range.
+fn first_page(pages: &str) -> String {
+    pages
+        .split(['-', '–', '—'])
+        .next()
+        .unwrap_or(pages)
+        .trim()
+        .to_string()
+}
+
+/// Builds the date from the `date` field or, failing that, the `year` and
+/// `month` fields.
+fn parse_date(fields: &HashMap<String, String>) -> Option<DateVariable> {
+    let mut parts: Vec<u32> = Vec::new();
+
+    if let Some(date) = fields.get("date") {
+        // Only the start of a date range is used.
+        let start = date.split('/').next().unwrap_or(date);
+        for part in start.trim().split('-') {
+            match part.parse::<u32>() {
+                Ok(p) => parts.push(p),
+                Err(_) => break,
+            }
+        }
+    } else if let Some(year) = fields.get("year") {
+        if let Ok(y) = latex_to_text(year).parse::<u32>() {
+            parts.push(y);
+            if let Some(Ok(m)) = fields.get("month").map(|m| m.parse::<u32>()) {
+                parts.push(m);
+            }
+        }
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(DateVariable {
+            date_parts: Some(vec![parts]),
+            ..Default::default()
+        })
+    }
+}
+
+/// Splits a list of names on "and" and parses each one.
+fn parse_names(raw: &str) -> Vec<NameVariable> {
+    let mut names: Vec<String> = Vec::new();
+    let mut name: Vec<String> = Vec::new();
+
+    for word in split_words(raw) {
+        if word.eq_ignore_ascii_case("and") {
+            names.push(name.join(" "));
+            name = Vec::new();
+        } else {
+            name.push(word);
+        }
+    }
+    names.push(name.join(" "));
+
+    names
+        .iter()
+        .filter(|n| !n.is_empty() && !n.eq_ignore_ascii_case("others"))
+        .map(|n| parse_name(n))
+        .collect()
+}
+
+/// Parses a name in any of the three BibTeX forms: "First von Last," "von
+/// Last, First," or "von Last, Jr, First."
+fn parse_name(raw: &str) -> NameVariable {
+    let parts: Vec<String> = split_commas(raw);
+
+    let to_option = |words: &[String]| -> Option<String> {
+        if words.is_empty() {
+            None
+        } else {
+            Some(latex_to_text(&words.join(" ")))
+        }
+    };
+
+    let (given, particle, family, suffix) = match parts.len() {
+        1 => {
+            let words = split_words(&parts[0]);
+            if words.len() == 1 {
+                (None, None, to_option(&words), None)
+            } else {
+                // The particle starts at the first lowercase word and runs
+                // until the family name, which always includes the last word.
+                let last = words.len() - 1;
+                match words[..last].iter().position(|w| starts_lowercase(w)) {
+                    Some(start) => {
+                        let end = start
+                            + words[start..last]
+                                .iter()
+                                .take_while(|w| starts_lowercase(w))
+                                .count();
+                        (
+                            to_option(&words[..start]),
+                            to_option(&words[start..end]),
+                            to_option(&words[end..]),
+                            None,
+                        )
+                    }
+                    None => (
+                        to_option(&words[..last]),
+                        None,
+                        to_option(&words[last..]),
+                        None,
+                    ),
+                }
+            }
+        }
+        n => {
+            let words = split_words(&parts[0]);
+            let particle_len = words
+                .iter()
+                .take(words.len().saturating_sub(1))
+                .take_while(|w| starts_lowercase(w))
+                .count();
+            let suffix = if n > 2 {
+                to_option(&[parts[1].clone()])
+            } else {
+                None
+            };
+            (
+                to_option(&[parts[n - 1].clone()]),
+                to_option(&words[..particle_len]),
+                to_option(&words[particle_len..]),
+                suffix,
+            )
+        }
+    };
+
+    NameVariable {
+        family,
+        given,
+        non_dropping_particle: particle,
+        suffix,
+        ..Default::default()
+    }
+}
+
+/// Whether a word in a name starts with a lowercase letter (outside of braces).
+fn starts_lowercase(word: &str) -> bool {
+    word.chars()
+        .next()
+        .map(|c| c.is_lowercase())
+        .unwrap_or(false)
+}
+
+/// Splits a string into words on whitespace outside braces.
+fn split_words(raw: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut depth = 0;
+
+    for c in raw.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            c if c.is_whitespace() && depth == 0 => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// Splits a name on commas outside braces.
+fn split_commas(raw: &str) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut part = String::new();
+    let mut depth = 0;
+
+    for c in raw.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(part.trim().to_string());
+                part = String::new();
+                continue;
+            }
+            _ => {}
+        }
+        part.push(c);
+    }
+    parts.push(part.trim().to_string());
+
+    parts
+}
+
+/// Converts LaTeX markup to plain text.
+///
+/// Italics become `<i>` tags, as they are in CSL JSON. Accents, escaped
+/// characters, dashes, and quotation marks become their Unicode equivalents.
+/// Protective braces and any other commands are removed.
+pub fn latex_to_text(raw: &str) -> String {
+    let chars: Vec<char> = raw.chars().collect();
+    let mut output = String::with_capacity(raw.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                if chars[i].is_ascii_alphabetic() {
+                    let start = i;
+                    while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                        i += 1;
+                    }
+                    let command: String = chars[start..i].iter().collect();
+                    while i < chars.len() && chars[i] == ' ' {
+                        i += 1;
+                    }
+
+                    match command.as_str() {
+                        "textit" | "emph" | "mkbibemph" | "textsl" | "mkbibitalic" => {
+                            let (arg, next) = read_group(&chars, i);
+                            output.push_str(&format!("<i>{}</i>", latex_to_text(&arg)));
+                            i = next;
+                        }
+                        "c" => {
+                            let (arg, next) = read_group(&chars, i);
+                            output.push_str(&accent(',', &arg));
+                            i = next;
+                        }
+                        "ss" => output.push('ß'),
+                        "o" => output.push('ø'),
+                        "O" => output.push('Ø'),
+                        "ae" => output.push('æ'),
+                        "AE" => output.push('Æ'),
+                        "oe" => output.push('œ'),
+                        "OE" => output.push('Œ'),
+                        "aa" => output.push('å'),
+                        "AA" => output.push('Å'),
+                        "l" => output.push('ł'),
+                        "L" => output.push('Ł'),
+                        "S" => output.push('§'),
+                        "P" => output.push('¶'),
+                        // Any other command is dropped, leaving its argument.
+                        _ => {}
+                    }
+                } else {
+                    match chars[i] {
+                        c @ ('\'' | '"' | '`' | '^' | '~' | '=' | '.') => {
+                            let (arg, next) = read_group(&chars, i + 1);
+                            output.push_str(&accent(c, &arg));
+                            i = next;
+                        }
+                        '\\' => {
+                            output.push(' ');
+                            i += 1;
+                        }
+                        c => {
+                            output.push(c);
+                            i += 1;
+                        }
+                    }
+                }
+            }
+            '{' | '}' | '$' => i += 1,
+            '~' => {
+                output.push('\u{a0}');
+                i += 1;
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                if chars.get(i + 2) == Some(&'-') {
+                    output.push('—');
+                    i += 3;
+                } else {
+                    output.push('–');
+                    i += 2;
+                }
+            }
+            '`' if chars.get(i + 1) == Some(&'`') => {
+                output.push('“');
+                i += 2;
+            }
+            '\'' if chars.get(i + 1) == Some(&'\'') => {
+                output.push('”');
+                i += 2;
+            }
+            c if c.is_whitespace() => {
+                if !output.ends_with(' ') {
+                    output.push(' ');
+                }
+                i += 1;
+            }
+            c => {
+                output.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    output.trim().to_string()
+}
+
+/// Reads a command's argument: either a braced group or a single character.
+///
+/// Returns the argument and the index after it.
+fn read_group(chars: &[char], start: usize) -> (String, usize) {
+    match chars.get(start) {
+        Some('{') => {
+            let mut depth = 0;
+            let mut i = start;
+            while i < chars.len() {
+                match chars[i] {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return (chars[start + 1..i].iter().collect(), i + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            (chars[start + 1..].iter().collect(), chars.len())
+        }
+        Some(c) => (c.to_string(), start + 1),
+        None => (String::new(), start),
+    }
+}
+
+/// Combines an accent command with a letter.
+fn accent(command: char, letter: &str) -> String {
+    let table = match command {
+        '\'' => "aáeéiíoóuúyýcćnńsśzźAÁEÉIÍOÓUÚYÝCĆNŃSŚZŹ",
+        '`' => "aàeèiìoòuùAÀEÈIÌOÒUÙ",
+        '"' => "aäeëiïoöuüyÿAÄEËIÏOÖUÜ",
+        '^' => "aâeêiîoôuûAÂEÊIÎOÔUÛ",
+        '~' => "aãnñoõAÃNÑOÕ",
+        '=' => "aāeēiīoōuūAĀEĒIĪOŌUŪ",
+        '.' => "zżZŻ",
+        ',' => "cçsşCÇSŞ",
+        _ => "",
+    };
+
+    let base = latex_to_text(letter);
+    let table: Vec<char> = table.chars().collect();
+    for pair in table.chunks(2) {
+        if base == pair[0].to_string() {
+            return pair[1].to_string();
+        }
+    }
+
+    base
+}
+
+/// A small parser for the BibTeX file format.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    strings: HashMap<String, String>,
+}
+
+impl Parser {
+    fn new(input: &str) -> Parser {
+        let mut strings = HashMap::new();
+        for (i, month) in [
+            "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+        ]
+        .iter()
+        .enumerate()
+        {
+            strings.insert(month.to_string(), (i + 1).to_string());
+        }
+
+        Parser {
+            chars: input.chars().collect(),
+            pos: 0,
+            line: 1,
+            strings,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        if c == '\n' {
+            self.line += 1;
+        }
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!(
+                "line {}: expected \"{}\" but found \"{}\"",
+                self.line, expected, c
+            )),
+            None => Err(format!(
+                "line {}: expected \"{}\" but reached the end of the file",
+                self.line, expected
+            )),
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        self.skip_whitespace();
+        let mut ident = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || "{}(),=#\"".contains(c) {
+                break;
+            }
+            ident.push(c);
+            self.bump();
+        }
+        ident
+    }
+
+    /// Reads everything up to the matching closing brace, keeping any inner
+    /// braces.
+    fn braced(&mut self) -> Result<String, String> {
+        let start = self.line;
+        let mut depth = 1;
+        let mut value = String::new();
+        while let Some(c) = self.bump() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(value);
+                    }
+                }
+                _ => {}
+            }
+            value.push(c);
+        }
+        Err(format!("line {}: unclosed brace", start))
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        let start = self.line;
+        let mut depth = 0;
+        let mut value = String::new();
+        while let Some(c) = self.bump() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                '"' if depth == 0 => return Ok(value),
+                _ => {}
+            }
+            value.push(c);
+        }
+        Err(format!("line {}: unclosed quotation mark", start))
+    }
+
+    /// Reads a field value, which may be several pieces joined with `#`.
+    fn value(&mut self) -> Result<String, String> {
+        let mut value = String::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('{') => {
+                    self.bump();
+                    value.push_str(&self.braced()?);
+                }
+                Some('"') => {
+                    self.bump();
+                    value.push_str(&self.quoted()?);
+                }
+                Some(_) => {
+                    let ident = self.identifier();
+                    if ident.is_empty() {
+                        return Err(format!("line {}: missing field value", self.line));
+                    }
+                    match self.strings.get(&ident.to_lowercase()) {
+                        Some(s) => value.push_str(s),
+                        None => value.push_str(&ident),
+                    }
+                }
+                None => {
+                    return Err(format!(
+                        "line {}: reached the end of the file in a field value",
+                        self.line
+                    ))
+                }
+            }
+
+            self.skip_whitespace();
+            if self.peek() == Some('#') {
+                self.bump();
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Skips the body of an entry that isn't a source, e.g., `@comment`.
+    fn skip_body(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.bump() {
+            Some('{') => self.braced().map(|_| ()),
+            Some('(') => {
+                while let Some(c) = self.bump() {
+                    if c == ')' {
+                        return Ok(());
+                    }
+                }
+                Err(format!("line {}: unclosed parenthesis", self.line))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn entries(&mut self) -> Result<Vec<Entry>, String> {
+        let mut entries = Vec::new();
+
+        // Anything outside an entry is a comment.
+        while let Some(c) = self.bump() {
+            if c != '@' {
+                continue;
+            }
+
+            let line = self.line;
+            let entry_type = self.identifier().to_lowercase();
+
+            match entry_type.as_str() {
+                "comment" | "preamble" => {
+                    self.skip_body()?;
+                    continue;
+                }
+                "string" => {
+                    self.skip_whitespace();
+                    let close = if self.bump() == Some('(') { ')' } else { '}' };
+                    let name = self.identifier().to_lowercase();
+                    self.expect('=')?;
+                    let value = self.value()?;
+                    self.expect(close)?;
+                    self.strings.insert(name, value);
+                    continue;
+                }
+                _ => {}
+            }
+
+            self.skip_whitespace();
+            let close = match self.bump() {
+                Some('{') => '}',
+                Some('(') => ')',
+                _ => {
+                    return Err(format!(
+                        "line {}: expected an opening brace after @{}",
+                        line, entry_type
+                    ))
+                }
+            };
+
+            let key = self.identifier();
+            if key.is_empty() {
+                return Err(format!("line {}: @{} entry has no key", line, entry_type));
+            }
+
+            let mut fields = HashMap::new();
+            loop {
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(',') => {
+                        self.bump();
+                    }
+                    Some(c) if c == close => {
+                        self.bump();
+                        break;
+                    }
+                    Some(_) => {
+                        let name = self.identifier().to_lowercase();
+                        if name.is_empty() {
+                            return Err(format!(
+                                "line {}: unexpected character in entry {}",
+                                self.line, key
+                            ));
+                        }
+                        self.expect('=')
+                            .map_err(|e| format!("{} (entry {})", e, key))?;
+                        let value = self.value().map_err(|e| format!("{} (entry {})", e, key))?;
+                        fields.insert(name, value);
+                    }
+                    None => {
+                        return Err(format!("line {}: entry {} is not closed", line, key));
+                    }
+                }
+            }
+
+            entries.push(Entry {
+                entry_type,
+                key,
+                line,
+                fields,
+            });
+        }
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries() {
+        let bib = r#"
+@string{jja = "Journal of Journal Articles"}
+
+A comment outside an entry.
+
+@article{authorJournalArticleTitle2021,
+  author = {Author, Article and Beethoven, Ludwig van and Smith, Jr., Sam},
+  title = {Journal Article Title: A \emph{Journal} Article},
+  journaltitle = jja,
+  shortjournal = {J. J. Articles},
+  volume = 99,
+  pages = {1000--1050},
+  date = {2021-05},
+}
+
+@book{authorBookTitleTitle2021,
+  author = {Book Author},
+  title = {Book Title: A Title for the Dummy Book},
+  edition = {2},
+  year = 2021,
+}
+
+@jurisdiction{DoeState2022,
+  title = {Doe v. State},
+  shorttitle = {Doe},
+  court = {7th Cir.},
+  journaltitle = {F.4th},
+  volume = {123},
+  pages = {456},
+  date = {2022-07-25},
+}
+"#;
+        let library = build_bib_lib(bib).unwrap();
+        assert_eq!(library.len(), 3);
+
+        let article = &library[0];
+        assert_eq!(article.id, "authorJournalArticleTitle2021");
+        assert_eq!(article.source_type.as_deref(), Some("article-journal"));
+        assert_eq!(
+            article.title.as_deref(),
+            Some("Journal Article Title: A <i>Journal</i> Article")
+        );
+        assert_eq!(
+            article.container_title.as_deref(),
+            Some("Journal of Journal Articles")
+        );
+        assert_eq!(article.page.as_deref(), Some("1000"));
+        assert_eq!(
+            article.issued.as_ref().unwrap().date_parts,
+            Some(vec![vec![2021, 5]])
+        );
+
+        let authors = article.author.as_ref().unwrap();
+        assert_eq!(authors.len(), 3);
+        assert_eq!(authors[1].family.as_deref(), Some("Beethoven"));
+        assert_eq!(authors[1].given.as_deref(), Some("Ludwig van"));
+        assert_eq!(authors[2].suffix.as_deref(), Some("Jr."));
+
+        let book = &library[1];
+        assert_eq!(book.source_type.as_deref(), Some("book"));
+        assert_eq!(book.edition.as_deref(), Some("2d"));
+        assert_eq!(
+            book.author.as_ref().unwrap()[0].given.as_deref(),
+            Some("Book")
+        );
+
+        let case = &library[2];
+        assert_eq!(case.source_type.as_deref(), Some("legal_case"));
+        assert_eq!(case.authority.as_deref(), Some("7th Cir."));
+        assert_eq!(case.container_title.as_deref(), Some("F.4th"));
+    }
+
+    #[test]
+    fn names() {
+        let name = parse_name("Ludwig van Beethoven");
+        assert_eq!(name.given.as_deref(), Some("Ludwig"));
+        assert_eq!(name.non_dropping_particle.as_deref(), Some("van"));
+        assert_eq!(name.family.as_deref(), Some("Beethoven"));
+
+        let name = parse_name("van Beethoven, Ludwig");
+        assert_eq!(name.given.as_deref(), Some("Ludwig"));
+        assert_eq!(name.non_dropping_particle.as_deref(), Some("van"));
+        assert_eq!(name.family.as_deref(), Some("Beethoven"));
+
+        let name = parse_name("{Barnes and Noble}");
+        assert_eq!(name.family.as_deref(), Some("Barnes and Noble"));
+        assert_eq!(parse_names("{Barnes and Noble} and Smith, Jane").len(), 2);
+    }
+
+    #[test]
+    fn latex() {
+        assert_eq!(latex_to_text(r#"Schr\"{o}dinger"#), "Schrödinger");
+        assert_eq!(latex_to_text(r"Caf\'e \& Bar"), "Café & Bar");
+        assert_eq!(latex_to_text(r"\S~5--7"), "§\u{a0}5–7");
+        assert_eq!(
+            latex_to_text("{NASA} and\n  the {M}oon"),
+            "NASA and the Moon"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let bib = "@article{key,\n  title = {Unclosed,\n}";
+        assert!(build_bib_lib(bib).is_err());
+    }
+}
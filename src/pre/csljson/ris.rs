@@ -0,0 +1,389 @@
+//! Functions for reading RIS libraries.
+//!
+//! RIS is the export format of Westlaw, HeinOnline, and most databases. Each
+//! line of a record is a two-character tag, two spaces, a hyphen, a space, and
+//! the value. Records start with `TY` and end with `ER`.
+//!
+//! RIS records rarely have ids that would be useful in citations. If a record
+//! has no `ID`, one is made from the first author's family name, the first
+//! three significant words of the title, and the year, *e.g.*,
+//! `smithTheRightPrivacy1890`.
+
+use super::{CSLSource, DateVariable, NameVariable};
+use ansi_term::Color;
+use slog::{debug, warn};
+use std::collections::HashMap;
+
+/// Words skipped when making ids from titles.
+const STOP_WORDS: [&str; 12] = [
+    "a", "an", "and", "at", "by", "for", "in", "of", "on", "or", "the", "to",
+];
+
+/// A raw record, before its fields are converted.
+#[derive(Default)]
+struct Record {
+    line: usize,
+    fields: HashMap<String, Vec<String>>,
+}
+
+impl Record {
+    fn first(&self, tags: &[&str]) -> Option<String> {
+        tags.iter()
+            .find_map(|t| self.fields.get(*t).and_then(|v| v.first()))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    }
+
+    fn all(&self, tags: &[&str]) -> Vec<String> {
+        tags.iter()
+            .filter_map(|t| self.fields.get(*t))
+            .flatten()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect()
+    }
+}
+
+/// Parse an RIS library into a collection of [`CSLSource`]s.
+///
+/// A record that can't be used is skipped with a warning giving its number, its
+/// `ID` (if it has one), and its line, rather than failing the whole library.
+pub fn build_ris_lib(ris_string: &str) -> Result<Vec<CSLSource>, String> {
+    debug!(slog_scope::logger(), "Starting RIS parsing...");
+
+    let mut library = Vec::new();
+    for (index, record) in records(ris_string).iter().enumerate() {
+        match build_source(record) {
+            Ok(s) => library.push(s),
+            Err(e) => {
+                let place = match record.first(&["ID"]) {
+                    Some(id) => format!("{}, line {}", id, record.line),
+                    None => format!("line {}", record.line),
+                };
+                warn!(
+                    slog_scope::logger(),
+                    "Skipping RIS record {} ({}): {}",
+                    index + 1,
+                    place,
+                    e
+                );
+                eprintln!(
+                    "  {} Skipping RIS record {} ({}): {}",
+                    Color::Yellow.paint("WARN"),
+                    index + 1,
+                    place,
+                    e
+                );
+            }
+        }
+    }
+
+    if library.is_empty() && !ris_string.trim().is_empty() {
+        return Err("error parsing the RIS library—no records found".to_string());
+    }
+
+    debug!(slog_scope::logger(), "RIS parsed");
+    Ok(library)
+}
+
+/// Splits the input into records.
+///
+/// Lines that don't start with a tag continue the previous line's value.
+fn records(input: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut record: Option<Record> = None;
+    let mut last_tag = String::new();
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim_start_matches('\u{feff}');
+        let tagged = line.len() >= 5
+            && line.is_char_boundary(2)
+            && line[..2]
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            && line[2..].starts_with("  -");
+
+        if !tagged {
+            if let Some(r) = record.as_mut() {
+                if let Some(v) = r.fields.get_mut(&last_tag).and_then(|v| v.last_mut()) {
+                    if !line.trim().is_empty() {
+                        v.push(' ');
+                        v.push_str(line.trim());
+                    }
+                }
+            }
+            continue;
+        }
+
+        let tag = line[..2].to_string();
+        let value = line[5..].trim().to_string();
+
+        match tag.as_str() {
+            "TY" => {
+                if let Some(r) = record.take() {
+                    records.push(r);
+                }
+                let mut r = Record {
+                    line: i + 1,
+                    ..Default::default()
+                };
+                r.fields.insert(tag.clone(), vec![value]);
+                record = Some(r);
+            }
+            "ER" => {
+                if let Some(r) = record.take() {
+                    records.push(r);
+                }
+            }
+            _ => {
+                if let Some(r) = record.as_mut() {
+                    r.fields.entry(tag.clone()).or_default().push(value);
+                }
+            }
+        }
+
+        last_tag = tag;
+    }
+
+    if let Some(r) = record {
+        records.push(r);
+    }
+
+    records
+}
+
+/// Convert a record into a [`CSLSource`].
+fn build_source(record: &Record) -> Result<CSLSource, String> {
+    let ris_type = record.first(&["TY"]).unwrap_or_default();
+    let source_type = match ris_type.as_str() {
+        "JOUR" | "JFULL" | "EJOUR" | "MGZN" => "article-journal",
+        "BOOK" | "EBOOK" | "EDBOOK" => "book",
+        "CHAP" | "ECHAP" => "chapter",
+        "CASE" => "legal_case",
+        "UNPB" | "MANSCPT" => "manuscript",
+        "STAT" | "BILL" => "legislation",
+        "RPRT" => "report",
+        "THES" => "thesis",
+        "ELEC" | "WEB" => "webpage",
+        _ => "document",
+    };
+
+    let title = record.first(&["TI", "T1", "CT"]);
+    if title.is_none() {
+        return Err("no title".to_string());
+    }
+
+    let author = names(&record.all(&["AU", "A1"]));
+    let issued = date(record);
+
+    let id = match record.first(&["ID"]) {
+        Some(id) => id,
+        None => make_id(&author, title.as_ref().unwrap(), &issued)?,
+    };
+
+    // Books' editors are in A2 for chapters and A3 or ED otherwise.
+    let editor = if source_type == "chapter" {
+        names(&record.all(&["A2", "ED"]))
+    } else {
+        names(&record.all(&["ED", "A3"]))
+    };
+
+    let (container_title, authority) = match source_type {
+        "chapter" => (record.first(&["T2", "BT"]), None),
+        "legal_case" => (record.first(&["T2", "JO", "JF"]), record.first(&["PB"])),
+        _ => (record.first(&["T2", "JO", "JF"]), None),
+    };
+
+    Ok(CSLSource {
+        source_type: Some(source_type.to_string()),
+        id,
+        author,
+        editor,
+        translator: names(&record.all(&["A4"])),
+        issued,
+        container_title,
+        container_title_short: record.first(&["J2", "JA"]),
+        authority,
+        edition: record.first(&["ET"]),
+        number: record.first(&["IS", "M1"]),
+        page: record.first(&["SP"]),
+        publisher: if source_type == "legal_case" {
+            None
+        } else {
+            record.first(&["PB"])
+        },
+        title,
+        title_short: record.first(&["ST"]),
+        url: record.first(&["UR"]),
+        volume: record.first(&["VL"]),
+        references: None,
+        ..Default::default()
+    })
+}
+
+/// Parses "Last, First, Suffix" names.
+fn names(raw: &[String]) -> Option<Vec<NameVariable>> {
+    if raw.is_empty() {
+        return None;
+    }
+
+    Some(
+        raw.iter()
+            .map(|n| {
+                let parts: Vec<&str> = n.split(',').map(|p| p.trim()).collect();
+                let part = |i: usize| {
+                    parts
+                        .get(i)
+                        .filter(|p| !p.is_empty())
+                        .map(|p| p.to_string())
+                };
+                NameVariable {
+                    family: part(0),
+                    given: part(1),
+                    non_dropping_particle: None,
+                    suffix: part(2),
+                    ..Default::default()
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Parses the date from `PY`, `DA`, or `Y1`, which are "YYYY/MM/DD/other."
+fn date(record: &Record) -> Option<DateVariable> {
+    let raw = record.first(&["PY", "DA", "Y1"])?;
+    let parts: Vec<u32> = raw
+        .split(['/', '-'])
+        .map_while(|p| p.trim().parse::<u32>().ok())
+        .collect();
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(DateVariable {
+            date_parts: Some(vec![parts]),
+            ..Default::default()
+        })
+    }
+}
+
+/// Makes an id from the author, title, and year.
+fn make_id(
+    author: &Option<Vec<NameVariable>>,
+    title: &str,
+    issued: &Option<DateVariable>,
+) -> Result<String, String> {
+    let mut id = String::new();
+
+    if let Some(family) = author
+        .as_ref()
+        .and_then(|a| a.first())
+        .and_then(|n| n.family.as_ref())
+    {
+        id.push_str(
+            &family
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+                .to_lowercase(),
+        );
+    }
+
+    title
+        .split_whitespace()
+        .map(|w| {
+            w.chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+        })
+        .filter(|w| !w.is_empty() && !STOP_WORDS.contains(&w.to_lowercase().as_str()))
+        .take(3)
+        .for_each(|w| {
+            let mut chars = w.chars();
+            if let Some(first) = chars.next() {
+                id.extend(first.to_uppercase());
+                id.push_str(chars.as_str());
+            }
+        });
+
+    if let Some(year) = issued
+        .as_ref()
+        .and_then(|i| i.date_parts.as_ref())
+        .and_then(|d| d.first())
+        .and_then(|d| d.first())
+    {
+        id.push_str(&year.to_string());
+    }
+
+    if id.is_empty() {
+        Err("no id and no author, title, or year to make one from".to_string())
+    } else {
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ris() {
+        let ris = "TY  - JOUR
+AU  - Warren, Samuel D.
+AU  - Brandeis, Louis D.
+TI  - The Right to
+  Privacy
+T2  - Harvard Law Review
+J2  - Harv. L. Rev.
+VL  - 4
+SP  - 193
+EP  - 220
+PY  - 1890
+ER  -
+
+TY  - BOOK
+AU  - Author, Book
+PY  - 2021
+ER  -
+
+TY  - CASE
+ID  - DoeState2022
+TI  - Doe v. State
+T2  - F.4th
+PB  - 7th Cir.
+VL  - 123
+SP  - 456
+DA  - 2022/07/25/
+ER  -
+";
+        let library = build_ris_lib(ris).unwrap();
+
+        assert_eq!(library.len(), 2);
+
+        let article = &library[0];
+        assert_eq!(article.id, "warrenRightPrivacy1890");
+        assert_eq!(article.title.as_deref(), Some("The Right to Privacy"));
+        assert_eq!(
+            article.container_title_short.as_deref(),
+            Some("Harv. L. Rev.")
+        );
+        assert_eq!(
+            article.author.as_ref().unwrap()[1].given.as_deref(),
+            Some("Louis D.")
+        );
+
+        let case = &library[1];
+        assert_eq!(case.id, "DoeState2022");
+        assert_eq!(case.authority.as_deref(), Some("7th Cir."));
+        assert_eq!(case.publisher, None);
+        assert_eq!(
+            case.issued.as_ref().unwrap().date_parts,
+            Some(vec![vec![2022, 7, 25]])
+        );
+    }
+
+    #[test]
+    fn no_records() {
+        assert!(build_ris_lib("Not an RIS file").is_err());
+    }
+}
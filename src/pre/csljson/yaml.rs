@@ -0,0 +1,221 @@
+//! Functions for reading CSL YAML libraries.
+//!
+//! CSL YAML is the same data as CSL JSON, in the form Pandoc uses for
+//! bibliographies. The file can be a bare list of sources or a YAML document
+//! (including a Markdown file's front matter) with the list under
+//! `references`.
+
+use super::CSLSource;
+use ansi_term::Color;
+use serde_yaml::{Mapping, Value};
+use slog::{debug, warn};
+
+/// Deserialize a CSL YAML library.
+///
+/// An entry that can't be deserialized is skipped with a warning giving its id
+/// and line, rather than failing the whole library.
+pub fn build_yaml_lib(yaml_string: &str) -> Result<Vec<CSLSource>, String> {
+    debug!(slog_scope::logger(), "Starting CSL YAML parsing...");
+
+    let document: Value = match serde_yaml::from_str(front_matter(yaml_string)) {
+        Ok(d) => d,
+        Err(e) => return Err(format!("error deserializing the CSL YAML—{}", e)),
+    };
+
+    let entries = match document {
+        Value::Sequence(s) => s,
+        Value::Mapping(mut m) => match m.remove("references") {
+            Some(Value::Sequence(s)) => s,
+            _ => return Err("the CSL YAML has no list of references".to_string()),
+        },
+        _ => return Err("the CSL YAML has no list of references".to_string()),
+    };
+
+    let mut library = Vec::with_capacity(entries.len());
+    for (index, mut entry) in entries.into_iter().enumerate() {
+        let id = entry
+            .get("id")
+            .and_then(|i| match i {
+                Value::String(s) => Some(s.to_string()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        normalize_dates(&mut entry);
+
+        match serde_yaml::from_value::<CSLSource>(entry) {
+            Ok(s) => library.push(s),
+            Err(e) => {
+                // An entry without an id is identified by its place in the list.
+                let name = if id.is_empty() {
+                    format!("number {}", index + 1)
+                } else {
+                    id.clone()
+                };
+                let line = match find_line(yaml_string, &id) {
+                    Some(l) => format!("line {}", l),
+                    None => "unknown line".to_string(),
+                };
+                warn!(
+                    slog_scope::logger(),
+                    "Skipping CSL YAML entry {} ({}): {}", name, line, e
+                );
+                eprintln!(
+                    "  {} Skipping CSL YAML entry {} ({}): {}",
+                    Color::Yellow.paint("WARN"),
+                    Color::Blue.paint(&name),
+                    line,
+                    e
+                );
+            }
+        }
+    }
+
+    debug!(slog_scope::logger(), "CSL YAML parsed");
+    Ok(library)
+}
+
+/// Returns only the front matter of a Markdown file, or the whole string if it
+/// has none.
+fn front_matter(input: &str) -> &str {
+    if !input.starts_with("---") {
+        return input;
+    }
+
+    let body = &input[3..];
+    for (start, line) in line_starts(body) {
+        if start > 0 && (line.trim_end() == "---" || line.trim_end() == "...") {
+            return &body[..start];
+        }
+    }
+
+    body
+}
+
+/// Iterates over each line with its starting byte index.
+fn line_starts(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut start = 0;
+    input.split_inclusive('\n').map(move |line| {
+        let this = start;
+        start += line.len();
+        (this, line)
+    })
+}
+
+/// Finds the line on which an entry's id is given, if it can be found.
+fn find_line(input: &str, id: &str) -> Option<usize> {
+    if id.is_empty() {
+        return None;
+    }
+
+    input
+        .lines()
+        .position(|line| {
+            let line = line.trim_start().trim_start_matches("- ").trim_start();
+            line.starts_with("id:")
+                && line[3..].trim().trim_matches(|c| c == '"' || c == '\'') == id
+        })
+        .map(|p| p + 1)
+}
+
+/// Converts the shorthand dates Pandoc accepts into `date-parts`.
+///
+/// Pandoc allows dates like `issued: 2021-05-03` and the older list of maps,
+/// `issued: [{year: 2021, month: 5}]`.
+fn normalize_dates(entry: &mut Value) {
+    let Value::Mapping(map) = entry else {
+        return;
+    };
+
+    for (key, value) in map.iter_mut() {
+        if !matches!(key.as_str(), Some("issued" | "accessed" | "event-date")) {
+            continue;
+        }
+
+        let parts: Vec<Value> = match value {
+            Value::String(s) => s
+                .split('/')
+                .next()
+                .unwrap_or(s)
+                .split('-')
+                .map_while(|p| p.parse::<u64>().ok())
+                .map(Value::from)
+                .collect(),
+            Value::Number(n) => vec![Value::Number(n.clone())],
+            Value::Sequence(s) => match s.first() {
+                Some(Value::Mapping(m)) => ["year", "month", "day"]
+                    .iter()
+                    .map_while(|k| m.get(*k).cloned())
+                    .collect(),
+                _ => continue,
+            },
+            _ => continue,
+        };
+
+        let mut date = Mapping::new();
+        date.insert(
+            Value::from("date-parts"),
+            Value::Sequence(vec![Value::Sequence(parts)]),
+        );
+        *value = Value::Mapping(date);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml() {
+        let yaml = r#"---
+title: A Paper
+references:
+- id: authorBookTitleTitle2021
+  type: book
+  author:
+  - family: Author
+    given: Book
+  issued: 2021
+  title: "Book Title: A Title for the Dummy Book"
+  title-short: Book Title
+- id: badEntry
+  type: [book]
+- id: authorJournalArticleTitle2021
+  type: article-journal
+  issued:
+    date-parts:
+    - [2021, 5]
+  title: "Journal Article Title: A Journal Article"
+...
+
+Body text.
+"#;
+        let library = build_yaml_lib(yaml).unwrap();
+
+        assert_eq!(library.len(), 2);
+        assert_eq!(library[0].id, "authorBookTitleTitle2021");
+        assert_eq!(
+            library[0].issued.as_ref().unwrap().date_parts,
+            Some(vec![vec![2021]])
+        );
+        assert_eq!(
+            library[1].issued.as_ref().unwrap().date_parts,
+            Some(vec![vec![2021, 5]])
+        );
+        assert_eq!(find_line(yaml, "badEntry"), Some(12));
+        assert_eq!(find_line(yaml, "missing"), None);
+        assert_eq!(find_line(yaml, ""), None);
+    }
+
+    #[test]
+    fn bare_list() {
+        let yaml = "- id: a\n  issued: 2021-05-03\n  title: A\n";
+        let library = build_yaml_lib(yaml).unwrap();
+
+        assert_eq!(
+            library[0].issued.as_ref().unwrap().date_parts,
+            Some(vec![vec![2021, 5, 3]])
+        );
+    }
+}
@@ -0,0 +1,491 @@
+//! This module contains functionality for rendering citations with a CSL style
+//! file.
+//!
+//! The legal styles are built by hand, but non-legal venues often require a
+//! style that Supra doesn't know. For those, each citation in the syntax tree is
+//! handed to a CSL processor along with its pincite and footnote number. The
+//! processor takes care of short forms, "ibid," and the bibliography; Supra
+//! still handles signals, parentheticals, punctuation, and cross-references.
+
+use crate::{
+    config::{LibraryFile, LibraryFormat},
+    pre::{
+        csljson::{raw_id, CSLSource, Library},
+        parser::{append_definitions, Branch, Labels, PreCite},
+        pincite::PinKind,
+    },
+};
+use ansi_term::Color;
+use hayagriva::{
+    citationberg::{
+        json::Item, taxonomy::Locator, FontStyle, FontVariant, FontWeight, IndependentStyle,
+    },
+    BibliographyDriver, BibliographyRequest, CitationItem, CitationRequest, CitePurpose, ElemChild,
+    ElemChildren, Formatting, LocatorPayload, SpecificLocator,
+};
+use serde_json::{json, value::RawValue, Map, Value};
+use slog::{debug, trace, warn};
+use std::collections::HashMap;
+
+/// Render the syntax tree with a CSL style.
+///
+/// Returns the rendered output and, if requested and the style has one, the
+/// bibliography.
+pub fn render_csl(
+    tree: &[Branch],
+    libraries: &[LibraryFile],
+    csl_library: &Library,
+    csl_style: &str,
+    crossref_map: &HashMap<&str, i32>,
+    bibliography: bool,
+) -> Result<String, String> {
+    debug!(slog_scope::logger(), "Beginning CSL rendering...");
+
+    let mut style = match IndependentStyle::from_xml(csl_style) {
+        Ok(s) => s,
+        Err(e) => return Err(format!("Could not parse the CSL style: {}", e)),
+    };
+
+    // The input supplies the punctuation after each citation, so the style's
+    // closing punctuation would double it up.
+    style.citation.layout.suffix = None;
+
+    // CSL JSON is read again so that the style gets the fields Supra doesn't
+    // use. Other formats only have what's in the `CSLSource`s.
+    let mut raw_layers: Vec<HashMap<String, Value>> = Vec::with_capacity(libraries.len());
+    for file in libraries {
+        let mut raw_layer = HashMap::new();
+        if matches!(file.format, LibraryFormat::CslJson | LibraryFormat::Zotero) {
+            // Only the entries in the library (which may have been filtered
+            // down to the cited sources) are converted.
+            let raw: Vec<&RawValue> = match serde_json::from_str(&file.contents) {
+                Ok(r) => r,
+                Err(e) => return Err(format!("Could not parse the CSL JSON library: {}", e)),
+            };
+            for entry in raw {
+                let Ok(id) = raw_id(entry) else {
+                    continue;
+                };
+                if csl_library.get(&id).is_none() {
+                    continue;
+                }
+                match serde_json::from_str(entry.get()) {
+                    Ok(item) => {
+                        raw_layer.insert(id, item);
+                    }
+                    Err(e) => return Err(format!("Could not parse the CSL JSON library: {}", e)),
+                }
+            }
+        }
+        raw_layers.push(raw_layer);
+    }
+
+    let items: Vec<Item> = match serde_json::from_value(Value::Array(
+        csl_library
+            .sources()
+            .map(|source| {
+                csl_library
+                    .layer(&source.id)
+                    .and_then(|l| raw_layers[l].get(&source.id).cloned())
+                    .unwrap_or_else(|| source_to_json(source))
+            })
+            .collect(),
+    )) {
+        Ok(i) => i,
+        Err(e) => return Err(format!("Could not convert the library: {}", e)),
+    };
+
+    let mut library_map: HashMap<String, &Item> = HashMap::new();
+    for item in &items {
+        if let Some(id) = item.id() {
+            library_map.insert(id.to_string(), item);
+        }
+    }
+
+    let locales = hayagriva::archive::locales();
+
+    // Collect the citations in the order they appear, along with the number of
+    // the footnote containing each. Every citation has to be passed to the
+    // driver before any can be rendered.
+    let mut citations: Vec<(&Branch, Option<usize>)> = Vec::new();
+    collect_citations(tree, None, &mut citations);
+
+    // The locators have to outlive the driver.
+    let locators: Vec<Option<(Locator, String)>> = citations
+        .iter()
+        .map(|(branch, _)| match branch {
+            Branch::Citation(citation) => citation.pincite.as_ref().map(|p| {
+                let locator = match p.kind {
+                    PinKind::Section => Locator::Section,
+                    PinKind::Paragraph => Locator::Paragraph,
+                    _ => Locator::Page,
+                };
+                (locator, p.locator())
+            }),
+            _ => None,
+        })
+        .collect();
+
+    let mut driver = BibliographyDriver::new();
+    let mut found: Vec<bool> = Vec::with_capacity(citations.len());
+
+    for ((branch, note_number), locator) in citations.iter().zip(&locators) {
+        // A citation in the body text is only the author (or, if the author is
+        // suppressed, the year), as in Pandoc.
+        let (csl_id, reference, purpose) = match branch {
+            Branch::Citation(citation) => (citation.key(), citation.reference, None),
+            Branch::Narrative(narrative) if narrative.suppress_author() => (
+                narrative.key(),
+                narrative.reference,
+                Some(CitePurpose::Year),
+            ),
+            Branch::Narrative(narrative) => (
+                narrative.key(),
+                narrative.reference,
+                Some(CitePurpose::Author),
+            ),
+            _ => continue,
+        };
+
+        match library_map.get(csl_library.resolve(csl_id)) {
+            Some(item) => {
+                let locator = locator
+                    .as_ref()
+                    .map(|(l, p)| SpecificLocator(*l, LocatorPayload::Str(p)));
+
+                driver.citation(CitationRequest::new(
+                    vec![CitationItem::new(*item, locator, None, false, purpose)],
+                    &style,
+                    None,
+                    &locales,
+                    *note_number,
+                ));
+                found.push(true);
+            }
+            None => {
+                warn!(
+                    slog_scope::logger(),
+                    "{} was not found in the CSL JSON library", reference
+                );
+                eprintln!(
+                    "  {} {} was not found in the CSL JSON library",
+                    Color::Yellow.paint("WARN"),
+                    reference
+                );
+                found.push(false);
+            }
+        }
+    }
+
+    let rendered = driver.finish(BibliographyRequest::new(&style, None, &locales));
+
+    // Match the rendered citations back up with the citations in the tree.
+    let mut rendered_citations = rendered.citations.iter();
+    let mut cites: Vec<String> = Vec::with_capacity(citations.len());
+    for (i, (branch, _)) in citations.iter().enumerate() {
+        if found[i] {
+            let Some(rendered_citation) = rendered_citations.next() else {
+                return Err(format!(
+                    "The CSL style rendered {} citations, but {} were cited",
+                    rendered.citations.len(),
+                    found.iter().filter(|f| **f).count()
+                ));
+            };
+            cites.push(write_children(&rendered_citation.citation));
+        } else if let Branch::Citation(citation) = branch {
+            cites.push(citation.reference.to_string());
+        } else if let Branch::Narrative(narrative) = branch {
+            cites.push(narrative.reference.to_string());
+        }
+    }
+
+    let mut output = String::new();
+    let mut cites = cites.into_iter();
+    let mut definitions = Vec::new();
+    let mut labels = Labels::new(tree);
+    for branch in tree {
+        let rendered = render_branch(branch, crossref_map, &mut cites);
+
+        if let Branch::Footnote(footnote) = branch {
+            let (text, definition) = footnote.wrap(&rendered, &mut labels);
+            output.push_str(&text);
+            definitions.extend(definition);
+        } else {
+            output.push_str(&rendered);
+        }
+    }
+    append_definitions(&mut output, &definitions);
+
+    if bibliography {
+        if let Some(rendered_bibliography) = rendered.bibliography {
+            if !rendered_bibliography.items.is_empty() {
+                let entries: Vec<String> = rendered_bibliography
+                    .items
+                    .iter()
+                    .map(|item| write_children(&item.content))
+                    .collect();
+
+                output.push_str("\n\n# References {.unnumbered}\n\n");
+                output.push_str(&entries.join("\n\n"));
+                output.push('\n');
+            }
+        } else {
+            warn!(
+                slog_scope::logger(),
+                "The CSL style has no bibliography; skipping the bibliography"
+            );
+            eprintln!(
+                "  {} The CSL style has no bibliography; skipping the bibliography",
+                Color::Yellow.paint("WARN"),
+            );
+        }
+    }
+
+    debug!(slog_scope::logger(), "CSL rendering complete");
+    Ok(output)
+}
+
+/// Converts a [`CSLSource`] back into CSL JSON.
+fn source_to_json(source: &CSLSource) -> Value {
+    let mut item: Map<String, Value> = source
+        .extra
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect();
+    item.insert("id".to_string(), json!(source.id));
+
+    let strings = [
+        ("type", &source.source_type),
+        ("container-title", &source.container_title),
+        ("container-title-short", &source.container_title_short),
+        ("authority", &source.authority),
+        ("jurisdiction", &source.jurisdiction),
+        ("division", &source.division),
+        ("edition", &source.edition),
+        ("number", &source.number),
+        ("page", &source.page),
+        ("publisher", &source.publisher),
+        ("title", &source.title),
+        ("title-short", &source.title_short),
+        ("URL", &source.url),
+        ("volume", &source.volume),
+    ];
+    for (name, value) in strings {
+        if let Some(v) = value {
+            item.insert(name.to_string(), json!(v));
+        }
+    }
+
+    let names = [
+        ("author", &source.author),
+        ("editor", &source.editor),
+        ("translator", &source.translator),
+    ];
+    for (name, value) in names {
+        if let Some(v) = value {
+            let names: Vec<Value> = v
+                .iter()
+                .map(|n| {
+                    let parts = [
+                        ("family", &n.family),
+                        ("given", &n.given),
+                        ("dropping-particle", &n.dropping_particle),
+                        ("non-dropping-particle", &n.non_dropping_particle),
+                        ("suffix", &n.suffix),
+                        ("literal", &n.literal),
+                    ];
+                    let mut name = Map::new();
+                    for (part, value) in parts {
+                        if let Some(v) = value {
+                            name.insert(part.to_string(), json!(v));
+                        }
+                    }
+                    Value::Object(name)
+                })
+                .collect();
+            item.insert(name.to_string(), Value::Array(names));
+        }
+    }
+
+    if let Some(issued) = &source.issued {
+        let mut date = Map::new();
+        if let Some(date_parts) = &issued.date_parts {
+            date.insert("date-parts".to_string(), json!(date_parts));
+        }
+        if let Some(season) = &issued.season {
+            date.insert("season".to_string(), json!(season));
+        }
+        if issued.circa {
+            date.insert("circa".to_string(), json!(true));
+        }
+        if let Some(literal) = &issued.literal {
+            date.insert("literal".to_string(), json!(literal));
+        }
+        item.insert("issued".to_string(), Value::Object(date));
+    }
+
+    Value::Object(item)
+}
+
+/// Collects the citations in the tree (including those in the body text),
+/// recursing into footnotes and parentheticals.
+fn collect_citations<'a>(
+    tree: &'a [Branch<'a>],
+    note_number: Option<usize>,
+    citations: &mut Vec<(&'a Branch<'a>, Option<usize>)>,
+) {
+    for branch in tree {
+        match branch {
+            Branch::Footnote(footnote) => collect_citations(
+                &footnote.contents,
+                Some(footnote.number as usize),
+                citations,
+            ),
+            Branch::Citation(citation) => {
+                citations.push((branch, note_number));
+                collect_citations(&citation.nested, note_number, citations);
+            }
+            Branch::Narrative(_) => citations.push((branch, note_number)),
+            _ => {}
+        }
+    }
+}
+
+/// Renders a branch, taking the citations from the already-rendered cites.
+fn render_branch(
+    branch: &Branch,
+    crossref_map: &HashMap<&str, i32>,
+    cites: &mut impl Iterator<Item = String>,
+) -> String {
+    trace!(slog_scope::logger(), "Rendering branch...");
+
+    match branch {
+        Branch::Text(text) => text.contents.to_string(),
+        Branch::Footnote(footnote) => {
+            let mut contents = String::new();
+            for footnote_branch in &footnote.contents {
+                contents.push_str(&render_branch(footnote_branch, crossref_map, cites));
+            }
+            // The footnote's markup is added by `render_csl`.
+            contents.trim().to_string()
+        }
+        Branch::Citation(citation) => {
+            let mut contents = String::new();
+
+            if let Some(r) = &citation.pre_cite {
+                match r {
+                    PreCite::Punctuation(p) => contents.push_str(p.contents),
+                    PreCite::Signal(s) => contents.push_str(s.contents),
+                }
+            }
+
+            let cite = cites.next().unwrap_or_default();
+            contents.push_str(&cite);
+
+            if let Some(parenthetical) = citation.parenthetical {
+                contents.push(' ');
+                if citation.nested.is_empty() {
+                    contents.push_str(parenthetical);
+                } else {
+                    contents.push('(');
+                    for nested_branch in &citation.nested {
+                        contents.push_str(&render_branch(nested_branch, crossref_map, cites));
+                    }
+                    contents.push(')');
+                }
+            }
+
+            // Don't double up the period after an abbreviation like "ibid."
+            if citation.parenthetical.is_some()
+                || !(citation.punctuation == "." && cite.ends_with('.'))
+            {
+                contents.push_str(citation.punctuation);
+            }
+
+            contents
+        }
+        Branch::CrossRef(crossref) => {
+            if crossref_map.contains_key(crossref.contents) {
+                crossref_map[crossref.contents].to_string()
+            } else {
+                eprintln!(
+                    "{} Pre-processing error: no footnote with the id \"{}\"",
+                    Color::Red.paint("ERRO"),
+                    crossref.contents
+                );
+                crossref.contents.to_string()
+            }
+        }
+        Branch::CiteBreak => "".to_string(),
+        Branch::Narrative(_) => cites.next().unwrap_or_default(),
+    }
+}
+
+/// Writes the CSL processor's output as markdown.
+///
+/// Italics use asterisks. Bold and small caps both use double asterisks, which
+/// the `--smallcaps` option can convert to true small caps.
+fn write_children(children: &ElemChildren) -> String {
+    let mut output = String::new();
+
+    for child in &children.0 {
+        match child {
+            ElemChild::Text(formatted) => {
+                output.push_str(&format_text(&formatted.text, &formatted.formatting))
+            }
+            ElemChild::Elem(elem) => output.push_str(&write_children(&elem.children)),
+            ElemChild::Markup(markup) => output.push_str(markup),
+            ElemChild::Link { text, .. } => {
+                output.push_str(&format_text(&text.text, &text.formatting))
+            }
+            ElemChild::Transparent { .. } => {}
+        }
+    }
+
+    output
+}
+
+/// Adds markdown formatting to a piece of text.
+fn format_text(text: &str, formatting: &Formatting) -> String {
+    if text.trim().is_empty() {
+        return text.to_string();
+    }
+
+    // Markdown emphasis can't start or end with whitespace.
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+    let mut inner = text.trim().to_string();
+
+    if formatting.font_style == FontStyle::Italic {
+        inner = format!("*{}*", inner);
+    }
+
+    if formatting.font_weight == FontWeight::Bold
+        || formatting.font_variant == FontVariant::SmallCaps
+    {
+        inner = format!("**{}**", inner);
+    }
+
+    format!("{}{}{}", leading, inner, trailing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting() {
+        let italic = Formatting {
+            font_style: FontStyle::Italic,
+            ..Default::default()
+        };
+        let small_caps = Formatting {
+            font_variant: FontVariant::SmallCaps,
+            ..Default::default()
+        };
+
+        assert_eq!(format_text(" A Title ", &italic), " *A Title* ");
+        assert_eq!(format_text("Author", &small_caps), "**Author**");
+        assert_eq!(format_text("plain", &Formatting::default()), "plain");
+    }
+}
@@ -0,0 +1,111 @@
+//! This module contains the errors found in the input.
+//!
+//! An error keeps the part of the input it's about, which borrows from the
+//! input like a token's contents do. That gives each error a byte span (see
+//! [`span_of`]), which is reported as a line and column.
+
+use std::ops::Range;
+
+/// A problem with the input.
+///
+/// * `source`: The part of the input with the problem.
+/// * `message`: What's wrong.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SourceError<'a> {
+    pub source: &'a str,
+    pub message: String,
+}
+
+impl<'a> SourceError<'a> {
+    /// Creates a new [`SourceError`].
+    pub fn new(source: &'a str, message: &str) -> SourceError<'a> {
+        SourceError {
+            source,
+            message: message.to_string(),
+        }
+    }
+
+    /// The byte span of the problem in the input, if its source is part of the
+    /// input.
+    pub fn span(&self, input: &str) -> Option<Range<usize>> {
+        span_of(self.source, input)
+    }
+
+    /// Describe the problem, with the line and column where it starts (if it's
+    /// known).
+    pub fn describe(&self, input: &str) -> String {
+        match self.span(input) {
+            Some(span) => {
+                let (line, column) = line_column(input, span.start);
+                format!("line {}, column {}: {}", line, column, self.message)
+            }
+            None => self.message.clone(),
+        }
+    }
+}
+
+/// The byte span of `part` in `input`, or `None` if `part` isn't a slice of
+/// `input`.
+///
+/// Tokens, branches, and errors all borrow from the input, so a span starts at
+/// the distance between the two.
+pub fn span_of(part: &str, input: &str) -> Option<Range<usize>> {
+    let start = (part.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+    (start + part.len() <= input.len()).then_some(start..start + part.len())
+}
+
+/// The line and column of a byte offset in the input.
+///
+/// Both start at one, and the column counts characters, not bytes.
+pub fn line_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |n| n + 1);
+    let column = before[line_start..].chars().count() + 1;
+
+    (line, column)
+}
+
+/// Describe every problem found in the input, one per line.
+pub fn describe_all(errors: &[SourceError], input: &str) -> String {
+    match errors {
+        [error] => error.describe(input),
+        _ => {
+            let mut description = format!("{} problems found in the input:", errors.len());
+            for error in errors {
+                description.push_str("\n  ");
+                description.push_str(&error.describe(input));
+            }
+            description
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_and_column() {
+        let input = "First line.\nSé^[cond [@x]]";
+        assert_eq!(line_column(input, 0), (1, 1));
+        assert_eq!(line_column(input, 12), (2, 1));
+        assert_eq!(line_column(input, input.find('^').unwrap()), (2, 3));
+    }
+
+    #[test]
+    fn describe() {
+        let input = "Text.^[A note [@x] at 5]";
+        let error = SourceError::new(&input[14..], "A citation without ending punctuation");
+        assert_eq!(error.span(input), Some(14..input.len()));
+        assert_eq!(
+            error.describe(input),
+            "line 1, column 15: A citation without ending punctuation"
+        );
+
+        // A source from somewhere else has no place in the input.
+        let error = SourceError::new("[@x]", "Elsewhere");
+        assert_eq!(error.span(input), None);
+        assert_eq!(error.describe(input), "Elsewhere");
+    }
+}
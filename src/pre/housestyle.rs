@@ -0,0 +1,112 @@
+//! This module contains functionality related to house-style files.
+//!
+//! Law reviews differ in small ways from the Bluebook and from each other. A
+//! house-style file records a journal's preferences so that switching journals
+//! only requires switching files. Any preference left out of the file follows
+//! the Bluebook.
+
+use ron::de::from_str;
+use serde::Deserialize;
+use slog::debug;
+
+/// A journal's house-style preferences.
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct HouseStyle {
+    /// The word following "*supra*" in short cites, *e.g.*, "note" or "n."
+    pub supra_note: String,
+    /// Whether hereinafters go in parentheses rather than brackets.
+    pub hereinafter_parentheses: bool,
+    /// The word joining the last two authors, *e.g.*, "&" or "and."
+    pub author_conjunction: String,
+    /// The number of authors that triggers "et al." in short cites.
+    pub short_et_al: usize,
+    /// The number of authors that triggers "et al." in long cites, if any.
+    pub long_et_al: Option<usize>,
+}
+
+impl Default for HouseStyle {
+    fn default() -> Self {
+        HouseStyle {
+            supra_note: "note".to_string(),
+            hereinafter_parentheses: false,
+            author_conjunction: "&".to_string(),
+            short_et_al: 3,
+            long_et_al: None,
+        }
+    }
+}
+
+/// Create the house style.
+///
+/// An "et al." limit below two would shorten a lone author, so it's an error.
+pub fn build_house_style(input: &str) -> Result<HouseStyle, String> {
+    let house_style: HouseStyle = match from_str(input) {
+        Ok(h) => h,
+        Err(e) => {
+            let err_msg = format!("error deserializing the house-style file—{}", e);
+            return Err(err_msg);
+        }
+    };
+
+    for (name, limit) in [
+        ("short_et_al", Some(house_style.short_et_al)),
+        ("long_et_al", house_style.long_et_al),
+    ] {
+        if let Some(limit) = limit.filter(|l| *l < 2) {
+            return Err(format!(
+                "error in the house-style file—{} must be at least 2, not {}",
+                name, limit
+            ));
+        }
+    }
+
+    debug!(slog_scope::logger(), "House-style file parsed");
+    Ok(house_style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_build() {
+        let ron_string = r#"
+(
+    supra_note: "n.",
+    hereinafter_parentheses: true,
+    author_conjunction: "and",
+)
+"#;
+        let output = build_house_style(ron_string).unwrap();
+
+        assert_eq!(&output.supra_note, "n.");
+        assert!(output.hereinafter_parentheses);
+        assert_eq!(&output.author_conjunction, "and");
+        assert_eq!(output.short_et_al, 3);
+        assert_eq!(output.long_et_al, None);
+    }
+
+    #[test]
+    fn empty_build() {
+        assert_eq!(build_house_style("()").unwrap(), HouseStyle::default());
+    }
+
+    #[test]
+    fn bad_et_al() {
+        assert_eq!(
+            build_house_style("(short_et_al: 1)"),
+            Err("error in the house-style file—short_et_al must be at least 2, not 1".to_string())
+        );
+        assert_eq!(
+            build_house_style("(long_et_al: Some(0))"),
+            Err("error in the house-style file—long_et_al must be at least 2, not 0".to_string())
+        );
+        assert_eq!(
+            build_house_style("(long_et_al: Some(2))")
+                .unwrap()
+                .long_et_al,
+            Some(2)
+        );
+    }
+}
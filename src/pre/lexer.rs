@@ -1,9 +1,19 @@
 //! This module contains the Supra lexer. It converts a string (formatted in
 //! Pandoc-style markdown) into a collection of tokens.
 
+use crate::pre::error::{self, SourceError};
+use ansi_term::Color;
 use lazy_static::lazy_static;
 use regex::Regex;
-use slog::{debug, o, trace};
+use slog::{debug, o, trace, warn};
+use std::{collections::HashMap, ops::Range};
+
+lazy_static! {
+    /// Regex for the first line of a reference footnote's definition.
+    ///
+    /// Captures the label, *e.g.*, `1` in `[^1]: The note.`
+    static ref DEFINITION: Regex = Regex::new(r"^ {0,3}\[\^([^\]\s]+)\]:[ \t]*").unwrap();
+}
 
 /// The lexer that works through the input string.
 struct Lexer {
@@ -38,11 +48,20 @@ enum Context {
     Reference,
     Pincite,
     Parenthetical,
+    Modifiers,
     CitePunctuation,
     CiteBreak,
     None,
 }
 
+/// The definition of a reference footnote, *e.g.*, `[^1]: The note.`
+struct NoteDefinition<'a> {
+    /// The bytes of the whole definition, including its final newline.
+    block: Range<usize>,
+    /// The note itself, after the label.
+    contents: &'a str,
+}
+
 /// The tokenized parts of the input string.
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -59,6 +78,12 @@ impl Token<'_> {
             contents,
         }
     }
+
+    /// The byte span of the token in the input it was lexed from, if it was
+    /// lexed from `input`.
+    pub fn span(&self, input: &str) -> Option<Range<usize>> {
+        error::span_of(self.contents, input)
+    }
 }
 
 /// The types of tokens.
@@ -76,10 +101,12 @@ pub enum TokenType {
     CrossRef,
     CiteBreak,
     CloseFootnote,
+    Narrative,
+    Modifiers,
 }
 
 /// The main lexer function.
-pub fn lexer(input: &str) -> Result<Vec<Token>, String> {
+pub fn lexer(input: &str) -> Result<Vec<Token<'_>>, Vec<SourceError<'_>>> {
     debug!(slog_scope::logger(), "Starting lexer...");
 
     match slog_scope::scope(
@@ -98,27 +125,171 @@ pub fn lexer(input: &str) -> Result<Vec<Token>, String> {
 ///
 /// The text lexer is the first lexer function called. It should start with text
 /// and then look for other types of tokens, sending them to other lexers when
-/// needed.
+/// needed. A problem in one footnote doesn't stop it from lexing the rest, so
+/// every problem is reported at once.
 ///
 /// It doesn't assume that the input starts with text. It instead accounts for
 /// the possibility of starting with a footnote. Why? I have no idea.
-fn text_lexer(input: &str) -> Result<Vec<Token>, String> {
+fn text_lexer(input: &str) -> Result<Vec<Token<'_>>, Vec<SourceError<'_>>> {
     trace!(slog_scope::logger(), "Starting text lexer...");
 
     let mut lexer = Lexer::new();
     let mut lex: Vec<Token> = Vec::new();
+    let mut errors: Vec<SourceError> = Vec::new();
+
+    // Parts of the input that aren't Markdown text are passed through.
+    let verbatim = verbatim(input, true);
+    let mut regions = verbatim.iter().peekable();
+
+    // Reference footnotes are lexed where they're referenced, so their
+    // definitions are skipped.
+    let definitions: Vec<_> = note_definitions(input)
+        .into_iter()
+        .filter(|(_, d)| !verbatim.iter().any(|r| r.contains(&d.block.start)))
+        .collect();
+    let mut labels: HashMap<&str, &NoteDefinition> = HashMap::new();
+    for (label, definition) in &definitions {
+        labels.entry(label).or_insert(definition);
+    }
+    let mut blocks = definitions.iter().map(|(_, d)| &d.block).peekable();
+    let mut skip_to = 0;
 
     // Determine if we're starting with text or a footnote. I can't imagine
     // starting with a footnote, but I'm accounting for it.
-    if input.as_bytes()[0] == b'^' && input.as_bytes()[1] == b'[' {
+    if input.starts_with("^[") {
         lexer.context = Context::Footnote;
     } else {
         lexer.context = Context::Text;
     }
 
     for (i, c) in input.bytes().enumerate() {
+        // Skipped bytes never start a footnote or citation.
+        if i < skip_to {
+            lexer.last_char = None;
+            continue;
+        }
+
+        // Pass over the parts that aren't Markdown text, which stay in the
+        // current token.
+        while regions.peek().is_some_and(|r| r.end <= i) {
+            regions.next();
+        }
+        if let Some(region) = regions.peek().filter(|r| r.start == i) {
+            skip_to = region.end;
+            continue;
+        }
+
         if lexer.context == Context::Text {
-            if c == b'[' && lexer.last_char == Some(b'^') {
+            // Skip definitions that start before the next footnote.
+            while blocks.peek().is_some_and(|b| b.end <= i) {
+                blocks.next();
+            }
+            if let Some(block) = blocks.peek().filter(|b| b.start == i) {
+                trace!(
+                    slog_scope::logger(),
+                    "Pushing token type {:?} containing {:?}",
+                    TokenType::Text,
+                    &input[lexer.start..i]
+                );
+                lex.push(Token::new(TokenType::Text, &input[lexer.start..i]));
+                lexer.start = block.end;
+                skip_to = block.end;
+                lexer.last_char = Some(c);
+                continue;
+            }
+
+            // A citation in the body text.
+            if let Some(length) = narrative_length(input, i) {
+                trace!(
+                    slog_scope::logger(),
+                    "Pushing token type {:?} containing {:?}",
+                    TokenType::Narrative,
+                    &input[i..i + length]
+                );
+                lex.push(Token::new(TokenType::Text, &input[lexer.start..i]));
+                lex.push(Token::new(TokenType::Narrative, &input[i..i + length]));
+                lexer.start = i + length;
+                skip_to = i + length;
+                continue;
+            }
+
+            // A cross-reference in the body text.
+            if let Some(length) = cross_ref_length(input, i, lexer.last_char) {
+                trace!(
+                    slog_scope::logger(),
+                    "Pushing token type {:?} containing {:?}",
+                    TokenType::CrossRef,
+                    &input[i..i + length]
+                );
+                lex.push(Token::new(TokenType::Text, &input[lexer.start..i]));
+                lex.push(Token::new(TokenType::CrossRef, &input[i..i + length]));
+                lexer.start = i + length;
+                skip_to = i + length;
+                continue;
+            }
+
+            // A citation in brackets, which only works in a footnote, stays
+            // in the text as it is.
+            let bracketed = if lexer.last_char == Some(b'^') {
+                None
+            } else {
+                bracketed_cite_length(input, i)
+            };
+            if let Some(length) = bracketed {
+                let cite = input[i..i + length].trim_end_matches(|c| c != ']');
+                warn!(
+                    slog_scope::logger(),
+                    "{} is in the body text, so it's left as it is; use a footnote or @key", cite
+                );
+                eprintln!(
+                    "  {} {} is in the body text, so it's left as it is; use a footnote or @key",
+                    Color::Yellow.paint("WARN"),
+                    cite
+                );
+                skip_to = i + length;
+                continue;
+            }
+
+            // A reference to a defined reference footnote.
+            let reference = if c == b'^' && lexer.last_char == Some(b'[') {
+                input[i + 1..]
+                    .find(']')
+                    .map(|j| (&input[i + 1..i + 1 + j], i + 1 + j))
+                    .filter(|(label, _)| !label.is_empty())
+                    .and_then(|(label, end)| labels.get(label).map(|d| (d, end)))
+            } else {
+                None
+            };
+
+            if let Some((definition, end)) = reference {
+                trace!(
+                    slog_scope::logger(),
+                    "Pushing token type {:?} containing {:?}",
+                    TokenType::Text,
+                    &input[lexer.start..i - 1]
+                );
+                lex.push(Token::new(TokenType::Text, &input[lexer.start..i - 1]));
+
+                match slog_scope::scope(
+                    &slog_scope::logger().new(o!("fn" => "note_lexer()")),
+                    || note_lexer(definition.contents),
+                ) {
+                    Ok(r) => {
+                        // The opening token is the reference, and the closing
+                        // token is the (empty) end of the definition.
+                        lex.push(Token::new(TokenType::OpenFootnote, &input[i - 1..end + 1]));
+                        lex.extend(r);
+                        lex.push(Token::new(
+                            TokenType::CloseFootnote,
+                            &definition.contents[definition.contents.len()..],
+                        ));
+                    }
+                    Err(e) => errors.extend(e),
+                }
+
+                lexer.start = end + 1;
+                skip_to = end + 1;
+            } else if c == b'[' && lexer.last_char == Some(b'^') {
                 // An opening footnote has been found. Crate a token from the
                 // preceding text, move the lexer back a spot, and loop around
                 // to work on the footnote.
@@ -159,7 +330,10 @@ fn text_lexer(input: &str) -> Result<Vec<Token>, String> {
                             TokenType::OpenFootnote,
                             "^["
                         );
-                        lex.push(Token::new(TokenType::OpenFootnote, "^["));
+                        lex.push(Token::new(
+                            TokenType::OpenFootnote,
+                            &input[lexer.start..lexer.start + 2],
+                        ));
 
                         for token in r {
                             trace!(
@@ -177,9 +351,9 @@ fn text_lexer(input: &str) -> Result<Vec<Token>, String> {
                             TokenType::CloseFootnote,
                             "]"
                         );
-                        lex.push(Token::new(TokenType::CloseFootnote, "]"));
+                        lex.push(Token::new(TokenType::CloseFootnote, &input[i..i + 1]));
                     }
-                    Err(e) => return Err(e),
+                    Err(e) => errors.extend(e),
                 };
 
                 // Back to text, and move the lexer ahead one.
@@ -189,7 +363,7 @@ fn text_lexer(input: &str) -> Result<Vec<Token>, String> {
         }
 
         // End of the input...
-        if i == input.bytes().len() - 1 && lexer.start != i {
+        if i == input.len() - 1 {
             if lexer.context == Context::Text {
                 trace!(
                     slog_scope::logger(),
@@ -199,21 +373,351 @@ fn text_lexer(input: &str) -> Result<Vec<Token>, String> {
                 );
                 lex.push(Token::new(TokenType::Text, &input[lexer.start..i + 1]));
             } else {
-                return Err("The input string ends with an open footnote.\nEnsure footnotes contain matching brackets before running.".to_string());
+                errors.push(SourceError::new(
+                    &input[lexer.start..],
+                    "This footnote is never closed; make sure its brackets match",
+                ));
             }
         }
 
         lexer.last_char = Some(c);
     }
 
+    // The end of the input is skipped if it's in a part that isn't text.
+    if skip_to >= input.len() && lexer.start < input.len() {
+        if lexer.context == Context::Text {
+            lex.push(Token::new(TokenType::Text, &input[lexer.start..]));
+        } else {
+            errors.push(SourceError::new(
+                &input[lexer.start..],
+                "This footnote is never closed; make sure its brackets match",
+            ));
+        }
+    }
+
     trace!(slog_scope::logger(), "Text lexer complete.");
-    Ok(lex)
+    if errors.is_empty() {
+        Ok(lex)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Find the length of a citation in the body text, if one starts at `i`.
+///
+/// Body-text citations are either a key (`@smith2021`), which names the
+/// source's author, or a key in brackets with the author suppressed
+/// (`[-@smith2021]`), which names the source. Like Pandoc, a bare key can't
+/// follow a letter or number (so email addresses aren't keys), and it ends
+/// before any punctuation that isn't followed by a letter or number.
+fn narrative_length(input: &str, i: usize) -> Option<usize> {
+    let bytes = input.as_bytes();
+
+    if bytes[i..].starts_with(b"[-@") {
+        let end = input[i..].find(']')?;
+        let key = &input[i + 3..i + end];
+        return (!key.is_empty() && !key.contains(char::is_whitespace)).then_some(end + 1);
+    }
+
+    if bytes[i] != b'@'
+        || (i > 0 && !bytes[i - 1].is_ascii_whitespace() && !b"(*\"'".contains(&bytes[i - 1]))
+    {
+        return None;
+    }
+
+    let key_char = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+    let mut length = 1;
+    while let Some(b) = bytes.get(i + length) {
+        if key_char(b) {
+            length += 1;
+        } else if b":.#$%&-+?<>~/".contains(b) && bytes.get(i + length + 1).is_some_and(key_char) {
+            length += 2;
+        } else {
+            break;
+        }
+    }
+
+    (length > 1 && key_char(&bytes[i + 1])).then_some(length)
+}
+
+/// Find the length of a citation in brackets, if one starts at `i`.
+///
+/// This is either a citation in Supra's syntax (`[@smith2021]`) or a group of
+/// Pandoc citations (`[see @smith2021, 12]`).
+fn bracketed_cite_length(input: &str, i: usize) -> Option<usize> {
+    if input.as_bytes()[i] != b'[' {
+        return None;
+    }
+    let rest = &input[i..];
+
+    if rest.starts_with("[@") {
+        let end = rest.find(']')?;
+        return (end > 2 && !rest[2..end].contains(char::is_whitespace)).then_some(end + 1);
+    }
+
+    group_lexer(rest).map(|(_, length)| length)
+}
+
+/// Find the length of a cross-reference in the body text, if one starts at `i`.
+///
+/// A cross-reference is an id in brackets after a question mark, *e.g.*,
+/// `[?remedies]`. The id can't be empty or contain whitespace, and a bracket
+/// right after a caret (`last_char`) opens a footnote instead.
+fn cross_ref_length(input: &str, i: usize, last_char: Option<u8>) -> Option<usize> {
+    if !input.as_bytes()[i..].starts_with(b"[?") || last_char == Some(b'^') {
+        return None;
+    }
+
+    let end = input[i..].find(']')?;
+    let id = &input[i + 2..i + end];
+    (!id.is_empty() && !id.contains(char::is_whitespace)).then_some(end + 1)
+}
+
+/// Find the parts of the input that aren't Markdown text.
+///
+/// Pandoc doesn't look for footnotes or citations in YAML metadata, code, HTML
+/// comments, TeX math, or backslash escapes, so neither does Supra. The lexers
+/// pass these parts through as they are. YAML metadata and fenced code are
+/// blocks, which are only found if `blocks` is true (they can't be in an inline
+/// footnote). Returns the byte ranges in order.
+fn verbatim(input: &str, blocks: bool) -> Vec<Range<usize>> {
+    let mut block_ranges: Vec<Range<usize>> = Vec::new();
+
+    if blocks {
+        // The open fence's character, length, and start.
+        let mut open: Option<(u8, usize, usize)> = None;
+        let mut blank = true;
+        let mut line_start = 0;
+        let mut skip_to = 0;
+
+        for line in input.split_inclusive('\n') {
+            let text = line.trim_end_matches(['\n', '\r']);
+            let line_end = line_start + line.len();
+            let indented = text.trim_start_matches(' ');
+            let fence = |c: u8| indented.bytes().take_while(|&b| b == c).count();
+
+            // The lines of a YAML block.
+            if line_start < skip_to {
+                blank = text.trim().is_empty();
+                line_start = line_end;
+                continue;
+            }
+
+            match open {
+                Some((c, length, start)) => {
+                    if fence(c) >= length
+                        && indented.trim_start_matches(c as char).trim().is_empty()
+                    {
+                        block_ranges.push(start..line_end);
+                        open = None;
+                    }
+                }
+                None => {
+                    if text.len() - indented.len() < 4 && (fence(b'`') >= 3 || fence(b'~') >= 3) {
+                        let c = indented.as_bytes()[0];
+                        open = Some((c, fence(c), line_start));
+                    } else if blank && text.trim_end() == "---" {
+                        if let Some(end) = yaml_end(input, line_start, line_end) {
+                            block_ranges.push(line_start..end);
+                            skip_to = end;
+                        }
+                    }
+                }
+            }
+
+            blank = text.trim().is_empty();
+            line_start = line_end;
+        }
+
+        // An unclosed fence runs to the end of the input.
+        if let Some((_, _, start)) = open {
+            block_ranges.push(start..input.len());
+        }
+    }
+
+    // Inline parts, which don't cross a blank line (except comments).
+    let bytes = input.as_bytes();
+    let paragraph_end = |from: usize| input[from..].find("\n\n").map_or(input.len(), |n| from + n);
+    // A cite break (`[$]`) isn't math.
+    let cite_break = |i: usize| i > 0 && bytes[i - 1] == b'[' && bytes.get(i + 1) == Some(&b']');
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut blocks_iter = block_ranges.into_iter().peekable();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if let Some(block) = blocks_iter.next_if(|b| b.start <= i) {
+            i = block.end;
+            ranges.push(block);
+            continue;
+        }
+        let limit = blocks_iter.peek().map_or(bytes.len(), |b| b.start);
+
+        let end = match bytes[i] {
+            b'\\' if bytes.get(i + 1).is_some_and(|b| b.is_ascii_punctuation()) => Some(i + 2),
+            b'`' => {
+                let length = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                let mut end = None;
+                let mut j = i + length;
+                let stop = paragraph_end(i).min(limit);
+                while let Some(k) = input[j..stop].find('`') {
+                    let run = bytes[j + k..].iter().take_while(|&&b| b == b'`').count();
+                    if run == length {
+                        end = Some(j + k + run);
+                        break;
+                    }
+                    j += k + run;
+                }
+
+                // A run of backticks without a match is literal.
+                if end.is_none() {
+                    i += length;
+                    continue;
+                }
+                end
+            }
+            b'<' if input[i..].starts_with("<!--") => {
+                input[i + 4..limit].find("-->").map(|n| i + 4 + n + 3)
+            }
+            b'$' if input[i..].starts_with("$$") => input[i + 2..paragraph_end(i).min(limit)]
+                .find("$$")
+                .map(|n| i + 2 + n + 2),
+            b'$' if !cite_break(i)
+                && bytes.get(i + 1).is_some_and(|b| !b.is_ascii_whitespace()) =>
+            {
+                let stop = paragraph_end(i).min(limit);
+                input[i + 1..stop]
+                    .match_indices('$')
+                    .map(|(n, _)| i + 1 + n)
+                    .find(|&j| {
+                        !bytes[j - 1].is_ascii_whitespace()
+                            && bytes[j - 1] != b'\\'
+                            && !cite_break(j)
+                            && !bytes.get(j + 1).is_some_and(|b| b.is_ascii_digit())
+                    })
+                    .map(|j| j + 1)
+            }
+            _ => None,
+        };
+
+        match end {
+            Some(end) => {
+                ranges.push(i..end);
+                i = end;
+            }
+            None => i += 1,
+        }
+    }
+
+    ranges
+}
+
+/// Find the end of a YAML metadata block whose opening `---` line runs from
+/// `start` to `body`, if it is one.
+///
+/// The block ends with a `---` or `...` line. A block at the start of the input
+/// is front matter. Anywhere else, `---` is usually a horizontal rule, so the
+/// block has to parse as a YAML mapping, like Pandoc requires. An unclosed block
+/// is just a horizontal rule.
+fn yaml_end(input: &str, start: usize, body: usize) -> Option<usize> {
+    if input[body..]
+        .lines()
+        .next()
+        .is_none_or(|l| l.trim().is_empty())
+    {
+        return None;
+    }
+
+    let mut line_start = body;
+    for line in input[body..].split_inclusive('\n') {
+        let text = line.trim_end();
+        if text == "---" || text == "..." {
+            let is_yaml = start == 0
+                || serde_yaml::from_str::<serde_yaml::Mapping>(&input[body..line_start]).is_ok();
+            return is_yaml.then_some(line_start + line.len());
+        }
+        line_start += line.len();
+    }
+
+    None
+}
+
+/// Find the definitions of reference footnotes.
+///
+/// A definition starts with `[^label]:` at the beginning of a line. It
+/// continues through the lines that follow it, and after a blank line through
+/// any lines indented by four spaces or a tab (for notes with several
+/// paragraphs). The definitions are returned in order, with their labels.
+fn note_definitions<'a>(input: &'a str) -> Vec<(&'a str, NoteDefinition<'a>)> {
+    let mut definitions = Vec::new();
+
+    // The label, start of the block, start of the contents, and end of the
+    // contents of the current definition.
+    let mut current: Option<(&str, usize, usize, usize)> = None;
+    let mut blank = false;
+    let mut line_start = 0;
+
+    // A finished definition's block runs through the end of its last line.
+    let finish = |(label, start, contents_start, contents_end): (&'a str, usize, usize, usize)| {
+        let newline = input[contents_end..]
+            .find('\n')
+            .map_or(input.len(), |n| contents_end + n + 1);
+        (
+            label,
+            NoteDefinition {
+                block: start..newline,
+                contents: &input[contents_start..contents_end],
+            },
+        )
+    };
+
+    for line in input.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let line_end = line_start + text.len();
+
+        if let Some(captures) = DEFINITION.captures(text) {
+            if let Some(c) = current.take() {
+                definitions.push(finish(c));
+            }
+            current = Some((
+                captures.get(1).unwrap().as_str(),
+                line_start,
+                line_start + captures.get(0).unwrap().end(),
+                line_end,
+            ));
+            blank = false;
+        } else if let Some((label, start, contents_start, _)) = current {
+            if text.trim().is_empty() {
+                blank = true;
+            } else if !blank || text.starts_with("    ") || text.starts_with('\t') {
+                current = Some((label, start, contents_start, line_end));
+                blank = false;
+            } else {
+                definitions.push(finish(current.take().unwrap()));
+            }
+        }
+
+        line_start += line.len();
+    }
+
+    if let Some(c) = current {
+        definitions.push(finish(c));
+    }
+
+    definitions
+}
+
+/// Lex an inline footnote.
+///
+/// The footnote (including the `^[` and `]`) is lexed by [`note_lexer`].
+fn footnote_lexer(input: &str) -> Result<Vec<Token<'_>>, Vec<SourceError<'_>>> {
+    note_lexer(&input[2..input.len() - 1])
 }
 
 /// Lex the contents of a footnote.
 ///
-/// Lexes the contents of a footnote (everything between the brackets) into
-/// tokens. Footnotes can contain the following:
+/// Lexes the contents of a footnote (everything between the brackets of an
+/// inline note, or after the label of a reference note) into tokens. Footnotes
+/// can contain the following:
 ///
 /// * A footnote ID.
 /// * Text before and after a citation clause or sentence.
@@ -222,7 +726,27 @@ fn text_lexer(input: &str) -> Result<Vec<Token>, String> {
 /// * The citation (see the citation lexer for its tokens).
 /// * A cite breaker.
 /// * A cross reference.
-fn footnote_lexer(input: &str) -> Result<Vec<Token>, String> {
+fn note_lexer(input: &str) -> Result<Vec<Token<'_>>, Vec<SourceError<'_>>> {
+    contents_lexer(input, false)
+}
+
+/// Lex the contents of a parenthetical.
+///
+/// A parenthetical can cite other sources, *e.g.*, `(quoting [@jones2001] at
+/// 5)`. Its contents (without the parentheses) are lexed like a footnote's,
+/// except that a citation can end with the parenthetical instead of with
+/// punctuation.
+pub fn parenthetical_lexer(input: &str) -> Result<Vec<Token<'_>>, Vec<SourceError<'_>>> {
+    contents_lexer(&input[1..input.len() - 1], true)
+}
+
+/// Lex the contents of a footnote or parenthetical.
+///
+/// See [`note_lexer`] and [`parenthetical_lexer`].
+fn contents_lexer(
+    input: &str,
+    parenthetical: bool,
+) -> Result<Vec<Token<'_>>, Vec<SourceError<'_>>> {
     trace!(
         slog_scope::logger(),
         "Starting footnote lexer for {}...",
@@ -230,26 +754,57 @@ fn footnote_lexer(input: &str) -> Result<Vec<Token>, String> {
     );
     let mut lexer = Lexer::new();
     let mut lex: Vec<Token> = Vec::new();
+    let mut errors: Vec<SourceError> = Vec::new();
 
     // Determine the starting block. It could be an ID, text (including pre-cite
-    // text), a citation, or a cite braker.
-    if input.as_bytes().get(2) == Some(&b'[') && input.as_bytes().get(3) == Some(&b'?') {
+    // text and citations), or a cite braker. A parenthetical has no ID.
+    if input.starts_with("[?") && !parenthetical {
         lexer.context = Context::ID;
-    } else if input.as_bytes().get(2) == Some(&b'[') && input.as_bytes().get(3) == Some(&b'@') {
-        lexer.context = Context::Citation;
-        // The number of open brackets needs to account for the opening footnote
-        // bracket.
-        lexer.open_brackets = -1;
-    } else if input.as_bytes().get(2) == Some(&b'[') && input.as_bytes().get(3) == Some(&b'$') {
+    } else if input.starts_with("[$") {
         lexer.context = Context::CiteBreak;
     } else {
         lexer.context = Context::Text;
     }
+    let mut skip_to = 0;
 
-    // The first two bytes are always b'^' and b'[', so skip those.
-    lexer.start = 2;
+    // Parts of the footnote that aren't Markdown text are passed through.
+    let verbatim = verbatim(input, false);
+    let mut regions = verbatim.iter().peekable();
 
     for (i, c) in input.bytes().enumerate() {
+        // Skipped bytes never start a footnote or citation.
+        if i < skip_to {
+            lexer.last_char = None;
+            continue;
+        }
+
+        while regions.peek().is_some_and(|r| r.end <= i) {
+            regions.next();
+        }
+        if let Some(region) = regions.peek().filter(|r| r.start == i) {
+            // Code, comments, and math after a citation (but not in its
+            // parenthetical) end the citation instead of becoming its pincite.
+            if lexer.context == Context::Citation
+                && lexer.open_brackets == 0
+                && lexer.open_parens == 0
+                && c != b'\\'
+            {
+                let end = lexer.start + input[lexer.start..i].trim_end().len();
+                match slog_scope::scope(
+                    &slog_scope::logger().new(o!("fn" => "cite_lexer()")),
+                    || cite_lexer(&input[lexer.start..end]),
+                ) {
+                    Ok(r) => lex.extend(r),
+                    Err(e) => errors.push(e),
+                }
+                lexer.context = Context::Text;
+                lexer.start = end;
+            }
+
+            skip_to = region.end;
+            continue;
+        }
+
         if lexer.context == Context::ID && c == b']' {
             // Found the end of an ID
             trace!(
@@ -274,7 +829,7 @@ fn footnote_lexer(input: &str) -> Result<Vec<Token>, String> {
             lexer.start = i + 1;
         } else if lexer.context == Context::Citation {
             // Look for the puncuation that ends the citation
-            if (c == b' ' || c == b']')
+            if (c.is_ascii_whitespace() || c == b']')
                 && (lexer.last_char == Some(b'.')
                     || lexer.last_char == Some(b',')
                     || lexer.last_char == Some(b';'))
@@ -297,13 +852,15 @@ fn footnote_lexer(input: &str) -> Result<Vec<Token>, String> {
                             lex.push(token);
                         }
                     }
-                    Err(e) => return Err(e),
+                    Err(e) => errors.push(e),
                 }
                 lexer.context = Context::Text;
                 lexer.start = i;
-            } else if c == b'[' {
+            } else if c == b'[' || c == b'{' {
+                // Braces (around a citation's modifiers) are balanced like
+                // brackets.
                 lexer.open_brackets += 1;
-            } else if c == b']' {
+            } else if c == b']' || c == b'}' {
                 lexer.open_brackets -= 1;
             } else if c == b'(' {
                 lexer.open_parens += 1;
@@ -323,8 +880,43 @@ fn footnote_lexer(input: &str) -> Result<Vec<Token>, String> {
             lexer.context = Context::Text;
             lexer.start = i + 1;
         } else if lexer.context == Context::Text {
-            // Look for a citation, crossref, or cite break.
-            if c == b'@' && lexer.last_char == Some(b'[') {
+            // Look for a group of citations, a citation, crossref, or cite
+            // break.
+            let group = if c == b'[' {
+                slog_scope::scope(
+                    &slog_scope::logger().new(o!("fn" => "group_lexer()")),
+                    || group_lexer(&input[i..]),
+                )
+            } else {
+                None
+            };
+
+            if let Some((tokens, length)) = group {
+                // If there was something before the group, send it to the
+                // pre-cite lexer.
+                if !&input[lexer.start..i].is_empty() {
+                    match slog_scope::scope(
+                        &slog_scope::logger().new(o!("fn" => "pre_cite_lexer()")),
+                        || pre_cite_lexer(&input[lexer.start..i]),
+                    ) {
+                        Ok(r) => lex.extend(r),
+                        Err(e) => errors.push(SourceError::new(&input[lexer.start..i], &e)),
+                    }
+                }
+
+                for token in tokens {
+                    trace!(
+                        slog_scope::logger(),
+                        "Pushing token type {:?} containing {:?}",
+                        token.token_type,
+                        token.contents,
+                    );
+                    lex.push(token);
+                }
+
+                lexer.start = i + length;
+                skip_to = i + length;
+            } else if c == b'@' && lexer.last_char == Some(b'[') {
                 // If there was something before the citation, send it to the
                 if !&input[lexer.start..i - 1].is_empty() {
                     match slog_scope::scope(
@@ -342,7 +934,7 @@ fn footnote_lexer(input: &str) -> Result<Vec<Token>, String> {
                                 lex.push(token);
                             }
                         }
-                        Err(e) => return Err(e),
+                        Err(e) => errors.push(SourceError::new(&input[lexer.start..i - 1], &e)),
                     }
                 }
 
@@ -386,7 +978,7 @@ fn footnote_lexer(input: &str) -> Result<Vec<Token>, String> {
                                 lex.push(token);
                             }
                         }
-                        Err(e) => return Err(e),
+                        Err(e) => errors.push(SourceError::new(&input[lexer.start..i - 1], &e)),
                     }
                 }
 
@@ -399,33 +991,83 @@ fn footnote_lexer(input: &str) -> Result<Vec<Token>, String> {
 
         // End of the input...
         if i == input.bytes().len() - 1 {
-            if lexer.context == Context::Text && c == b']' {
+            // A citation that ends the footnote ends with the input. One that
+            // ends a parenthetical doesn't need punctuation.
+            if lexer.context == Context::Citation
+                && (c == b'.' || c == b',' || c == b';' || parenthetical)
+                && lexer.open_brackets == 0
+                && lexer.open_parens == 0
+            {
+                match slog_scope::scope(
+                    &slog_scope::logger().new(o!("fn" => "cite_lexer()")),
+                    || cite_lexer(&input[lexer.start..]),
+                ) {
+                    Ok(r) => {
+                        for token in r {
+                            trace!(
+                                slog_scope::logger(),
+                                "Pushing token type {:?} containing {:?}",
+                                token.token_type,
+                                token.contents,
+                            );
+                            lex.push(token);
+                        }
+                    }
+                    Err(e) => errors.push(e),
+                }
+                lexer.context = Context::Text;
+                lexer.start = i + 1;
+            }
+
+            if lexer.context == Context::Text {
                 // If there was something before the end, add it as a text
                 // token.
-                if !&input[lexer.start..i].trim().is_empty() {
+                if !&input[lexer.start..].trim().is_empty() {
                     trace!(
                         slog_scope::logger(),
                         "End of input; pushing token type {:?} containing {:?}",
                         TokenType::Text,
-                        &input[lexer.start..i]
+                        &input[lexer.start..]
                     );
-                    lex.push(Token::new(TokenType::Text, &input[lexer.start..i]));
+                    lex.push(Token::new(TokenType::Text, &input[lexer.start..]));
                 }
             } else {
-                // If the footnote lexer ends on something other than Text, then
-                // there's probably no punctuation in the citation.
-                return Err(format!(
-                    "Something is wrong with the footnote \"{:?}\", probably a citation without ending punctuation.",
-                    input
-                ));
+                errors.push(unclosed(&input[lexer.start..], &lexer.context));
             }
         }
 
         lexer.last_char = Some(c);
     }
 
+    // The end of the input is skipped if it's in a part that isn't text.
+    if skip_to >= input.len() && lexer.start < input.len() {
+        if lexer.context == Context::Text {
+            lex.push(Token::new(TokenType::Text, &input[lexer.start..]));
+        } else {
+            errors.push(unclosed(&input[lexer.start..], &lexer.context));
+        }
+    }
+
     trace!(slog_scope::logger(), "Footnote lexer complete.");
-    Ok(lex)
+    if errors.is_empty() {
+        Ok(lex)
+    } else {
+        Err(errors)
+    }
+}
+
+/// The error for a part of a footnote that the end of the footnote leaves open.
+fn unclosed<'a>(source: &'a str, context: &Context) -> SourceError<'a> {
+    let message = match context {
+        Context::ID => "This footnote id is never closed",
+        Context::CrossRef => "This cross-reference is never closed",
+        Context::CiteBreak => "This cite breaker is never closed",
+        // If the footnote lexer ends in a citation, there's probably no
+        // punctuation after it.
+        _ => "This citation has no ending punctuation (or its brackets or parentheses don't match)",
+    };
+
+    SourceError::new(source, message)
 }
 
 /// Lex the text before a citation.
@@ -437,10 +1079,10 @@ fn pre_cite_lexer(input: &str) -> Result<Vec<Token>, String> {
     lazy_static! {
         /// Regex for finding the signal that ends pre-citation text.
         ///
-        /// Each signal has three forms: (1) an italicized version that starts a
-        /// sentence, (2) an italicized version that starts a clause, and (3) an
-        /// un-italicized version that is used as part of a sentence.
-        pub static ref SIGNAL: Regex = Regex::new("(?P<signal>\\*See generally, e\\.g\\.\\*,|\\*see generally, e\\.g\\.\\*,|see generally, e\\.g\\.,|\\*See generally\\*|\\*see generally\\*|see generally|\\*But cf\\., e\\.g\\.\\*,|\\*but cf\\., e\\.g\\.\\*,|but cf\\., e\\.g\\.,|\\*But cf\\.\\*|\\*but cf\\.\\*|but cf\\.|\\*But see, e\\.g\\.\\*,|\\*but see, e\\.g\\.\\*,|but see, e\\.g\\.,|\\*But see\\*|\\*but see\\*|but see|\\*Contra\\*|\\*contra\\*|contra|\\*Compare\\*|\\*compare\\*|compare|\\*with\\*|with|\\*Cf\\., e\\.g\\.\\*,|\\*cf\\., e\\.g\\.\\*,|cf\\., e\\.g\\.,|\\*Cf\\.\\*|\\*cf\\.\\*|cf\\.|\\*See also, e\\.g\\.\\*,|\\*see also, e\\.g\\.\\*,|see also, e\\.g\\.,|\\*See also\\*|\\*see also\\*|see also|\\*See, e\\.g\\.\\*,|\\*see, e\\.g\\.\\*,|see, e\\.g\\.,|\\*See\\*|\\*see\\*|see|\\*Accord\\*|\\*accord\\*|accord|\\*E\\.g\\.\\*|\\*e\\.g\\.\\*|e\\.g\\.)+\\s*$").unwrap();
+        /// Each signal can be italicized or not and capitalized or not (except
+        /// *with*), and a signal combined with *e.g.* can be missing its
+        /// commas. The parser normalizes them.
+        pub static ref SIGNAL: Regex = Regex::new(r"(?:^|[^\w*])(?P<signal>\*?(?:[Ss]ee generally|[Bb]ut cf\.|[Bb]ut see|[Cc]ontra|[Cc]ompare|with|[Cc]f\.|[Ss]ee also|[Ss]ee|[Aa]ccord|[Ee]\.g\.)\*?(?:,?\s+\*?e\.g\.)?\*?,?\s*)$").unwrap();
 
         /// Regex for finding punctuation before a citation clause or sentence.
         ///
@@ -463,7 +1105,7 @@ fn pre_cite_lexer(input: &str) -> Result<Vec<Token>, String> {
     // Because the regex looks for the signal followed by any whitespace and
     // then the end of the text, there should be zero or one matches in any
     // search.
-    let signal = SIGNAL.find(input);
+    let signal = SIGNAL.captures(input).and_then(|c| c.name("signal"));
 
     // Look for ending punctuation if there's no signal.
     let punctuation = match signal {
@@ -531,11 +1173,164 @@ fn pre_cite_lexer(input: &str) -> Result<Vec<Token>, String> {
     Ok(lex)
 }
 
+/// Lex a group of citations in Pandoc's syntax.
+///
+/// Pandoc puts any number of citations in one set of brackets, separated by
+/// semicolons, with an optional prefix before each key and locator after it,
+/// *e.g.*, `[*see* @smith2021, 12 (explaining); @jones2021]`. Each citation is
+/// lexed into the same tokens as one in Supra's syntax: the prefix into
+/// pre-cite tokens, the locator into a pincite, a parenthetical at the end
+/// into a parenthetical, and the semicolon into the citation's punctuation.
+/// The last citation's punctuation is the period, comma, or semicolon after the
+/// closing bracket, if there is one.
+///
+/// The input starts with the opening bracket. Returns `None` if the brackets
+/// don't contain a group, including a lone citation in Supra's syntax
+/// (`[@smith2021]`). Otherwise returns the tokens and the number of bytes they
+/// cover.
+pub fn group_lexer(input: &str) -> Option<(Vec<Token<'_>>, usize)> {
+    // Find the closing bracket.
+    let mut open_brackets = 0;
+    let close = input.bytes().position(|c| {
+        if c == b'[' {
+            open_brackets += 1;
+        } else if c == b']' {
+            open_brackets -= 1;
+        }
+        open_brackets == 0
+    })?;
+
+    let contents = &input[1..close];
+    if contents.starts_with('@')
+        && !contents.contains(|c: char| c.is_whitespace() || c == ',' || c == ';')
+    {
+        return None;
+    }
+
+    // Split the citations on semicolons outside parentheses and brackets.
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut item_start = 1;
+    for (i, c) in contents.bytes().enumerate() {
+        match c {
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth -= 1,
+            b';' if depth == 0 => {
+                items.push(item_start..i + 1);
+                item_start = i + 2;
+            }
+            _ => {}
+        }
+    }
+    items.push(item_start..close);
+
+    // Every citation in a group has a key.
+    let keys = items
+        .iter()
+        .map(|item| key_start(&input[item.clone()]).map(|k| item.start + k))
+        .collect::<Option<Vec<usize>>>()?;
+
+    trace!(
+        slog_scope::logger(),
+        "Starting group lexer for {}...",
+        &input[..close + 1]
+    );
+
+    let mut lex: Vec<Token> = Vec::new();
+    let mut length = close + 1;
+
+    for (n, (item, at)) in items.iter().zip(keys).enumerate() {
+        // The prefix, without any `-` that suppresses the author.
+        let prefix = &input[item.start..at];
+        let prefix = prefix.strip_suffix('-').unwrap_or(prefix);
+        if !prefix.is_empty() {
+            lex.extend(pre_cite_lexer(prefix).ok()?);
+        }
+
+        let key_end = input[at..item.end]
+            .find(|c: char| c.is_whitespace() || c == ',')
+            .map_or(item.end, |k| at + k);
+        lex.push(Token::new(TokenType::Reference, &input[at..key_end]));
+
+        // The locator, and a parenthetical after it.
+        let rest = input[key_end..item.end].trim_end();
+        let (locator, parenthetical) = split_parenthetical(rest);
+        let pincite = locator.trim().trim_start_matches(',').trim_start();
+        let pincite = ["pp.", "p.", "pages", "page"]
+            .iter()
+            .find_map(|label| pincite.strip_prefix(label))
+            .map_or(pincite, |p| p.trim_start());
+        if !pincite.is_empty() {
+            lex.push(Token::new(TokenType::Pincite, pincite));
+        }
+        if let Some(p) = parenthetical {
+            lex.push(Token::new(TokenType::Parenthetical, p));
+        }
+
+        if n < items.len() - 1 {
+            lex.push(Token::new(
+                TokenType::CitePunctuation,
+                &input[item.end..item.end + 1],
+            ));
+        } else {
+            if matches!(input.as_bytes().get(length), Some(b'.' | b',' | b';')) {
+                length += 1;
+            }
+            lex.push(Token::new(
+                TokenType::CitePunctuation,
+                &input[close + 1..length],
+            ));
+        }
+    }
+
+    trace!(slog_scope::logger(), "Group lexer complete.");
+    Some((lex, length))
+}
+
+/// Find the at symbol that starts the key in a citation from a group.
+///
+/// The at symbol starts the citation or follows whitespace or a `-`, so an
+/// email address in a prefix isn't mistaken for a key.
+fn key_start(item: &str) -> Option<usize> {
+    item.match_indices('@').map(|(i, _)| i).find(|&i| {
+        let before = &item[..i];
+        let before = before.strip_suffix('-').unwrap_or(before);
+        (before.is_empty() || before.ends_with(char::is_whitespace)) && i + 1 < item.len()
+    })
+}
+
+/// Split a parenthetical off the end of a citation.
+///
+/// Returns the text before the parenthetical and the parenthetical itself.
+fn split_parenthetical(input: &str) -> (&str, Option<&str>) {
+    if !input.ends_with(')') {
+        return (input, None);
+    }
+
+    let mut open_parens = 0;
+    for (i, c) in input.bytes().enumerate().rev() {
+        if c == b')' {
+            open_parens += 1;
+        } else if c == b'(' {
+            open_parens -= 1;
+            if open_parens == 0 {
+                if i == 0 || input.as_bytes()[i - 1].is_ascii_whitespace() {
+                    return (&input[..i], Some(&input[i..]));
+                }
+                break;
+            }
+        }
+    }
+
+    (input, None)
+}
+
 /// Lex a citation.
 ///
-/// Lexes a citation, which requires a reference and ending punctuation, and
-/// might include a pincite or parenthetical (or both).
-fn cite_lexer(input: &str) -> Result<Vec<Token>, String> {
+/// Lexes a citation, which requires a reference and ending punctuation (except
+/// at the end of a parenthetical), and might include modifiers in braces right
+/// after the reference, a pincite, or a parenthetical.
+fn cite_lexer(input: &str) -> Result<Vec<Token<'_>>, SourceError<'_>> {
     trace!(
         slog_scope::logger(),
         "Starting citation lexer for {}...",
@@ -556,6 +1351,20 @@ fn cite_lexer(input: &str) -> Result<Vec<Token>, String> {
             );
             lex.push(Token::new(TokenType::Reference, &input[lexer.start..i + 1]));
 
+            lexer.context = Context::Pincite;
+            lexer.start = i + 1;
+        } else if lexer.context == Context::Pincite && c == b'{' && lexer.start == i {
+            // Modifiers right after the reference, *e.g.*, `{.long}`.
+            lexer.context = Context::Modifiers;
+        } else if lexer.context == Context::Modifiers && c == b'}' {
+            trace!(
+                slog_scope::logger(),
+                "Pushing token type {:?} containing {:?}",
+                TokenType::Modifiers,
+                &input[lexer.start..i + 1]
+            );
+            lex.push(Token::new(TokenType::Modifiers, &input[lexer.start..i + 1]));
+
             lexer.context = Context::Pincite;
             lexer.start = i + 1;
         } else if lexer.context == Context::Pincite && c == b'(' && lexer.last_char == Some(b' ') {
@@ -596,19 +1405,34 @@ fn cite_lexer(input: &str) -> Result<Vec<Token>, String> {
         // End of the input...
         if i == input.bytes().len() - 1 {
             if lexer.context == Context::Pincite && i > lexer.start {
-                if !&input[lexer.start..i].trim().is_empty() {
+                // A citation at the end of a parenthetical has no punctuation.
+                let end = if c == b'.' || c == b',' || c == b';' {
+                    i
+                } else {
+                    i + 1
+                };
+
+                if !&input[lexer.start..end].trim().is_empty() {
                     trace!(
                         slog_scope::logger(),
                         "Pushing token type {:?} containing {:?}",
                         TokenType::Pincite,
-                        &input[lexer.start..i]
+                        &input[lexer.start..end]
                     );
-                    lex.push(Token::new(TokenType::Pincite, &input[lexer.start..i]));
+                    lex.push(Token::new(TokenType::Pincite, &input[lexer.start..end]));
                 };
 
-                lexer.start = i;
+                lexer.start = end;
             } else if lexer.context == Context::Parenthetical {
-                return Err("No closing parentheses found for the parenthetical".to_string());
+                return Err(SourceError::new(
+                    &input[lexer.start..],
+                    "No closing parenthesis found for the parenthetical",
+                ));
+            } else if lexer.context == Context::Modifiers {
+                return Err(SourceError::new(
+                    &input[lexer.start..],
+                    "No closing brace found for the citation's modifiers",
+                ));
             }
 
             trace!(
@@ -856,6 +1680,151 @@ mod tests {
             assert_eq!(content[8].contents, "\nThis is the final line of text.\n");
             assert_eq!(content[8].token_type, TokenType::Text);
         }
+
+        #[test]
+        fn reference_footnotes() {
+            let content = text_lexer("Text.[^a] More[^none].\n\n[^a]: *See* [@jones2021].\n\n    Second paragraph.\n\nAfter.\n").unwrap();
+            assert_eq!(content.len(), 9);
+            assert_eq!(content[0].contents, "Text.");
+            assert_eq!(content[1].contents, "[^a]");
+            assert_eq!(content[1].token_type, TokenType::OpenFootnote);
+            assert_eq!(content[2].contents, "*See* ");
+            assert_eq!(content[2].token_type, TokenType::Signal);
+            assert_eq!(content[3].contents, "[@jones2021]");
+            assert_eq!(content[3].token_type, TokenType::Reference);
+            assert_eq!(content[4].contents, ".");
+            assert_eq!(content[4].token_type, TokenType::CitePunctuation);
+            assert_eq!(content[5].contents, "\n\n    Second paragraph.");
+            assert_eq!(content[5].token_type, TokenType::Text);
+            assert_eq!(content[6].token_type, TokenType::CloseFootnote);
+            assert_eq!(content[7].contents, " More[^none].\n\n");
+            assert_eq!(content[7].token_type, TokenType::Text);
+            assert_eq!(content[8].contents, "\nAfter.\n");
+        }
+
+        #[test]
+        fn narratives() {
+            let content =
+                text_lexer("As @smith2020 argues, [-@jones2021] held (see me@example.com).")
+                    .unwrap();
+            let contents: Vec<(&TokenType, &str)> = content
+                .iter()
+                .map(|t| (&t.token_type, t.contents))
+                .collect();
+            assert_eq!(
+                contents,
+                vec![
+                    (&TokenType::Text, "As "),
+                    (&TokenType::Narrative, "@smith2020"),
+                    (&TokenType::Text, " argues, "),
+                    (&TokenType::Narrative, "[-@jones2021]"),
+                    (&TokenType::Text, " held (see me@example.com)."),
+                ]
+            );
+        }
+
+        #[test]
+        fn bracketed_in_text() {
+            // These only work in footnotes, so they stay in the text.
+            let input = "Text [@smith2020] and [see @jones2021, 5; @doe]. End.";
+            let content = text_lexer(input).unwrap();
+            assert_eq!(content.len(), 1);
+            assert_eq!(content[0].token_type, TokenType::Text);
+            assert_eq!(content[0].contents, input);
+        }
+
+        #[test]
+        fn narrative_keys() {
+            assert_eq!(narrative_length("@doe.2021. Next", 0), Some(9));
+            assert_eq!(narrative_length("@doe:2021:a", 0), Some(11));
+            assert_eq!(narrative_length("(@doe)", 1), Some(4));
+            assert_eq!(narrative_length("@ doe", 0), None);
+            assert_eq!(narrative_length("a@doe", 1), None);
+            assert_eq!(narrative_length("[-@doe smith]", 0), None);
+        }
+
+        #[test]
+        fn cross_refs() {
+            let content =
+                text_lexer("*See infra* note [?remedies].^[[?remedies] Text [?x].] [?] [? no]")
+                    .unwrap();
+            let contents: Vec<(&TokenType, &str)> = content
+                .iter()
+                .map(|t| (&t.token_type, t.contents))
+                .collect();
+            assert_eq!(
+                contents,
+                vec![
+                    (&TokenType::Text, "*See infra* note "),
+                    (&TokenType::CrossRef, "[?remedies]"),
+                    (&TokenType::Text, "."),
+                    (&TokenType::OpenFootnote, "^["),
+                    (&TokenType::ID, "[?remedies]"),
+                    (&TokenType::Text, " Text "),
+                    (&TokenType::CrossRef, "[?x]"),
+                    (&TokenType::Text, "."),
+                    (&TokenType::CloseFootnote, "]"),
+                    (&TokenType::Text, " [?] [? no]"),
+                ]
+            );
+        }
+    }
+
+    mod verbatim {
+        use super::*;
+
+        #[test]
+        fn blocks() {
+            let input = "---\ntitle: \"A^[b]\"\n---\n\nText.\n\n```rust\nlet x = \"^[\";\n```\n\n---\n\nEnd.\n\n---\nA rule.^[Note.]\n\n---\n\n---\nauthor: Me\n...\n~~~~\nunclosed";
+            let ranges: Vec<&str> = verbatim(input, true)
+                .into_iter()
+                .map(|r| &input[r])
+                .collect();
+            assert_eq!(
+                ranges,
+                vec![
+                    "---\ntitle: \"A^[b]\"\n---\n",
+                    "```rust\nlet x = \"^[\";\n```\n",
+                    "---\nauthor: Me\n...\n",
+                    "~~~~\nunclosed"
+                ]
+            );
+        }
+
+        #[test]
+        fn inline() {
+            let input =
+                r"A `^[x]` and ``a ` b`` \^[ <!-- ^[c] --> $x^[2]$ $5 and $6 [$] `unmatched";
+            let ranges: Vec<&str> = verbatim(input, false)
+                .into_iter()
+                .map(|r| &input[r])
+                .collect();
+            assert_eq!(
+                ranges,
+                vec!["`^[x]`", "``a ` b``", r"\^", "<!-- ^[c] -->", "$x^[2]$"]
+            );
+        }
+
+        #[test]
+        fn text_lexer_passes_through() {
+            let input = "---\nnote: ^[not a note]\n---\nText `^[code]` \\^[escaped] <!-- ^[comment] -->.^[Real [@a] `[@b]`.]\n```\n^[fenced]\n```\n";
+            let content = text_lexer(input).unwrap();
+            assert_eq!(content.len(), 8);
+            assert_eq!(
+                content[0].contents,
+                "---\nnote: ^[not a note]\n---\nText `^[code]` \\^[escaped] <!-- ^[comment] -->."
+            );
+            assert_eq!(content[1].token_type, TokenType::OpenFootnote);
+            assert_eq!(content[2].contents, "Real ");
+            assert_eq!(content[3].contents, "[@a]");
+            assert_eq!(content[3].token_type, TokenType::Reference);
+            assert_eq!(content[4].contents, "");
+            assert_eq!(content[4].token_type, TokenType::CitePunctuation);
+            assert_eq!(content[5].contents, " `[@b]`.");
+            assert_eq!(content[5].token_type, TokenType::Text);
+            assert_eq!(content[6].token_type, TokenType::CloseFootnote);
+            assert_eq!(content[7].contents, "\n```\n^[fenced]\n```\n");
+        }
     }
 
     mod footnote_lexer {
@@ -1027,6 +1996,106 @@ mod tests {
         }
     }
 
+    mod group_lexer {
+        use super::*;
+
+        #[test]
+        fn pandoc_group() {
+            let footnote = footnote_lexer(
+                r"^[*See* [@jones2021, p. 12 (explaining); *see also* @smith2020 at 3; -@doe2019].]",
+            )
+            .unwrap();
+            let contents: Vec<(&TokenType, &str)> = footnote
+                .iter()
+                .map(|t| (&t.token_type, t.contents))
+                .collect();
+            assert_eq!(
+                contents,
+                vec![
+                    (&TokenType::Signal, "*See* "),
+                    (&TokenType::Reference, "@jones2021"),
+                    (&TokenType::Pincite, "12"),
+                    (&TokenType::Parenthetical, "(explaining)"),
+                    (&TokenType::CitePunctuation, ";"),
+                    (&TokenType::Text, " "),
+                    (&TokenType::Signal, "*see also* "),
+                    (&TokenType::Reference, "@smith2020"),
+                    (&TokenType::Pincite, "at 3"),
+                    (&TokenType::CitePunctuation, ";"),
+                    (&TokenType::Text, " "),
+                    (&TokenType::Reference, "@doe2019"),
+                    (&TokenType::CitePunctuation, "."),
+                ]
+            );
+        }
+
+        #[test]
+        fn not_groups() {
+            assert!(group_lexer("[@jones2021] at 5.").is_none());
+            assert!(group_lexer("[hereinafter Jones]").is_none());
+            assert!(group_lexer("[email me@example.com]").is_none());
+            assert!(group_lexer("[@jones2021; no key]").is_none());
+        }
+
+        #[test]
+        fn mixed_syntax() {
+            let footnote = footnote_lexer(r"^[[@jones2021] at 5; *cf.* [@smith2020, 7].]").unwrap();
+            assert_eq!(footnote.len(), 8);
+            assert_eq!(footnote[0].contents, "[@jones2021]");
+            assert_eq!(footnote[2].contents, ";");
+            assert_eq!(footnote[4].contents, "*cf.* ");
+            assert_eq!(footnote[4].token_type, TokenType::Signal);
+            assert_eq!(footnote[5].contents, "@smith2020");
+            assert_eq!(footnote[6].contents, "7");
+            assert_eq!(footnote[6].token_type, TokenType::Pincite);
+            assert_eq!(footnote[7].contents, ".");
+        }
+    }
+
+    mod parenthetical_lexer {
+        use super::*;
+
+        #[test]
+        fn nested_citations() {
+            let parenthetical = parenthetical_lexer(
+                "(quoting [@jones2021] at 5 (explaining); *see also* [@smith2020, 7])",
+            )
+            .unwrap();
+            let contents: Vec<(&TokenType, &str)> = parenthetical
+                .iter()
+                .map(|t| (&t.token_type, t.contents))
+                .collect();
+            assert_eq!(
+                contents,
+                vec![
+                    (&TokenType::Text, "quoting "),
+                    (&TokenType::Reference, "[@jones2021]"),
+                    (&TokenType::Pincite, " at 5"),
+                    (&TokenType::Parenthetical, "(explaining)"),
+                    (&TokenType::CitePunctuation, ";"),
+                    (&TokenType::Text, " "),
+                    (&TokenType::Signal, "*see also* "),
+                    (&TokenType::Reference, "@smith2020"),
+                    (&TokenType::Pincite, "7"),
+                    (&TokenType::CitePunctuation, ""),
+                ]
+            );
+        }
+
+        #[test]
+        fn ends_without_punctuation() {
+            let parenthetical = parenthetical_lexer("(citing [@jones2021] at 5)").unwrap();
+            assert_eq!(parenthetical.len(), 4);
+            assert_eq!(parenthetical[2].contents, " at 5");
+            assert_eq!(parenthetical[2].token_type, TokenType::Pincite);
+            assert_eq!(parenthetical[3].contents, "");
+
+            let parenthetical = parenthetical_lexer("(explaining the rule)").unwrap();
+            assert_eq!(parenthetical.len(), 1);
+            assert_eq!(parenthetical[0].token_type, TokenType::Text);
+        }
+    }
+
     mod pre_cite_lexer {
         use super::*;
 
@@ -1053,6 +2122,22 @@ mod tests {
         mod signal_tests {
             use super::*;
 
+            #[test]
+            fn test_unformatted() {
+                // See
+                let s = pre_cite_lexer(r"This sentence the See signal. See ").unwrap();
+                assert_eq!(s[1].contents, "See ");
+                assert_eq!(s[1].token_type, TokenType::Signal);
+                // see e.g.
+                let s = pre_cite_lexer(r"This sentence has no commas; see e.g. ").unwrap();
+                assert_eq!(s[1].contents, "see e.g. ");
+                assert_eq!(s[1].token_type, TokenType::Signal);
+                // foresee
+                let s = pre_cite_lexer(r"This sentence doesn't foresee ").unwrap();
+                assert_eq!(s.len(), 1);
+                assert_eq!(s[0].token_type, TokenType::Text);
+            }
+
             #[test]
             fn test_eg() {
                 // *E.g.*
@@ -1556,6 +2641,22 @@ mod tests {
             assert_eq!(citation7[1].contents, r" 100--01");
             assert_eq!(citation7[1].token_type, TokenType::Pincite);
         }
+
+        #[test]
+        fn modifiers() {
+            let citation = cite_lexer(r#"[@jones2021]{.long hereinafter="Jones"} at 5."#).unwrap();
+            assert_eq!(citation.len(), 4);
+            assert_eq!(citation[0].contents, r"[@jones2021]");
+            assert_eq!(citation[0].token_type, TokenType::Reference);
+            assert_eq!(citation[1].contents, r#"{.long hereinafter="Jones"}"#);
+            assert_eq!(citation[1].token_type, TokenType::Modifiers);
+            assert_eq!(citation[2].contents, r" at 5");
+            assert_eq!(citation[2].token_type, TokenType::Pincite);
+            assert_eq!(citation[3].contents, r".");
+            assert_eq!(citation[3].token_type, TokenType::CitePunctuation);
+
+            assert!(cite_lexer(r"[@jones2021]{.long at 5.").is_err());
+        }
     }
 
     mod test_errors {
@@ -1569,6 +2670,17 @@ mod tests {
             assert!(content.is_err());
         }
 
+        #[test]
+        fn every_footnote() {
+            let input = "One.^[[@test2001] at 45] Two.^[[@test2002] (unclosed.] Three.^[[?id]";
+            let errors = lexer(input).unwrap_err();
+            assert_eq!(errors.len(), 3);
+            assert_eq!(errors[0].span(input).map(|s| s.start), Some(6));
+            assert_eq!(errors[0].source, "[@test2001] at 45");
+            assert_eq!(errors[1].source, "[@test2002] (unclosed.");
+            assert_eq!(errors[2].source, "^[[?id]");
+        }
+
         #[test]
         fn bad_citation_puncutation() {
             let content =
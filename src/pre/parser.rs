@@ -1,17 +1,30 @@
 //! This module contains the Supra parser. It converts collection of tokens into
 //! the Supra syntax tree.
 
-use crate::pre::lexer::{Token, TokenType};
-use slog::{debug, o, trace};
+use crate::{
+    config::Style,
+    pre::{
+        error::{self, SourceError},
+        lexer::{self, Token, TokenType},
+        pincite::{self, Pincite},
+        signal::{self, SignalKind},
+    },
+};
+use ansi_term::Color;
+use lazy_static::lazy_static;
+use regex::Regex;
+use slog::{debug, o, trace, warn};
+use std::{collections::HashSet, ops::Range};
 
 /// The types of branches on the syntax tree.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum Branch<'a> {
     Text(Text<'a>),
     Footnote(Footnote<'a>),
     Citation(Citation<'a>),
     CrossRef(CrossRef<'a>),
     CiteBreak,
+    Narrative(Narrative<'a>),
 }
 
 impl Branch<'_> {
@@ -30,10 +43,43 @@ impl Branch<'_> {
             }
             Branch::Citation(citation) => format!(
                 "{} {:?} {:?} {}",
-                citation.reference, citation.pincite, citation.parenthetical, citation.punctuation
+                citation.reference,
+                citation.pincite.as_ref().map(|p| p.render(Style::Bluebook)),
+                citation.parenthetical,
+                citation.punctuation
             ),
             Branch::CrossRef(crossref) => crossref.contents.to_string(),
             Branch::CiteBreak => "".to_string(),
+            Branch::Narrative(narrative) => narrative.reference.to_string(),
+        }
+    }
+
+    /// The byte span of the branch in the input, if it has one.
+    ///
+    /// A citation runs from its signal or pre-cite punctuation (or its
+    /// reference) through its ending punctuation. A footnote's span is its first
+    /// branch's, since a reference footnote's contents aren't next to its
+    /// reference. A cite breaker has no span.
+    pub fn span(&self, input: &str) -> Option<Range<usize>> {
+        // Every branch's contents borrow from the input.
+        let whole = |s: &str| error::span_of(s, input);
+        let start = |s: &str| whole(s).map(|s| s.start);
+
+        match self {
+            Branch::Text(text) => whole(text.contents),
+            Branch::Footnote(footnote) => footnote.contents.iter().find_map(|b| b.span(input)),
+            Branch::Citation(citation) => {
+                let first = match &citation.pre_cite {
+                    Some(PreCite::Signal(s)) => s.contents,
+                    Some(PreCite::Punctuation(p)) => p.contents,
+                    None => citation.reference,
+                };
+                let end = whole(citation.punctuation)?.end;
+                start(first).map(|n| n..end)
+            }
+            Branch::CrossRef(crossref) => whole(crossref.contents),
+            Branch::CiteBreak => None,
+            Branch::Narrative(narrative) => whole(narrative.reference),
         }
     }
 }
@@ -51,51 +97,220 @@ impl Text<'_> {
 }
 
 /// Contents of a footnote branch.
-#[derive(Debug, PartialEq)]
+///
+/// A reference footnote (`[^label]` in the text, with its definition elsewhere)
+/// has a `label`. An inline footnote (`^[...]`) doesn't.
+#[derive(Debug, PartialEq, Eq)]
 pub struct Footnote<'a> {
     pub number: i32,
     pub id: Option<&'a str>,
+    pub label: Option<&'a str>,
     pub contents: Vec<Branch<'a>>,
 }
 
-impl Footnote<'_> {
-    fn new<'b>(number: i32, id: Option<&'b str>, contents: Vec<Branch<'b>>) -> Footnote<'b> {
+impl<'a> Footnote<'a> {
+    fn new<'b>(
+        number: i32,
+        id: Option<&'b str>,
+        label: Option<&'b str>,
+        contents: Vec<Branch<'b>>,
+    ) -> Footnote<'b> {
         Footnote {
             number,
             id,
+            label,
             contents,
         }
     }
+
+    /// Wrap the rendered contents in the footnote's markup.
+    ///
+    /// Returns the text that goes where the footnote was and, for a reference
+    /// footnote, the definition that goes with the others at the end.
+    pub fn wrap(&self, contents: &str, labels: &mut Labels<'a>) -> (String, Option<String>) {
+        match self.label {
+            Some(label) => {
+                let label = labels.label(label);
+                (
+                    format!("[^{}]", label),
+                    Some(format!("[^{}]: {}", label, contents)),
+                )
+            }
+            None => (format!("^[{}]", contents), None),
+        }
+    }
+}
+
+/// The labels of the reference footnotes in the output.
+///
+/// A note that's referred to more than once is rendered each time, but Pandoc
+/// keeps only one definition for each label. So every reference after the
+/// first gets a label of its own, *e.g.*, `[^b-2]`, that isn't used anywhere
+/// else in the document.
+pub struct Labels<'a> {
+    taken: HashSet<String>,
+    given: HashSet<&'a str>,
+}
+
+impl<'a> Labels<'a> {
+    pub fn new(tree: &[Branch<'a>]) -> Labels<'a> {
+        let taken = tree
+            .iter()
+            .filter_map(|branch| match branch {
+                Branch::Footnote(footnote) => footnote.label.map(str::to_string),
+                _ => None,
+            })
+            .collect();
+
+        Labels {
+            taken,
+            given: HashSet::new(),
+        }
+    }
+
+    /// The label for the next reference to the note `label`.
+    fn label(&mut self, label: &'a str) -> String {
+        if self.given.insert(label) {
+            return label.to_string();
+        }
+
+        (2..)
+            .map(|n| format!("{}-{}", label, n))
+            .find(|l| self.taken.insert(l.clone()))
+            .unwrap()
+    }
+}
+
+/// Add the definitions of reference footnotes to the end of the output.
+pub fn append_definitions(output: &mut String, definitions: &[String]) {
+    if definitions.is_empty() {
+        return;
+    }
+
+    output.truncate(output.trim_end().len());
+    output.push_str("\n\n");
+    output.push_str(&definitions.join("\n\n"));
+    output.push('\n');
 }
 
 /// Contents of a citation branch.
+///
+/// A parenthetical that cites other sources, *e.g.*, `(quoting [@jones2001] at
+/// 5)`, is also parsed into `nested`, the branches between its parentheses.
+/// `nested` is empty for any other parenthetical.
 #[derive(Debug, PartialEq, Eq)]
 pub struct Citation<'a> {
     pub pre_cite: Option<PreCite<'a>>,
     pub reference: &'a str,
-    pub pincite: Option<&'a str>,
+    pub modifiers: Modifiers<'a>,
+    pub pincite: Option<Pincite>,
     pub parenthetical: Option<&'a str>,
+    pub nested: Vec<Branch<'a>>,
     pub punctuation: &'a str,
 }
 
-impl Citation<'_> {
+impl<'a> Citation<'a> {
+    /// The id of the cited source, *e.g.*, `smith2021` for `[@smith2021]`.
+    pub fn key(&self) -> &'a str {
+        reference_key(self.reference)
+    }
+
+    /// The ids of the cited source and of the sources cited in its
+    /// parenthetical, in order.
+    pub fn keys(&self) -> Vec<&'a str> {
+        let mut keys = vec![self.key()];
+        for branch in &self.nested {
+            if let Branch::Citation(citation) = branch {
+                keys.extend(citation.keys());
+            }
+        }
+        keys
+    }
+
     fn new<'b>(
         pre_cite: Option<PreCite<'b>>,
         reference: &'b str,
-        pincite: Option<&'b str>,
+        pincite: Option<Pincite>,
         parenthetical: Option<&'b str>,
         punctuation: &'b str,
     ) -> Citation<'b> {
         Citation {
             pre_cite,
             reference,
+            modifiers: Modifiers::default(),
             pincite,
             parenthetical,
+            nested: Vec::new(),
             punctuation,
         }
     }
 }
 
+/// Modifiers that override how a citation is rendered.
+///
+/// They're given in braces right after the reference, *e.g.*,
+/// `[@smith2004]{.long}` or `[@smith2004]{.noid hereinafter="Smith I"}`.
+///
+/// * `form`: Forces a long (`.long`) or short (`.short`) cite.
+/// * `no_id`: Never renders the citation as an *Id.* (`.noid`).
+/// * `hereinafter`: The source's hereinafter (`hereinafter="..."`), which
+///   works like a `supra-hereinafter` in the source's note.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Modifiers<'a> {
+    pub form: Option<Form>,
+    pub no_id: bool,
+    pub hereinafter: Option<&'a str>,
+}
+
+/// The forms a citation can be forced into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Form {
+    Long,
+    Short,
+}
+
+/// Remove the brackets and at symbol from a reference.
+///
+/// References in Supra's syntax have both (`[@smith2021]`), but references in a
+/// group of Pandoc citations have only the at symbol (`@smith2021`). A body-text
+/// reference that suppresses the author also has a `-` (`[-@smith2021]`).
+pub fn reference_key(reference: &str) -> &str {
+    let key = reference.strip_prefix('[').unwrap_or(reference);
+    let key = key.strip_suffix(']').unwrap_or(key);
+    let key = key.strip_prefix('-').unwrap_or(key);
+    key.strip_prefix('@').unwrap_or(key)
+}
+
+/// Contents of a body-text (narrative) citation branch.
+///
+/// `footnote` is the number of the last footnote before the citation, which
+/// places it among the footnotes for the short-form rules.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Narrative<'a> {
+    pub reference: &'a str,
+    pub footnote: i32,
+}
+
+impl<'a> Narrative<'a> {
+    fn new(reference: &'a str, footnote: i32) -> Narrative<'a> {
+        Narrative {
+            reference,
+            footnote,
+        }
+    }
+
+    /// The id of the cited source.
+    pub fn key(&self) -> &'a str {
+        reference_key(self.reference)
+    }
+
+    /// Whether the citation names the source rather than its author
+    /// (`[-@smith2021]`).
+    pub fn suppress_author(&self) -> bool {
+        self.reference.starts_with("[-@")
+    }
+}
+
 /// The pre-cite data.
 #[derive(Debug, PartialEq, Eq)]
 pub enum PreCite<'a> {
@@ -116,17 +331,65 @@ impl Punctuation<'_> {
 }
 
 /// The pre-cite signal.
+///
+/// * `contents`: The signal as it's written, with the whitespace after it.
+/// * `kind`: The kind of signal, if Supra recognizes it.
+/// * `eg`: Whether the signal is combined with *e.g.*
+/// * `sentence_start`: Whether the signal starts a citation sentence.
 #[derive(Debug, PartialEq, Eq)]
 pub struct Signal<'a> {
     pub contents: &'a str,
+    pub kind: Option<SignalKind>,
+    pub eg: bool,
+    pub sentence_start: bool,
 }
 
 impl Signal<'_> {
     fn new(contents: &'_ str) -> Signal<'_> {
-        Signal { contents }
+        let (kind, eg) = match signal::parse(contents) {
+            Some((kind, eg)) => (Some(kind), eg),
+            None => (None, false),
+        };
+
+        Signal {
+            contents,
+            kind,
+            eg,
+            sentence_start: false,
+        }
+    }
+
+    /// The signal italicized and capitalized for the Bluebook styles, with the
+    /// whitespace after it.
+    pub fn normalized(&self) -> String {
+        match self.kind {
+            Some(kind) => format!(
+                "{}{}",
+                signal::render(kind, self.eg, self.sentence_start),
+                &self.contents[self.contents.trim_end().len()..]
+            ),
+            None => self.contents.to_string(),
+        }
     }
 }
 
+/// Whether a citation starting after `tokens` starts a citation sentence.
+///
+/// It does if it's the first thing in the footnote or comes after a sentence's
+/// ending punctuation. A citation in a parenthetical never does.
+fn starts_sentence(tokens: &[Token]) -> bool {
+    for token in tokens.iter().rev() {
+        match token.token_type {
+            TokenType::OpenFootnote => return true,
+            TokenType::ID => {}
+            _ if token.contents.trim().is_empty() => {}
+            _ => return token.contents.trim_end().ends_with(['.', '!', '?']),
+        }
+    }
+
+    false
+}
+
 /// Contents of a crossref branch.
 #[derive(Debug, PartialEq, Eq)]
 pub struct CrossRef<'a> {
@@ -143,8 +406,46 @@ impl CrossRef<'_> {
 #[derive(Debug, PartialEq, Eq)]
 pub struct CiteBraker;
 
+/// Collect the ids of every source cited in the tree.
+///
+/// The ids don't include the brackets and at symbol, *e.g.*, `smith2021`.
+pub fn cited_keys<'a>(tree: &[Branch<'a>]) -> HashSet<&'a str> {
+    let mut keys = HashSet::new();
+
+    for branch in tree {
+        match branch {
+            Branch::Footnote(footnote) => keys.extend(cited_keys(&footnote.contents)),
+            Branch::Citation(citation) => keys.extend(citation.keys()),
+            Branch::Narrative(narrative) => {
+                keys.insert(narrative.key());
+            }
+            _ => {}
+        }
+    }
+
+    keys
+}
+
+/// Turn body-text keys that don't name a source back into text.
+///
+/// A bare key (`@smith2021`) could be an email address or a handle, so it's
+/// only a citation if the library has the source. A key with the author
+/// suppressed (`[-@smith2021]`) is always a citation.
+pub fn unknown_narratives_to_text(tree: &mut [Branch], known: impl Fn(&str) -> bool) {
+    for branch in tree.iter_mut() {
+        if let Branch::Narrative(narrative) = branch {
+            if !narrative.suppress_author() && !known(narrative.key()) {
+                *branch = Branch::Text(Text::new(narrative.reference));
+            }
+        }
+    }
+}
+
 /// The main parser.
-pub fn parser<'a>(tokens: &[Token<'a>], offset: i32) -> Result<Vec<Branch<'a>>, String> {
+pub fn parser<'a>(
+    tokens: &[Token<'a>],
+    offset: i32,
+) -> Result<Vec<Branch<'a>>, Vec<SourceError<'a>>> {
     debug!(slog_scope::logger(), "Starting parser...");
 
     match slog_scope::scope(
@@ -160,9 +461,13 @@ pub fn parser<'a>(tokens: &[Token<'a>], offset: i32) -> Result<Vec<Branch<'a>>,
 }
 
 /// Parse text and footnotes (sending footnotes to `footnote_parser`)
-fn text_parser<'a>(tokens: &[Token<'a>], offset: i32) -> Result<Vec<Branch<'a>>, String> {
+fn text_parser<'a>(
+    tokens: &[Token<'a>],
+    offset: i32,
+) -> Result<Vec<Branch<'a>>, Vec<SourceError<'a>>> {
     trace!(slog_scope::logger(), "Starting text parser...");
     let mut parse: Vec<Branch> = Vec::new();
+    let mut errors: Vec<SourceError> = Vec::new();
     let mut footnote_number = offset;
     let mut footnote_index: usize = 0;
     let mut open_footnote: bool = false;
@@ -179,6 +484,25 @@ fn text_parser<'a>(tokens: &[Token<'a>], offset: i32) -> Result<Vec<Branch<'a>>,
                     parse.push(Branch::Text(Text::new(token.contents)))
                 };
             }
+            TokenType::Narrative => {
+                trace!(
+                    slog_scope::logger(),
+                    "Pushing branch type Narrative containing {:?}",
+                    token.contents
+                );
+                parse.push(Branch::Narrative(Narrative::new(
+                    token.contents,
+                    footnote_number,
+                )))
+            }
+            TokenType::CrossRef if !open_footnote => {
+                trace!(
+                    slog_scope::logger(),
+                    "Pushing branch type CrossRef containing {:?}",
+                    token.contents
+                );
+                parse.push(Branch::CrossRef(CrossRef::new(token.contents)))
+            }
             TokenType::OpenFootnote => {
                 footnote_number += 1;
                 footnote_index = i;
@@ -199,7 +523,7 @@ fn text_parser<'a>(tokens: &[Token<'a>], offset: i32) -> Result<Vec<Branch<'a>>,
                         );
                         parse.push(Branch::Footnote(f));
                     }
-                    Err(e) => return Err(e),
+                    Err(e) => errors.extend(e),
                 }
             }
             _ => {}
@@ -207,19 +531,41 @@ fn text_parser<'a>(tokens: &[Token<'a>], offset: i32) -> Result<Vec<Branch<'a>>,
     }
 
     trace!(slog_scope::logger(), "Text parsing completed.");
-    Ok(parse)
+    if errors.is_empty() {
+        Ok(parse)
+    } else {
+        Err(errors)
+    }
 }
 
 /// Parse the contents of a footnote into branches.
-fn footnote_parser<'a>(tokens: &[Token<'a>], footnote_number: i32) -> Result<Footnote<'a>, String> {
+fn footnote_parser<'a>(
+    tokens: &[Token<'a>],
+    footnote_number: i32,
+) -> Result<Footnote<'a>, Vec<SourceError<'a>>> {
     trace!(slog_scope::logger(), "Starting footnote parser...");
     let mut contents: Vec<Branch> = Vec::new();
     let mut id = None;
+    let mut label = None;
     let mut citation_index: usize = 0;
     let mut citation_started = false;
+    let mut last_signal = None;
+    let mut errors: Vec<SourceError> = Vec::new();
 
     for (i, token) in tokens.iter().enumerate() {
         match token.token_type {
+            TokenType::OpenFootnote => {
+                // A reference footnote opens with its reference, *e.g.*,
+                // `[^1]`.
+                if let Some(l) = token
+                    .contents
+                    .strip_prefix("[^")
+                    .and_then(|c| c.strip_suffix(']'))
+                {
+                    trace!(slog_scope::logger(), "Adding label: {:?}", l);
+                    label = Some(l);
+                }
+            }
             TokenType::ID => {
                 trace!(slog_scope::logger(), "Adding id: {:?}", token.contents);
                 id = Some(token.contents);
@@ -246,7 +592,22 @@ fn footnote_parser<'a>(tokens: &[Token<'a>], footnote_number: i32) -> Result<Foo
                 &slog_scope::logger().new(o!("fn" => "cite_parser()")),
                 || cite_parser(&tokens[citation_index..i + 1]),
             ) {
-                Ok(c) => {
+                Ok(mut c) => {
+                    if let Some(PreCite::Signal(signal)) = &mut c.pre_cite {
+                        signal.sentence_start = starts_sentence(&tokens[..citation_index]);
+
+                        // *With* is only a signal after *compare*. Otherwise
+                        // it's just a word.
+                        if signal.kind == Some(SignalKind::With)
+                            && last_signal != Some(SignalKind::Compare)
+                        {
+                            signal.kind = None;
+                        }
+                        if signal.kind.is_some() {
+                            last_signal = signal.kind;
+                        }
+                    }
+
                     trace!(
                         slog_scope::logger(),
                         "Pushing branch type Citation containing {:?}",
@@ -255,7 +616,10 @@ fn footnote_parser<'a>(tokens: &[Token<'a>], footnote_number: i32) -> Result<Foo
                     contents.push(Branch::Citation(c));
                     citation_started = false;
                 }
-                Err(e) => return Err(e),
+                Err(e) => {
+                    errors.extend(e);
+                    citation_started = false;
+                }
             },
             TokenType::Text => {
                 trace!(
@@ -286,11 +650,15 @@ fn footnote_parser<'a>(tokens: &[Token<'a>], footnote_number: i32) -> Result<Foo
     }
 
     trace!(slog_scope::logger(), "Footnote parsing completed.");
-    Ok(Footnote::new(footnote_number, id, contents))
+    if errors.is_empty() {
+        Ok(Footnote::new(footnote_number, id, label, contents))
+    } else {
+        Err(errors)
+    }
 }
 
 /// Parse the parts of a citation.
-fn cite_parser<'a>(tokens: &[Token<'a>]) -> Result<Citation<'a>, String> {
+fn cite_parser<'a>(tokens: &[Token<'a>]) -> Result<Citation<'a>, Vec<SourceError<'a>>> {
     trace!(slog_scope::logger(), "Starting citation parser...");
     let mut citation = Citation::new(None, "", None, None, "");
 
@@ -303,8 +671,15 @@ fn cite_parser<'a>(tokens: &[Token<'a>]) -> Result<Citation<'a>, String> {
                 citation.pre_cite = Some(PreCite::Signal(Signal::new(token.contents)))
             }
             TokenType::Reference => citation.reference = token.contents,
-            TokenType::Pincite => citation.pincite = pin_parser(token.contents),
-            TokenType::Parenthetical => citation.parenthetical = Some(token.contents),
+            TokenType::Modifiers => citation.modifiers = modifier_parser(token.contents),
+            TokenType::Pincite => citation.pincite = pincite::parse(token.contents),
+            TokenType::Parenthetical => {
+                citation.parenthetical = Some(token.contents);
+                citation.nested = slog_scope::scope(
+                    &slog_scope::logger().new(o!("fn" => "parenthetical_parser()")),
+                    || parenthetical_parser(token.contents),
+                )?;
+            }
             TokenType::CitePunctuation => citation.punctuation = token.contents,
             _ => {}
         }
@@ -314,19 +689,101 @@ fn cite_parser<'a>(tokens: &[Token<'a>]) -> Result<Citation<'a>, String> {
     Ok(citation)
 }
 
-/// Determine any pincites.
+/// Parse a citation's modifiers.
 ///
-/// This function determines whether there's a pincite. If there is, it also
-/// removes the "at."
-fn pin_parser(input: &str) -> Option<&str> {
-    if input.trim().is_empty() {
-        None
-    } else if !input.contains("at") {
-        Some(input.trim())
-    } else {
-        let at_start = input.find("at").unwrap();
-        Some(input[at_start + 2..].trim())
+/// Unknown modifiers are reported and ignored.
+fn modifier_parser(input: &str) -> Modifiers<'_> {
+    lazy_static! {
+        /// Regex for a class (`.long`) or a key-value pair (`key="value"`).
+        static ref MODIFIER: Regex =
+            Regex::new(r#"\.([\w-]+)|([\w-]+)\s*=\s*"([^"]*)"|(\S+)"#).unwrap();
     }
+
+    let mut modifiers = Modifiers::default();
+
+    for captures in MODIFIER.captures_iter(&input[1..input.len() - 1]) {
+        let modifier = captures.get(0).unwrap().as_str();
+        match (
+            captures.get(1).map(|c| c.as_str()),
+            captures.get(2).map(|c| c.as_str()),
+            captures.get(3),
+        ) {
+            (Some("long"), _, _) => modifiers.form = Some(Form::Long),
+            (Some("short"), _, _) => modifiers.form = Some(Form::Short),
+            (Some("noid"), _, _) => modifiers.no_id = true,
+            (_, Some("hereinafter"), Some(value)) => modifiers.hereinafter = Some(value.as_str()),
+            _ => {
+                warn!(
+                    slog_scope::logger(),
+                    "Unknown citation modifier {} in {}", modifier, input
+                );
+                eprintln!(
+                    "  {} Unknown citation modifier {} in {}",
+                    Color::Yellow.paint("WARN"),
+                    modifier,
+                    input
+                );
+            }
+        }
+    }
+
+    modifiers
+}
+
+/// Collect the hereinafters given in citations' modifiers.
+///
+/// Returns each source's id and its hereinafter, in the order they appear.
+pub fn hereinafters<'a>(tree: &[Branch<'a>]) -> Vec<(&'a str, &'a str)> {
+    let mut hereinafters = Vec::new();
+
+    for branch in tree {
+        match branch {
+            Branch::Footnote(footnote) => {
+                hereinafters.extend(self::hereinafters(&footnote.contents))
+            }
+            Branch::Citation(citation) => {
+                if let Some(hereinafter) = citation.modifiers.hereinafter {
+                    hereinafters.push((citation.key(), hereinafter));
+                }
+                hereinafters.extend(self::hereinafters(&citation.nested));
+            }
+            _ => {}
+        }
+    }
+
+    hereinafters
+}
+
+/// Parse the sources cited in a parenthetical.
+///
+/// Returns the branches between the parentheses, or nothing if the
+/// parenthetical doesn't cite a source (and so is left as it is). A
+/// parenthetical that can't be lexed is explanatory text, not a citation.
+fn parenthetical_parser(parenthetical: &str) -> Result<Vec<Branch<'_>>, Vec<SourceError<'_>>> {
+    if !parenthetical.contains('@') {
+        return Ok(Vec::new());
+    }
+    let tokens = match lexer::parenthetical_lexer(parenthetical) {
+        Ok(tokens) => tokens,
+        Err(errors) => {
+            debug!(
+                slog_scope::logger(),
+                "Leaving {:?} as it is: {}", parenthetical, errors[0].message
+            );
+            return Ok(Vec::new());
+        }
+    };
+
+    if !tokens.iter().any(|t| t.token_type == TokenType::Reference) {
+        return Ok(Vec::new());
+    }
+
+    trace!(
+        slog_scope::logger(),
+        "Parsing the citations in {:?}",
+        parenthetical
+    );
+    Ok(footnote_parser(&tokens, 0)?.contents)
 }
 
 #[cfg(test)]
@@ -334,7 +791,22 @@ mod tests {
     use super::*;
 
     mod text_parser {
-        //
+        use super::*;
+
+        #[test]
+        fn spans() {
+            let input = "Text.^[A note. *See* [@jones2021] at 5.]";
+            let tokens = lexer::lexer(input).unwrap();
+            let tree = parser(&tokens, 0).unwrap();
+
+            assert_eq!(tree[0].span(input), Some(0..5));
+            let Branch::Footnote(footnote) = &tree[1] else {
+                panic!("Expected a footnote");
+            };
+            assert_eq!(tree[1].span(input), Some(7..15));
+            let citation = footnote.contents[1].span(input).unwrap();
+            assert_eq!(&input[citation], "*See* [@jones2021] at 5.");
+        }
     }
 
     mod footnote_parser {
@@ -452,7 +924,7 @@ mod tests {
             assert_eq!(footnote.contents[2].print_contents(), r#""#);
             assert_eq!(
                 footnote.contents[3].print_contents(),
-                r#"[@smith2020] Some("10--12") Some("(super-complicated discussion)") ;"#
+                r#"[@smith2020] Some("10–12") Some("(super-complicated discussion)") ;"#
             );
             assert_eq!(
                 footnote.contents[4].print_contents(),
@@ -520,18 +992,67 @@ mod tests {
             let citation = cite_parser(&input).unwrap();
 
             assert_eq!(citation.reference, "[@jones2021]");
-            assert_eq!(citation.pincite, Some(r"§\ 100.3[D](2)"));
+            assert_eq!(
+                citation
+                    .pincite
+                    .map(|p| p.render(Style::Bluebook))
+                    .as_deref(),
+                Some(r"§\ 100.3[D](2)")
+            );
             assert_eq!(
                 citation.parenthetical,
                 Some("(a parenthetical (with internal parentheses))")
             );
             assert_eq!(citation.punctuation, ".");
         }
+
+        #[test]
+        fn explanatory_parentheticals() {
+            // Neither parenthetical lexes as a footnote, and neither cites a
+            // source, so both are kept as they are.
+            for parenthetical in ["(the [$ key)", "(write to me@example.com [$ soon)"] {
+                let input = vec![
+                    Token::new(TokenType::Reference, "[@jones2021]"),
+                    Token::new(TokenType::Parenthetical, parenthetical),
+                    Token::new(TokenType::CitePunctuation, "."),
+                ];
+                let citation = cite_parser(&input).unwrap();
+
+                assert_eq!(citation.parenthetical, Some(parenthetical));
+                assert!(citation.nested.is_empty());
+            }
+        }
+    }
+
+    mod modifier_parser {
+        use super::*;
+
+        #[test]
+        fn modifiers() {
+            let modifiers = modifier_parser(r#"{.short .noid hereinafter = "Smith I"}"#);
+            assert_eq!(modifiers.form, Some(Form::Short));
+            assert!(modifiers.no_id);
+            assert_eq!(modifiers.hereinafter, Some("Smith I"));
+
+            let modifiers = modifier_parser(r"{.long .unknown}");
+            assert_eq!(
+                modifiers,
+                Modifiers {
+                    form: Some(Form::Long),
+                    no_id: false,
+                    hereinafter: None,
+                }
+            );
+        }
     }
 
     mod pin_parser {
         use super::*;
 
+        fn pin_parser(input: &str) -> Option<String> {
+            pincite::parse(input).map(|p| p.render(Style::Bluebook))
+        }
+
         #[test]
         fn pin_variety() {
             let pin0 = pin_parser(" ");
@@ -545,12 +1066,12 @@ mod tests {
 
             assert_eq!(pin0, None);
             assert_eq!(pin7, None);
-            assert_eq!(pin1, Some(r"§\ 100.3[D](2)"));
-            assert_eq!(pin2, Some("100--01"));
-            assert_eq!(pin3, Some("100, 200"));
-            assert_eq!(pin4, Some("100 n.1"));
-            assert_eq!(pin5, Some("100 nn.1--45"));
-            assert_eq!(pin6, Some("500, 550 n.5"));
+            assert_eq!(pin1.as_deref(), Some(r"§\ 100.3[D](2)"));
+            assert_eq!(pin2.as_deref(), Some("100–01"));
+            assert_eq!(pin3.as_deref(), Some("100, 200"));
+            assert_eq!(pin4.as_deref(), Some("100 n.1"));
+            assert_eq!(pin5.as_deref(), Some("100 nn.1–45"));
+            assert_eq!(pin6.as_deref(), Some("500, 550 n.5"));
         }
     }
 }
@@ -0,0 +1,358 @@
+//! This module contains the structured pincite.
+//!
+//! A pincite is parsed from the text after a citation's reference into the kind
+//! of thing it points to (pages, sections, paragraphs, *etc.*) and the numbers
+//! it points to. That lets Supra normalize range dashes, shorten page ranges,
+//! decide whether a short form needs an "at," and tell whether two pincites
+//! point to the same place.
+
+use crate::config::Style;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    /// Regex for a range dash: a hyphen, en dash, em dash, or Pandoc's `--`
+    /// or `---`.
+    static ref DASH: Regex = Regex::new(r"\s*(?:---|--|—|–|-)\s*").unwrap();
+
+    /// Regex for a range dash that isn't a hyphen, which can be part of a
+    /// section or paragraph number (*e.g.*, `§ 12-3-104`).
+    static ref LONG_DASH: Regex = Regex::new(r"\s*(?:---|--|—|–)\s*").unwrap();
+
+    /// Regex for the footnotes in a pincite, *e.g.*, the `n.4` in `12 n.4`.
+    static ref NOTES: Regex = Regex::new(r"(?:^|\s)nn?\.\s*").unwrap();
+
+    /// Regex for a front-matter page number in Roman numerals (below a
+    /// thousand), all in lowercase or all in uppercase, *e.g.*, `xiv`.
+    static ref ROMAN: Regex = Regex::new(
+        r"^(?:(?:cd|d?c{0,3})(?:xc|xl|l?x{0,3})(?:ix|iv|v?i{0,3})|(?:CD|D?C{0,3})(?:XC|XL|L?X{0,3})(?:IX|IV|V?I{0,3}))$"
+    )
+    .unwrap();
+}
+
+/// The dash between the ends of a range.
+const EN_DASH: &str = "–";
+
+/// What a pincite points to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PinKind {
+    /// Pages, *e.g.*, `12`, `12--15`, or `12 n.4`.
+    Page,
+    /// Star pages in an electronic database, *e.g.*, `*3`.
+    Star,
+    /// Sections, *e.g.*, `§ 1983` or `§§ 1961--1968`.
+    Section,
+    /// Paragraphs, *e.g.*, `¶ 4`.
+    Paragraph,
+    /// The source as a whole, *i.e.*, `passim`.
+    Passim,
+    /// A page that isn't known yet, *i.e.*, `tk`.
+    Tk,
+    /// Anything else, which is kept as it's written.
+    Other,
+}
+
+/// A single page, section, or paragraph, or a range of them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: String,
+    pub end: Option<String>,
+}
+
+/// A parsed pincite.
+///
+/// * `kind`: What the pincite points to.
+/// * `spans`: The pages, sections, or paragraphs.
+/// * `notes`: The footnotes on the (last) page, *e.g.*, the `4` in `12 n.4`.
+/// * `text`: The pincite as it's written (without any "at"), which is kept
+///   only for [`PinKind::Passim`] and [`PinKind::Other`].
+///
+/// Two pincites are equal if they point to the same place, however they're
+/// written, *e.g.*, `at 10-12` and `10--12`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pincite {
+    pub kind: PinKind,
+    pub spans: Vec<Span>,
+    pub notes: Vec<Span>,
+    pub text: String,
+}
+
+impl Pincite {
+    /// A pincite that's kept as it's written.
+    fn verbatim(kind: PinKind, text: &str) -> Pincite {
+        Pincite {
+            kind,
+            spans: Vec::new(),
+            notes: Vec::new(),
+            text: text.to_string(),
+        }
+    }
+
+    /// Whether a short form puts "at" before the pincite.
+    ///
+    /// The Bluebook doesn't use "at" before a section or paragraph symbol (rule
+    /// 3.3), or before "passim."
+    pub fn takes_at(&self) -> bool {
+        !matches!(
+            self.kind,
+            PinKind::Section | PinKind::Paragraph | PinKind::Passim
+        )
+    }
+
+    /// Render the pincite for a style.
+    ///
+    /// Ranges get an en dash. The Bluebook styles also drop repetitious digits
+    /// from page ranges, keeping the last two (rule 3.2(a)), *e.g.*, `1234–40`.
+    pub fn render(&self, style: Style) -> String {
+        let shorten = style.is_bluebook();
+
+        match self.kind {
+            PinKind::Page => {
+                let mut pin = join(&self.spans, "", shorten);
+                if !self.notes.is_empty() {
+                    if !pin.is_empty() {
+                        pin.push(' ');
+                    }
+                    pin.push_str(if plural(&self.notes) { "nn." } else { "n." });
+                    pin.push_str(&join(&self.notes, "", false));
+                }
+                pin
+            }
+            PinKind::Star => join(&self.spans, "*", shorten),
+            PinKind::Section => {
+                let symbol = if plural(&self.spans) { "§§" } else { "§" };
+                format!(r"{}\ {}", symbol, join(&self.spans, "", false))
+            }
+            PinKind::Paragraph => {
+                let symbol = if plural(&self.spans) { "¶¶" } else { "¶" };
+                format!(r"{}\ {}", symbol, join(&self.spans, "", false))
+            }
+            PinKind::Tk => "tk".to_string(),
+            PinKind::Passim | PinKind::Other => self.text.clone(),
+        }
+    }
+
+    /// The sections or paragraphs without their symbol (or the rendered
+    /// pincite for anything else), with full ranges.
+    ///
+    /// This is for a CSL style, which adds its own label.
+    pub fn locator(&self) -> String {
+        match self.kind {
+            PinKind::Section | PinKind::Paragraph => join(&self.spans, "", false),
+            _ => self.render(Style::Csl),
+        }
+    }
+}
+
+/// Parse a pincite.
+///
+/// Any "at" before the pincite is removed. Returns `None` if there's no
+/// pincite.
+pub fn parse(input: &str) -> Option<Pincite> {
+    let pin = input.trim();
+    let pin = match pin.strip_prefix("at") {
+        Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => rest.trim(),
+        _ => pin,
+    };
+
+    if pin.is_empty() {
+        return None;
+    }
+
+    if pin.eq_ignore_ascii_case("tk") {
+        return Some(Pincite::verbatim(PinKind::Tk, ""));
+    }
+    if pin.trim_matches(['*', '_']).eq_ignore_ascii_case("passim") {
+        return Some(Pincite::verbatim(PinKind::Passim, pin));
+    }
+
+    let parsed = if let Some(rest) = pin.strip_prefix('§') {
+        spans(symbol_number(rest.trim_start_matches('§')), false).map(|s| (PinKind::Section, s))
+    } else if let Some(rest) = pin.strip_prefix('¶') {
+        spans(symbol_number(rest.trim_start_matches('¶')), false).map(|s| (PinKind::Paragraph, s))
+    } else if pin.starts_with('*') && !pin.starts_with("**") {
+        spans(&pin.replace('*', ""), true).map(|s| (PinKind::Star, s))
+    } else {
+        None
+    };
+
+    if let Some((kind, spans)) = parsed {
+        return Some(Pincite {
+            kind,
+            spans,
+            notes: Vec::new(),
+            text: String::new(),
+        });
+    }
+
+    // Pages, with any footnotes after them.
+    let (pages, notes) = match NOTES.find(pin) {
+        Some(n) => (pin[..n.start()].trim(), Some(&pin[n.end()..])),
+        None => (pin, None),
+    };
+    let page_spans = if pages.is_empty() && notes.is_some() {
+        Some(Vec::new())
+    } else {
+        spans(pages, true)
+    };
+    let note_spans = match notes {
+        Some(n) => spans(n, true),
+        None => Some(Vec::new()),
+    };
+
+    match (page_spans, note_spans) {
+        (Some(spans), Some(notes)) => Some(Pincite {
+            kind: PinKind::Page,
+            spans,
+            notes,
+            text: String::new(),
+        }),
+        _ => Some(Pincite::verbatim(PinKind::Other, pin)),
+    }
+}
+
+/// Remove the space (including Pandoc's escaped, nonbreaking space) between a
+/// section or paragraph symbol and the number.
+fn symbol_number(input: &str) -> &str {
+    let input = input.trim_start();
+    input.strip_prefix(r"\ ").unwrap_or(input).trim_start()
+}
+
+/// Parse a comma-separated list of spans, *e.g.*, `5, 7--9`.
+///
+/// A hyphen is a range dash only for pages (`pages` is true). Page numbers have
+/// to be single words. Returns `None` if the list can't be parsed.
+fn spans(input: &str, pages: bool) -> Option<Vec<Span>> {
+    let dash: &Regex = if pages { &DASH } else { &LONG_DASH };
+
+    input
+        .split(',')
+        .map(|piece| {
+            let piece = piece.trim();
+            let mut ends = dash.splitn(piece, 2).map(str::trim);
+            let start = ends.next().filter(|s| !s.is_empty())?;
+            let end = match ends.next() {
+                Some("") => return None,
+                e => e,
+            };
+
+            if pages && [Some(start), end].iter().flatten().any(|e| !is_page(e)) {
+                return None;
+            }
+
+            Some(Span {
+                start: start.to_string(),
+                end: end.map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Whether a string can be a page number, *e.g.*, `12`, `12a`, `3:14`, or
+/// `xiv`.
+fn is_page(input: &str) -> bool {
+    (input
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '.' || c == ':')
+        && input.chars().any(|c| c.is_ascii_digit()))
+        || (!input.is_empty() && ROMAN.is_match(input))
+}
+
+/// Whether the spans need a plural symbol or label (`§§` or `nn.`).
+fn plural(spans: &[Span]) -> bool {
+    spans.len() > 1 || spans.iter().any(|s| s.end.is_some())
+}
+
+/// Join spans, with `prefix` before each page (the star for star pages).
+fn join(spans: &[Span], prefix: &str, shorten: bool) -> String {
+    spans
+        .iter()
+        .map(|span| match &span.end {
+            Some(end) => {
+                let end = if shorten {
+                    shorten_end(&span.start, end)
+                } else {
+                    end
+                };
+                format!("{}{}{}{}", prefix, span.start, EN_DASH, end)
+            }
+            None => format!("{}{}", prefix, span.start),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Drop the repetitious digits from the end of a page range, keeping the last
+/// two, *e.g.*, `40` for `1234--1240`.
+fn shorten_end<'a>(start: &str, end: &'a str) -> &'a str {
+    let numeric = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !numeric(start) || !numeric(end) || start.len() != end.len() {
+        return end;
+    }
+
+    let shared = start
+        .bytes()
+        .zip(end.bytes())
+        .take_while(|(s, e)| s == e)
+        .count();
+    let keep = (end.len() - shared).max(2).min(end.len());
+
+    &end[end.len() - keep..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(input: &str) -> String {
+        parse(input).unwrap().render(Style::Bluebook)
+    }
+
+    #[test]
+    fn pages() {
+        assert_eq!(render("at 12"), "12");
+        assert_eq!(render("1234-1240"), "1234–40");
+        assert_eq!(render("1234 -- 1334"), "1234–334");
+        assert_eq!(render("100---105"), "100–05");
+        assert_eq!(render("5–7, 12"), "5–7, 12");
+        assert_eq!(render("12 n.4"), "12 n.4");
+        assert_eq!(render("12 nn. 4-5"), "12 nn.4–5");
+        assert_eq!(render("*3-*4"), "*3–4");
+        assert_eq!(render("tk"), "tk");
+        assert_eq!(render("xiv-xvi"), "xiv–xvi");
+        assert_eq!(parse("civil").unwrap().kind, PinKind::Other);
+        assert_eq!(parse("mix").unwrap().kind, PinKind::Other);
+        assert_eq!(
+            parse("1234-1240").unwrap().render(Style::Oscola),
+            "1234–1240"
+        );
+    }
+
+    #[test]
+    fn sections_and_paragraphs() {
+        assert_eq!(render("§ 1983"), r"§\ 1983");
+        assert_eq!(render(r"at §\ 100.3[D](2)"), r"§\ 100.3[D](2)");
+        assert_eq!(render("§§ 1961--1968"), r"§§\ 1961–1968");
+        assert_eq!(render("§ 12-3-104"), r"§\ 12-3-104");
+        assert_eq!(render("¶ 4"), r"¶\ 4");
+        assert!(!parse("§ 1983").unwrap().takes_at());
+        assert!(parse("12").unwrap().takes_at());
+        assert_eq!(parse("§§ 1961--1968").unwrap().locator(), "1961–1968");
+    }
+
+    #[test]
+    fn other() {
+        assert_eq!(render("*passim*"), "*passim*");
+        assert_eq!(parse("*passim*").unwrap().kind, PinKind::Passim);
+        assert_eq!(render("12 & n.4"), "12 & n.4");
+        assert_eq!(parse("12 & n.4").unwrap().kind, PinKind::Other);
+        assert_eq!(parse(" "), None);
+    }
+
+    #[test]
+    fn equality() {
+        assert_eq!(parse("at 10-12"), parse("10--12"));
+        assert_eq!(parse("§ 3"), parse(r"§\ 3"));
+        assert_ne!(parse("10"), parse("10 n.2"));
+    }
+}
@@ -1,8 +1,14 @@
 //! This module contains functionality for rendering the output.
 
-use crate::pre::{
-    parser::{Branch, PreCite},
-    sourcemap::{SourceMap, SourceType},
+use crate::{
+    config::Style,
+    pre::{
+        csljson::Library,
+        lexer::{self, TokenType},
+        parser::{self, append_definitions, Branch, Citation, Form, Labels, PreCite},
+        pincite::Pincite,
+        sourcemap::{buildsource, SourceMap, SourceType},
+    },
 };
 use ansi_term::Color;
 use slog::{debug, trace};
@@ -16,10 +22,97 @@ use std::collections::HashMap;
 /// one citation. If it did, then an `*Id.*` is not appropriate. If it didn't,
 /// the next citation will ask whether it was the only source cited in the last
 /// clause. If it was, then an `*Id.*` is appropriate.
+///
+/// AGLC is stricter, so this also tracks the footnote containing the last
+/// citation, every source cited in that footnote, and whether the last citation
+/// had a pin.
 struct LastCitation {
     sources: Vec<String>,
     closed: bool,
-    last_pin: String,
+    last_pin: Option<Pincite>,
+    footnote: i32,
+    footnote_sources: Vec<String>,
+    pinned: bool,
+}
+
+impl LastCitation {
+    fn new() -> LastCitation {
+        LastCitation {
+            sources: Vec::new(),
+            closed: false,
+            last_pin: None,
+            footnote: 0,
+            footnote_sources: Vec::new(),
+            pinned: false,
+        }
+    }
+}
+
+/// Render a citation to a source that isn't in the library as it's written.
+///
+/// The citation is copied from the input. If every source in a group
+/// (`[see @a; @b]`) is unknown, each citation takes the bracket or space before
+/// it along, so the group is left exactly as it was. Otherwise the group's
+/// brackets are left out, like they are for the sources that are rendered.
+fn unknown_citation(
+    branch: &Branch,
+    citation: &Citation,
+    input: &str,
+    source_map: &SourceMap,
+    csl_library: &Library,
+) -> String {
+    let Some(span) = branch.span(input) else {
+        return format!("{}{}", citation.reference, citation.punctuation);
+    };
+    if citation.reference.starts_with('[') {
+        return input[span].to_string();
+    }
+
+    let unknown = group_start(input, span.start)
+        .and_then(|start| lexer::group_lexer(&input[start..]))
+        .is_some_and(|(tokens, _)| {
+            tokens
+                .iter()
+                .filter(|t| t.token_type == TokenType::Reference)
+                .all(|t| {
+                    !source_map.contains_key(csl_library.resolve(parser::reference_key(t.contents)))
+                })
+        });
+
+    if unknown {
+        let mut start = input[..span.start].trim_end().len();
+        if input[..start].ends_with('[') {
+            start -= 1;
+        } else {
+            start = span.start;
+        }
+        input[start..span.end].to_string()
+    } else {
+        // The last citation's punctuation comes after the closing bracket.
+        let end = span.end - citation.punctuation.len();
+        let written = &input[span.start..end];
+        format!(
+            "{}{}",
+            written.strip_suffix(']').unwrap_or(written),
+            citation.punctuation
+        )
+    }
+}
+
+/// Find the opening bracket of the group of citations around `at`.
+fn group_start(input: &str, at: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in input[..at].bytes().enumerate().rev() {
+        match c {
+            b')' | b']' => depth += 1,
+            b'(' => depth -= 1,
+            b'[' if depth == 0 => return Some(i),
+            b'[' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
 }
 
 /// The main render function.
@@ -28,10 +121,16 @@ struct LastCitation {
 /// function to a a string in return. Sending to a separate function is
 /// necessary because some branches contain sub-branches, which themselves need
 /// to be rendered/send to the [`render_branch`] function.
+///
+/// The source map is keyed by the library's ids, so the cited keys are
+/// resolved against `csl_library` in case they're aliases.
 pub fn render(
     tree: &[Branch],
+    input: &str,
     source_map: &mut SourceMap,
+    csl_library: &Library,
     crossref_map: &HashMap<&str, i32>,
+    style: Style,
 ) -> String {
     debug!(slog_scope::logger(), "Beginning rendering...");
 
@@ -41,22 +140,33 @@ pub fn render(
 
     // Track the footnotes and cites.
     let mut current_footnote = 0;
-    let mut last_citation = LastCitation {
-        sources: Vec::new(),
-        closed: false,
-        last_pin: "".to_string(),
-    };
+    let mut last_citation = LastCitation::new();
 
-    // Push the render results.
+    // Push the render results. Footnotes go back in the style they came in,
+    // with the definitions of reference footnotes at the end.
+    let mut definitions = Vec::new();
+    let mut labels = Labels::new(tree);
     for branch in tree {
-        output.push_str(&render_branch(
+        let rendered = render_branch(
             branch,
+            input,
             source_map,
+            csl_library,
             crossref_map,
             &mut current_footnote,
             &mut last_citation,
-        ));
+            style,
+        );
+
+        if let Branch::Footnote(footnote) = branch {
+            let (text, definition) = footnote.wrap(&rendered, &mut labels);
+            output.push_str(&text);
+            definitions.extend(definition);
+        } else {
+            output.push_str(&rendered);
+        }
     }
+    append_definitions(&mut output, &definitions);
 
     debug!(slog_scope::logger(), "Rendering complete");
     output
@@ -67,12 +177,16 @@ pub fn render(
 /// Receives branches and renders them depending on their type, returning a
 /// string. Note that branches themselves can call this funciton to render their
 /// contents (which footnote branches do).
+#[allow(clippy::too_many_arguments)]
 fn render_branch(
     branch: &Branch,
+    input: &str,
     source_map: &mut SourceMap,
+    csl_library: &Library,
     crossref_map: &HashMap<&str, i32>,
     current_footnote: &mut i32,
     last_citation: &mut LastCitation,
+    style: Style,
 ) -> String {
     trace!(slog_scope::logger(), "Rendering branch...");
 
@@ -91,15 +205,30 @@ fn render_branch(
             for footnote_branch in &footnote.contents {
                 contents.push_str(&render_branch(
                     footnote_branch,
+                    input,
                     source_map,
+                    csl_library,
                     crossref_map,
                     current_footnote,
                     last_citation,
+                    style,
                 ));
             }
-            format!("^[{}]", contents.trim())
+            // The footnote's markup is added by `render`.
+            contents.trim().to_string()
+        }
+        // A source that isn't in the library (which was reported when the
+        // source map was built) is left as it's written, and it breaks the
+        // chain of citations like a cite breaker.
+        Branch::Citation(citation)
+            if !source_map.contains_key(csl_library.resolve(citation.key())) =>
+        {
+            last_citation.sources = Vec::new();
+            last_citation.closed = true;
+            unknown_citation(branch, citation, input, source_map, csl_library)
         }
         Branch::Citation(citation) => {
+            let key = csl_library.resolve(citation.key());
             let mut contents = String::new();
             let mut capitalize = true;
             let mut render_punctuation = true;
@@ -118,7 +247,11 @@ fn render_branch(
                     }
                     PreCite::Signal(s) => {
                         capitalize = false;
-                        contents.push_str(s.contents);
+                        if style.is_bluebook() {
+                            contents.push_str(&s.normalized());
+                        } else {
+                            contents.push_str(s.contents);
+                        }
                     }
                 }
             }
@@ -133,12 +266,27 @@ fn render_branch(
             // the current one, OR (2)\ (a)\ the last citation clause is open,
             // and (b)\ the immediately preceding source is the same one, use an
             // `*Id.*`.
-            if (last_citation.closed
-                && last_citation.sources.len() == 1
-                && source_map[citation.reference].id == last_citation.sources[0])
-                || (!last_citation.closed
-                    && !last_citation.sources.is_empty()
-                    && &source_map[citation.reference].id == last_citation.sources.last().unwrap())
+            //
+            // AGLC also requires that (1)\ the last citation was in the same
+            // footnote or the only source in its footnote, and (2)\ a cite
+            // without a pin doesn't follow a cite with one.
+            let aglc_ibid = style != Style::Aglc
+                || ((last_citation.footnote == *current_footnote
+                    || last_citation.footnote_sources.len() == 1)
+                    && (citation.pincite.is_some() || !last_citation.pinned));
+
+            // A modifier that forces a form or rules out an *Id.* also
+            // prevents one.
+            let id_allowed = citation.modifiers.form.is_none() && !citation.modifiers.no_id;
+
+            if id_allowed
+                && aglc_ibid
+                && ((last_citation.closed
+                    && last_citation.sources.len() == 1
+                    && source_map[key].id == last_citation.sources[0])
+                    || (!last_citation.closed
+                        && !last_citation.sources.is_empty()
+                        && &source_map[key].id == last_citation.sources.last().unwrap()))
             {
                 // It's an *Id.*
 
@@ -147,103 +295,106 @@ fn render_branch(
                     capitalize = false;
                 }
 
-                // Render the *Id.*
-                match capitalize {
-                    true => contents.push_str("*Id.*"),
-                    false => contents.push_str("*id.*"),
+                // Render the *Id.* (or, for OSCOLA and AGLC, the "ibid").
+                match (style, capitalize) {
+                    (Style::Bluebook | Style::Bluepages, true) => contents.push_str("*Id.*"),
+                    (Style::Bluebook | Style::Bluepages, false) => contents.push_str("*id.*"),
+                    (_, true) => contents.push_str("Ibid"),
+                    (_, false) => contents.push_str("ibid"),
                 }
 
                 // If there's nothing after the *Id.*, don't render any
-                // subsequent punctuation.
-                render_punctuation = false;
-
-                // If there's a pincite AND it's different from the last
-                // pincite, add a pin.
-                if citation.pincite.is_some()
-                    && citation.pincite.as_ref().unwrap() != &last_citation.last_pin
-                {
-                    contents.push_str(" at ");
-                    contents.push_str(citation.pincite.as_ref().unwrap());
-
-                    // There's something between the *Id.* and the punctuation,
-                    // so the period in the *Id.* can't be the ending
-                    // punctuation.
-                    render_punctuation = true;
-                }
-            } else if source_map[citation.reference].source_type == SourceType::Case {
-                // Case citation.
-
-                // Determine if this is the first footnote OR if the case has
-                // been cited in the last 4 footnotes.
-                let current_footnote_local = *current_footnote;
-
-                // Has the case been cited in the last five footnotes?
-                let last_five = source_map[citation.reference]
-                    .all_footnotes
-                    .contains(&(current_footnote_local - 1))
-                    || source_map[citation.reference]
-                        .all_footnotes
-                        .contains(&(current_footnote_local - 2))
-                    || source_map[citation.reference]
-                        .all_footnotes
-                        .contains(&(current_footnote_local - 3))
-                    || source_map[citation.reference]
-                        .all_footnotes
-                        .contains(&(current_footnote_local - 4))
-                    || source_map[citation.reference]
-                        .all_footnotes
-                        .contains(&(current_footnote_local - 5));
-
-                // Or is it the first time its cited?
-                if current_footnote_local == source_map[citation.reference].all_footnotes[0]
-                    || !last_five
-                {
-                    // Long form.
-                    if citation.pincite.is_some() {
-                        contents.push_str(
-                            &source_map[citation.reference]
-                                .long_cite_w_pin(citation.pincite.unwrap()),
-                        );
-                    } else {
-                        contents.push_str(&source_map[citation.reference].long_cite_no_pin());
-                    }
-                } else {
-                    // Short form.
-                    if citation.pincite.is_some() {
-                        contents.push_str(
-                            &source_map[citation.reference]
-                                .short_cite_w_pin(citation.pincite.unwrap()),
-                        );
-                    } else {
-                        contents.push_str(&source_map[citation.reference].short_cite_no_pin())
+                // subsequent punctuation. "ibid" has no period, so it always
+                // needs the punctuation.
+                render_punctuation = !style.is_bluebook();
+
+                // If there's a pincite AND it points somewhere other than the
+                // last pincite, add a pin.
+                if let Some(pincite) = &citation.pincite {
+                    if citation.pincite != last_citation.last_pin {
+                        if style.is_bluebook() && pincite.takes_at() {
+                            contents.push_str(" at ");
+                        } else {
+                            contents.push(' ');
+                        }
+                        contents.push_str(&pincite.render(style));
+
+                        // There's something between the *Id.* and the
+                        // punctuation, so the period in the *Id.* can't be
+                        // the ending punctuation.
+                        render_punctuation = true;
                     }
                 }
-            } else if !source_map[citation.reference].cited {
-                // Citation to a source using supra that has not yet been cited.
-
-                // Set the cited flag and output a long cite.
-                source_map.get_mut(citation.reference).unwrap().cited = true;
-
-                if citation.pincite.is_some() {
-                    contents.push_str(
-                        &source_map[citation.reference].long_cite_w_pin(citation.pincite.unwrap()),
-                    );
+            } else {
+                // Whether the cite is long or short. A modifier can force
+                // either one.
+                let long = if let Some(form) = citation.modifiers.form {
+                    form == Form::Long
+                } else if style.is_bluebook() && source_map[key].source_type == SourceType::Case {
+                    // Case citation. OSCOLA has no five-footnote rule for
+                    // cases, so they follow the same path as every other
+                    // source.
+                    let current_footnote_local = *current_footnote;
+
+                    // Has the case been cited in the last five footnotes (or in
+                    // the body text since the fifth-to-last footnote)?
+                    let source = &source_map[key];
+                    let last_five = (1..=5).any(|n| {
+                        source.all_footnotes.contains(&(current_footnote_local - n))
+                            || source
+                                .text_references
+                                .contains(&(current_footnote_local - n))
+                    });
+
+                    // Or is it the first time its cited? A citation in the body
+                    // text isn't a full citation, so the first footnote
+                    // citation is always long.
+                    let first = !source.cited || current_footnote_local == source.all_footnotes[0];
+
+                    first || !last_five
                 } else {
-                    contents.push_str(&source_map[citation.reference].long_cite_no_pin());
+                    // A source using supra gets a long cite only the first
+                    // time it's cited.
+                    !source_map[key].cited
+                };
+
+                source_map.get_mut(key).unwrap().cited = true;
+
+                let source = &source_map[key];
+                match (&citation.pincite, long) {
+                    (Some(pincite), true) => contents.push_str(&source.long_cite_w_pin(pincite)),
+                    (None, true) => contents.push_str(&source.long_cite_no_pin()),
+                    (Some(pincite), false) => contents.push_str(&source.short_cite_w_pin(pincite)),
+                    (None, false) => contents.push_str(&source.short_cite_no_pin()),
                 }
-            } else if citation.pincite.is_some() {
-                // Otherwise the source using a supra has already been cited.
-                contents.push_str(
-                    &source_map[citation.reference].short_cite_w_pin(citation.pincite.unwrap()),
-                );
-            } else {
-                contents.push_str(&source_map[citation.reference].short_cite_no_pin());
             }
 
             // Parenthetical, regardless of the cite type.
             if citation.parenthetical.is_some() {
                 contents.push(' ');
-                contents.push_str(citation.parenthetical.unwrap());
+
+                if citation.nested.is_empty() {
+                    contents.push_str(citation.parenthetical.unwrap());
+                } else {
+                    // Sources cited in a parenthetical get long and short
+                    // forms like any others, but they're tracked apart from
+                    // the last citation: an *Id.* never refers to one.
+                    let mut nested_citation = LastCitation::new();
+                    contents.push('(');
+                    for nested_branch in &citation.nested {
+                        contents.push_str(&render_branch(
+                            nested_branch,
+                            input,
+                            source_map,
+                            csl_library,
+                            crossref_map,
+                            current_footnote,
+                            &mut nested_citation,
+                            style,
+                        ));
+                    }
+                    contents.push(')');
+                }
 
                 render_punctuation = true;
             }
@@ -261,21 +412,28 @@ fn render_branch(
             // one. If it's not, add the new citation to the collection if it's
             // not already there.
             if last_citation.closed {
-                last_citation.sources = vec![source_map[citation.reference].id.clone()];
+                last_citation.sources = vec![source_map[key].id.clone()];
                 last_citation.closed = false;
-            } else if !last_citation
-                .sources
-                .contains(&source_map[citation.reference].id)
-            {
+            } else if !last_citation.sources.contains(&source_map[key].id) {
+                last_citation.sources.push(source_map[key].id.clone());
+            }
+
+            // Track the sources in the current footnote.
+            if last_citation.footnote != *current_footnote {
+                last_citation.footnote = *current_footnote;
+                last_citation.footnote_sources = Vec::new();
+            }
+            if !last_citation.footnote_sources.contains(&source_map[key].id) {
                 last_citation
-                    .sources
-                    .push(source_map[citation.reference].id.clone());
+                    .footnote_sources
+                    .push(source_map[key].id.clone());
             }
 
             // If there was a pincite, update that.
             if citation.pincite.is_some() {
-                last_citation.last_pin = citation.pincite.as_ref().unwrap().to_string();
+                last_citation.last_pin = citation.pincite.clone();
             }
+            last_citation.pinned = citation.pincite.is_some();
 
             // Then, if the latest's sources punctuation ends a clause, close
             // off the clause.
@@ -307,5 +465,14 @@ fn render_branch(
             last_citation.closed = true;
             "".to_string()
         }
+
+        // A citation in the body text names the source. It doesn't affect
+        // *Id.*s, which only refer to citations in footnotes.
+        Branch::Narrative(narrative) => {
+            match source_map.get(csl_library.resolve(narrative.key())) {
+                Some(source) => buildsource::build_narrative(source, narrative.suppress_author()),
+                None => narrative.reference.to_string(),
+            }
+        }
     }
 }
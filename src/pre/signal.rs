@@ -0,0 +1,308 @@
+//! This module contains the signals that introduce citations.
+//!
+//! A signal is parsed into its kind (*see*, *but cf.*, *etc.*) and whether it's
+//! combined with *e.g.* That lets Supra italicize and capitalize signals
+//! consistently and check that they follow the Bluebook's order (rules 1.2 and
+//! 1.3).
+
+use crate::pre::{
+    error,
+    parser::{Branch, PreCite},
+};
+use ansi_term::Color;
+use slog::warn;
+
+/// The kinds of signals, in the order rule 1.2 lists them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignalKind {
+    Eg,
+    Accord,
+    See,
+    SeeAlso,
+    Cf,
+    Compare,
+    With,
+    Contra,
+    ButSee,
+    ButCf,
+    SeeGenerally,
+}
+
+impl SignalKind {
+    /// The signal's words, uncapitalized.
+    fn words(self) -> &'static str {
+        match self {
+            SignalKind::Eg => "e.g.",
+            SignalKind::Accord => "accord",
+            SignalKind::See => "see",
+            SignalKind::SeeAlso => "see also",
+            SignalKind::Cf => "cf.",
+            SignalKind::Compare => "compare",
+            SignalKind::With => "with",
+            SignalKind::Contra => "contra",
+            SignalKind::ButSee => "but see",
+            SignalKind::ButCf => "but cf.",
+            SignalKind::SeeGenerally => "see generally",
+        }
+    }
+
+    /// The signal's place in rule 1.2's order. *With* is part of *compare*.
+    fn rank(self) -> u8 {
+        match self {
+            SignalKind::Eg => 0,
+            SignalKind::Accord => 1,
+            SignalKind::See => 2,
+            SignalKind::SeeAlso => 3,
+            SignalKind::Cf => 4,
+            SignalKind::Compare | SignalKind::With => 5,
+            SignalKind::Contra => 6,
+            SignalKind::ButSee => 7,
+            SignalKind::ButCf => 8,
+            SignalKind::SeeGenerally => 9,
+        }
+    }
+
+    /// The signal's type under rule 1.2: support (0), comparison (1),
+    /// contradiction (2), or background (3).
+    fn category(self) -> u8 {
+        match self.rank() {
+            0..=4 => 0,
+            5 => 1,
+            6..=8 => 2,
+            _ => 3,
+        }
+    }
+}
+
+/// Parse a signal, however it's italicized, capitalized, or punctuated.
+///
+/// Returns the signal's kind and whether it's combined with *e.g.*, or `None`
+/// if it isn't a signal.
+pub fn parse(input: &str) -> Option<(SignalKind, bool)> {
+    let words = input
+        .replace(['*', ','], "")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase();
+
+    let (base, eg) = match words.strip_suffix(" e.g.") {
+        Some(base) => (base, true),
+        None => (words.as_str(), false),
+    };
+
+    let kind = match base {
+        "e.g." => SignalKind::Eg,
+        "accord" => SignalKind::Accord,
+        "see" => SignalKind::See,
+        "see also" => SignalKind::SeeAlso,
+        "cf." => SignalKind::Cf,
+        "compare" => SignalKind::Compare,
+        "with" => SignalKind::With,
+        "contra" => SignalKind::Contra,
+        "but see" => SignalKind::ButSee,
+        "but cf." => SignalKind::ButCf,
+        "see generally" => SignalKind::SeeGenerally,
+        _ => return None,
+    };
+
+    Some((kind, eg))
+}
+
+/// Render a signal the Bluebook way (rule 1.2).
+///
+/// The signal is italicized and, if it starts a citation sentence,
+/// capitalized. *E.g.* is followed by a comma, and a signal combined with it
+/// gets a comma on each side of it, *e.g.*, `*See, e.g.*,`.
+pub fn render(kind: SignalKind, eg: bool, capitalize: bool) -> String {
+    let words = kind.words();
+    let words = if capitalize && kind != SignalKind::With {
+        let mut chars = words.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    } else {
+        words.to_string()
+    };
+
+    if eg {
+        format!("*{}, e.g.*,", words)
+    } else if kind == SignalKind::Eg {
+        format!("*{}*,", words)
+    } else {
+        format!("*{}*", words)
+    }
+}
+
+/// Whether a signal combined with *e.g.* is missing one of its commas, *e.g.*,
+/// `see e.g.`
+fn missing_commas(input: &str) -> bool {
+    let plain = input.replace('*', "");
+    let plain = plain.trim();
+
+    !(plain.contains(", e.g.") && plain.ends_with(','))
+}
+
+/// Check the signals in the footnotes against the Bluebook.
+///
+/// Warns when a signal combined with *e.g.* is missing its commas, when a
+/// signal comes before one that rule 1.2 puts ahead of it, and when a
+/// different type of signal doesn't start a new citation sentence (rule 1.3).
+/// A citation string ends with any text between citations.
+pub fn check_signals(tree: &[Branch], input: &str) {
+    for branch in tree {
+        if let Branch::Footnote(footnote) = branch {
+            for message in check_string(&footnote.contents, footnote.number, input) {
+                warn!(slog_scope::logger(), "{}", message);
+                eprintln!("  {} {}", Color::Yellow.paint("WARN"), message);
+            }
+        }
+    }
+}
+
+/// Check the signals in one footnote (or parenthetical).
+///
+/// Returns a message for each problem found.
+fn check_string(branches: &[Branch], footnote: i32, input: &str) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut last: Option<SignalKind> = None;
+    let mut same_sentence = false;
+
+    for branch in branches {
+        match branch {
+            Branch::Text(text) if !text.contents.trim().is_empty() => {
+                last = None;
+                same_sentence = false;
+            }
+            Branch::CrossRef(_) => {
+                last = None;
+                same_sentence = false;
+            }
+            Branch::Citation(citation) => {
+                // Where the citation is, for the warnings.
+                let place = match branch.span(input) {
+                    Some(span) => {
+                        let (line, column) = error::line_column(input, span.start);
+                        format!("In note {} (line {}, column {})", footnote, line, column)
+                    }
+                    None => format!("In note {}", footnote),
+                };
+
+                if let Some(PreCite::Signal(signal)) = &citation.pre_cite {
+                    if let Some(kind) = signal.kind {
+                        if signal.eg && missing_commas(signal.contents) {
+                            messages.push(format!(
+                                "{}, \"{}\" needs a comma before and after \"e.g.\"",
+                                place,
+                                signal.contents.trim()
+                            ));
+                        }
+
+                        if let Some(previous) = last {
+                            if kind.rank() < previous.rank() {
+                                messages.push(format!(
+                                    "{}, *{}* should come before *{}* (Bluebook rule 1.2)",
+                                    place,
+                                    kind.words(),
+                                    previous.words()
+                                ));
+                            } else if same_sentence && kind.category() != previous.category() {
+                                messages.push(format!(
+                                    "{}, *{}* should start a new citation sentence (Bluebook rule 1.3)",
+                                    place,
+                                    kind.words()
+                                ));
+                            }
+                        }
+
+                        last = Some(kind);
+                    }
+                }
+
+                messages.extend(check_string(&citation.nested, footnote, input));
+                same_sentence = citation.punctuation.trim() == ";";
+            }
+            _ => {}
+        }
+    }
+
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pre::{lexer, parser};
+
+    #[test]
+    fn parse_signals() {
+        assert_eq!(parse("*See, e.g.*, "), Some((SignalKind::See, true)));
+        assert_eq!(parse("see e.g. "), Some((SignalKind::See, true)));
+        assert_eq!(parse("*But cf.* "), Some((SignalKind::ButCf, false)));
+        assert_eq!(parse("E.g. "), Some((SignalKind::Eg, false)));
+        assert_eq!(
+            parse("*See generally*"),
+            Some((SignalKind::SeeGenerally, false))
+        );
+        assert_eq!(parse("discussing"), None);
+    }
+
+    #[test]
+    fn render_signals() {
+        assert_eq!(render(SignalKind::SeeAlso, false, true), "*See also*");
+        assert_eq!(render(SignalKind::SeeAlso, false, false), "*see also*");
+        assert_eq!(render(SignalKind::Cf, true, true), "*Cf., e.g.*,");
+        assert_eq!(render(SignalKind::With, false, true), "*with*");
+        assert_eq!(render(SignalKind::Eg, false, true), "*E.g.*,");
+    }
+
+    #[test]
+    fn commas() {
+        assert!(!missing_commas("*See, e.g.*, "));
+        assert!(!missing_commas("see, e.g., "));
+        assert!(missing_commas("see e.g. "));
+        assert!(missing_commas("*See, e.g.* "));
+    }
+
+    #[test]
+    fn order() {
+        assert!(SignalKind::See.rank() < SignalKind::Cf.rank());
+        assert_eq!(SignalKind::With.rank(), SignalKind::Compare.rank());
+        assert_ne!(SignalKind::Cf.category(), SignalKind::ButSee.category());
+    }
+
+    /// Check the signals in the first footnote of the input.
+    fn check(input: &str) -> Vec<String> {
+        let tokens = lexer::lexer(input).unwrap();
+        let tree = parser::parser(&tokens, 0).unwrap();
+        match &tree[1] {
+            Branch::Footnote(footnote) => check_string(&footnote.contents, footnote.number, input),
+            _ => panic!("no footnote"),
+        }
+    }
+
+    #[test]
+    fn warnings() {
+        // A signal out of order.
+        assert_eq!(
+            check("Text.^[*Cf.* [@a] at 1; *see* [@b] at 2.]"),
+            ["In note 1 (line 1, column 25), *see* should come before *cf.* (Bluebook rule 1.2)"]
+        );
+
+        // A different type of signal in the same citation sentence, but not in
+        // a new one.
+        assert_eq!(
+            check("Text.^[*See* [@a] at 1; *but see* [@b] at 2.]"),
+            ["In note 1 (line 1, column 25), *but see* should start a new citation sentence (Bluebook rule 1.3)"]
+        );
+        assert!(check("Text.^[*See* [@a] at 1. *But see* [@b] at 2.]").is_empty());
+
+        // *e.g.* without its commas.
+        assert_eq!(
+            check("Text.^[*See e.g.* [@a] at 1.]"),
+            ["In note 1 (line 1, column 8), \"*See e.g.*\" needs a comma before and after \"e.g.\""]
+        );
+        assert!(check("Text.^[*See, e.g.*, [@a] at 1; *see also* [@b].]").is_empty());
+    }
+}
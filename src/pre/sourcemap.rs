@@ -1,10 +1,13 @@
 //! The module contains the functionality for creating the source map.
 
-mod buildsource;
+pub mod buildsource;
 
-use super::csljson::CSLSource;
+use super::csljson::{CSLSource, Library};
+use super::housestyle::HouseStyle;
 use super::parser::Branch;
+use super::pincite::Pincite;
 use super::userjournals::UserJournals;
+use crate::config::Style;
 use ansi_term::Color;
 use slog::{debug, o, trace, warn};
 use std::collections::HashMap;
@@ -22,6 +25,8 @@ pub type SourceMap<'a> = HashMap<&'a str, Source<'a>>;
 ///   variant.
 /// * `all_footnotes`: A collection of all of the footnotes in which the source
 ///   is cited.
+/// * `text_references`: For each citation of the source in the body text, the
+///   number of the footnote before it.
 /// * `long_cite_no_pin`: A [`String`] of the source's long cite, with no
 ///   pincite.
 /// * `long_cite_with_pin`: A tuple of [`String`]s that are used for long cites
@@ -34,12 +39,16 @@ pub type SourceMap<'a> = HashMap<&'a str, Source<'a>>;
 ///   short form can be used. TODO This could be changed to `last_cited` and
 ///   used for determining whether `Id.` can be used instead of a short cite.
 /// * `hereinafter`: Whether the `Source` requires a hereinafter.
+/// * `style`: The citation style, which determines how pincites are joined to
+///   the long and short cites.
 #[derive(Debug)]
 pub struct Source<'a> {
     pub csl_source: &'a CSLSource,
     pub id: String,
     pub source_type: SourceType,
+    pub style: Style,
     pub all_footnotes: Vec<i32>,
+    pub text_references: Vec<i32>,
     pub long_cite_no_pin: Option<String>,
     pub long_cite_w_pin: Option<(String, String)>,
     pub short_author: Option<String>,
@@ -56,13 +65,46 @@ impl Source<'_> {
     }
 
     /// Output a long cite with a pin.
-    pub fn long_cite_w_pin(&self, pin: &str) -> String {
+    pub fn long_cite_w_pin(&self, pincite: &Pincite) -> String {
+        let pin = pincite.render(self.style);
+        let pin = pin.as_str();
         let mut cite = self.long_cite_w_pin.as_ref().unwrap().0.to_owned();
-        if self.source_type == SourceType::Book {
+        if self.style == Style::Oscola {
+            // OSCOLA pinpoints follow books, chapters, and manuscripts with a
+            // space. They follow first pages with a comma, unless the pinpoint
+            // is to a paragraph.
+            if self.source_type == SourceType::Book
+                || self.source_type == SourceType::Chapter
+                || self.source_type == SourceType::Manuscript
+                || pin.starts_with('[')
+            {
+                cite.push(' ');
+            } else {
+                cite.push_str(", ");
+            }
+            cite.push_str(pin);
+        } else if self.style == Style::Aglc {
+            // AGLC pinpoints follow books and manuscripts with a space, and
+            // everything else (which ends with a first page or paragraph) with
+            // a comma.
+            if self.source_type == SourceType::Book
+                || self.source_type == SourceType::Manuscript
+                || (self.source_type == SourceType::Chapter && self.csl_source.page.is_none())
+            {
+                cite.push(' ');
+            } else {
+                cite.push_str(", ");
+            }
+            cite.push_str(pin);
+        } else if self.source_type == SourceType::Book {
             cite.push(' ');
             cite.push_str(pin);
         } else if self.source_type == SourceType::Manuscript {
-            cite.push_str(" (manuscript at ");
+            if pincite.takes_at() {
+                cite.push_str(" (manuscript at ");
+            } else {
+                cite.push_str(" (manuscript ");
+            }
             cite.push_str(pin);
             cite.push(')');
         } else {
@@ -80,19 +122,33 @@ impl Source<'_> {
     }
 
     /// Output a short cite with a pin.
-    pub fn short_cite_w_pin(&self, pin: &str) -> String {
+    ///
+    /// The Bluebook styles put "at" before the pin unless it's to a section,
+    /// paragraph, or "passim."
+    pub fn short_cite_w_pin(&self, pincite: &Pincite) -> String {
         let mut cite = self.short_cite_w_pin.as_ref().unwrap().to_owned();
-        if self.source_type == SourceType::Book
+        if self.style == Style::Oscola || self.style == Style::Aglc {
+            // OSCOLA and AGLC never use "at."
+            cite.push(' ');
+        } else if self.source_type == SourceType::Book
             || self.source_type == SourceType::Chapter
             || self.source_type == SourceType::JournalArticle
             || self.source_type == SourceType::Manuscript
         {
-            cite.push_str(", at ");
+            if pincite.takes_at() {
+                cite.push_str(", at ");
+            } else {
+                cite.push_str(", ");
+            }
         } else if self.source_type == SourceType::Case {
-            cite.push_str(" at ");
+            if pincite.takes_at() {
+                cite.push_str(" at ");
+            } else {
+                cite.push(' ');
+            }
         }
 
-        cite.push_str(pin);
+        cite.push_str(&pincite.render(self.style));
 
         cite
     }
@@ -121,20 +177,23 @@ pub enum SourceType {
 /// then determine which sources need "hereinafters." After that, it creates
 /// appropriate long cites. Then short cites are created.
 ///
-/// Note, the identifiers in the [`SourceMap`] map include the brackets and at
-/// symbol, *e.g.*, `[@smith2021]`. But the identifers in [`CSLSource`]s don't,
-/// *e.g.*, `smith2021`.
+/// Note, the identifiers in the [`SourceMap`] are the [`CSLSource`]s' ids, which
+/// don't include the brackets and at symbol, *e.g.*, `smith2021`. A key cited
+/// in the document is an alias when it's renamed, so keys are resolved with
+/// [`Library::resolve`] before they're looked up.
 pub fn build_source_map<'a>(
     tree: &'a [Branch],
-    csl_library: &'a [CSLSource],
+    csl_library: &'a Library,
     user_journals: &'a Option<UserJournals>,
+    style: Style,
+    house_style: &HouseStyle,
 ) -> SourceMap<'a> {
     debug!(slog_scope::logger(), "Starting source map...");
 
     // Start the source map.
     let mut source_map = slog_scope::scope(
         &slog_scope::logger().new(o!("fn" => "start_source_map()")),
-        || start_source_map(csl_library, tree),
+        || start_source_map(csl_library, tree, style, house_style),
     );
 
     // Determine the need for hereinafters
@@ -146,13 +205,13 @@ pub fn build_source_map<'a>(
     // Add the long cites for every source.
     slog_scope::scope(
         &slog_scope::logger().new(o!("fn" => "add_long_cites()")),
-        || add_long_cites(&mut source_map, user_journals),
+        || add_long_cites(&mut source_map, user_journals, house_style),
     );
 
     // Add the short cites for every source.
     slog_scope::scope(
         &slog_scope::logger().new(o!("fn" => "add_short_cites()")),
-        || add_short_cites(&mut source_map),
+        || add_short_cites(&mut source_map, house_style),
     );
 
     debug!(slog_scope::logger(), "Source map complete.");
@@ -160,159 +219,50 @@ pub fn build_source_map<'a>(
 }
 
 /// Begin the source map, which contains only partial info.
-fn start_source_map<'a>(csl_library: &'a [CSLSource], tree: &'a [Branch]) -> SourceMap<'a> {
+fn start_source_map<'a>(
+    csl_library: &'a Library,
+    tree: &'a [Branch],
+    style: Style,
+    house_style: &HouseStyle,
+) -> SourceMap<'a> {
     debug!(slog_scope::logger(), "Starting source map build...");
 
     // Create a blank source map.
     let mut source_map: SourceMap<'a> = HashMap::new();
 
-    // Iterate through each branch in the tree, looking for footnotes.
+    // Iterate through each branch in the tree, looking for citations in
+    // footnotes and the body text.
     for branch in tree {
-        if let Branch::Footnote(footnote) = branch {
-            for footnote_branch in &footnote.contents {
-                // Iterate through the footnote branches looking for citations.
-                if let Branch::Citation(citation) = footnote_branch {
-                    // Remove the brackets and @ to search through the CSL
-                    // library's ids
-                    let csl_id = &citation.reference[2..citation.reference.len() - 1];
-
-                    // If the key is already in the sourcemap, Add the footnote
-                    // number to the list of footnotes and continue to the next
-                    // iteration of the loop.
-                    if source_map.contains_key(citation.reference) {
-                        source_map
-                            .get_mut(&citation.reference)
-                            .unwrap()
-                            .all_footnotes
-                            .push(footnote.number);
-
-                        trace!(
-                            slog_scope::logger(),
-                            "Adding footnote {} to {}",
-                            footnote.number,
-                            Color::Blue.paint(csl_id)
-                        );
-
-                        continue;
-                    }
-
-                    // Iterate through the CSL JSON library looking for the id
-                    trace!(
-                        slog_scope::logger(),
-                        "Searching the CSL JSON library for {:?}...",
-                        csl_id
-                    );
-                    let mut iter = csl_library.iter();
-                    let csl_source = iter.find(|&x| x.id == csl_id);
-
-                    // If the citation is not found in the CSL JSON library, log
-                    // the error and continue to the next iteration of the loop.
-                    if csl_source.is_none() {
-                        warn!(
-                            slog_scope::logger(),
-                            "{} was not found in the CSL JSON library; not adding to source map",
-                            Color::Blue.paint(csl_id)
-                        );
-                        eprintln!("  {} {} was not found in the CSL JSON library; not adding to source map",
-                                Color::Yellow.paint("WARN"),
-                                Color::Blue.paint(csl_id)
-                            );
-
-                        continue;
-                    }
-
-                    // If found in the CSL JSON library, add it to the source
-                    // map.
-                    trace!(
-                        slog_scope::logger(),
-                        "Found {:?} in the CSL JSON library.",
-                        csl_id
-                    );
-                    let csl_source = csl_source.unwrap();
-
-                    // If there's no type in the CSL JSON library, log the error
-                    // and continue to the next iteration of the loop.
-                    if csl_source.source_type.is_none() {
-                        warn!(
-                            slog_scope::logger(),
-                            "{} does not have a type; not adding to source map",
-                            Color::Blue.paint(csl_id),
-                        );
-                        eprintln!(
-                            "  {} {} does not have a type; not adding to source map",
-                            Color::Yellow.paint("WARN"),
-                            Color::Blue.paint(csl_id)
-                        );
-
-                        continue;
-                    }
-
-                    // Determine the source type. All supported source types
-                    // should be in this match.
-                    let source_type = match csl_source.source_type.as_ref().unwrap().as_str() {
-                        "book" => SourceType::Book,
-                        "chapter" => SourceType::Chapter,
-                        "article-journal" => SourceType::JournalArticle,
-                        "manuscript" => SourceType::Manuscript,
-                        "legal_case" => SourceType::Case,
-                        _ => SourceType::Other,
-                    };
-
-                    // Unsupported type
-                    if source_type == SourceType::Other {
-                        warn!(
-                            slog_scope::logger(),
-                            "{}'s type ({}) is not supported; not adding to source map",
-                            Color::Blue.paint(csl_id),
-                            csl_source.source_type.as_ref().unwrap(),
-                        );
-                        eprintln!(
-                            "  {} {}'s type ({}) is not supported; not adding to source map",
-                            Color::Yellow.paint("WARN"),
-                            Color::Blue.paint(csl_id),
-                            csl_source.source_type.as_ref().unwrap(),
-                        );
-
-                        continue;
-                    }
-
-                    // Build the Source.
-                    match slog_scope::scope(
-                        &slog_scope::logger().new(o!("fn" => "build_source()")),
-                        || {
-                            buildsource::build_source(
-                                csl_source,
-                                csl_id,
-                                source_type,
+        match branch {
+            Branch::Footnote(footnote) => {
+                // Sources cited in a parenthetical count as cited in the
+                // footnote.
+                for footnote_branch in &footnote.contents {
+                    if let Branch::Citation(citation) = footnote_branch {
+                        for key in citation.keys() {
+                            add_reference(
+                                &mut source_map,
+                                csl_library,
+                                key,
                                 footnote.number,
-                            )
-                        },
-                    ) {
-                        Ok(source) => {
-                            trace!(
-                                slog_scope::logger(),
-                                "Adding source {}",
-                                Color::Blue.paint(csl_id)
-                            );
-                            source_map.insert(citation.reference, source);
-                        }
-                        Err(e) => {
-                            warn!(
-                                slog_scope::logger(),
-                                "Cannot build citation for {}; {}",
-                                Color::Blue.paint(csl_id),
-                                e
+                                false,
+                                style,
+                                house_style,
                             );
-                            eprintln!(
-                                "  {} Cannot build citation for {}; {:?}",
-                                Color::Yellow.paint("WARN"),
-                                Color::Blue.paint(csl_id),
-                                e
-                            )
                         }
                     }
                 }
             }
+            Branch::Narrative(narrative) => add_reference(
+                &mut source_map,
+                csl_library,
+                narrative.key(),
+                narrative.footnote,
+                true,
+                style,
+                house_style,
+            ),
+            _ => {}
         }
     }
 
@@ -320,6 +270,174 @@ fn start_source_map<'a>(csl_library: &'a [CSLSource], tree: &'a [Branch]) -> Sou
     source_map
 }
 
+/// Add a reference to a source to the source map.
+///
+/// A source that's already in the map gets the footnote number (or, for a
+/// citation in the body text, the number of the footnote before it). Otherwise,
+/// the source is looked up in the library and built.
+fn add_reference<'a>(
+    source_map: &mut SourceMap<'a>,
+    csl_library: &'a Library,
+    csl_id: &'a str,
+    footnote: i32,
+    in_text: bool,
+    style: Style,
+    house_style: &HouseStyle,
+) {
+    // An old key and its new one are the same source.
+    let csl_id = csl_library.resolve(csl_id);
+
+    // If the key is already in the sourcemap, add the footnote number to its
+    // list of footnotes (or body-text references) and return.
+    if let Some(source) = source_map.get_mut(csl_id) {
+        if in_text {
+            source.text_references.push(footnote);
+        } else {
+            source.all_footnotes.push(footnote);
+        }
+
+        trace!(
+            slog_scope::logger(),
+            "Adding footnote {} to {}",
+            footnote,
+            Color::Blue.paint(csl_id)
+        );
+
+        return;
+    }
+
+    // Look up the id in the CSL JSON library
+    trace!(
+        slog_scope::logger(),
+        "Searching the CSL JSON library for {:?}...",
+        csl_id
+    );
+    let csl_source = csl_library.get(csl_id);
+
+    // If the citation is not found in the CSL JSON library, log
+    // the error and continue to the next iteration of the loop.
+    if csl_source.is_none() {
+        warn!(
+            slog_scope::logger(),
+            "{} was not found in the CSL JSON library; not adding to source map",
+            Color::Blue.paint(csl_id)
+        );
+        eprintln!(
+            "  {} {} was not found in the CSL JSON library; not adding to source map",
+            Color::Yellow.paint("WARN"),
+            Color::Blue.paint(csl_id)
+        );
+
+        return;
+    }
+
+    // If found in the CSL JSON library, add it to the source
+    // map.
+    trace!(
+        slog_scope::logger(),
+        "Found {:?} in the CSL JSON library.",
+        csl_id
+    );
+    let csl_source = csl_source.unwrap();
+
+    // A `supra-type` in the note overrides the library's type.
+    //
+    // If there's no type in the CSL JSON library, log the error
+    // and continue to the next iteration of the loop.
+    let Some(type_name) = csl_source
+        .supra
+        .source_type
+        .as_ref()
+        .or(csl_source.source_type.as_ref())
+    else {
+        warn!(
+            slog_scope::logger(),
+            "{} does not have a type; not adding to source map",
+            Color::Blue.paint(csl_id),
+        );
+        eprintln!(
+            "  {} {} does not have a type; not adding to source map",
+            Color::Yellow.paint("WARN"),
+            Color::Blue.paint(csl_id)
+        );
+
+        return;
+    };
+
+    // Determine the source type. All supported source types
+    // should be in this match.
+    let source_type = match type_name.as_str() {
+        "book" => SourceType::Book,
+        "chapter" => SourceType::Chapter,
+        "article-journal" => SourceType::JournalArticle,
+        "manuscript" => SourceType::Manuscript,
+        "legal_case" => SourceType::Case,
+        _ => SourceType::Other,
+    };
+
+    // Unsupported type
+    if source_type == SourceType::Other {
+        warn!(
+            slog_scope::logger(),
+            "{}'s type ({}) is not supported; not adding to source map",
+            Color::Blue.paint(csl_id),
+            type_name,
+        );
+        eprintln!(
+            "  {} {}'s type ({}) is not supported; not adding to source map",
+            Color::Yellow.paint("WARN"),
+            Color::Blue.paint(csl_id),
+            type_name,
+        );
+
+        return;
+    }
+
+    // Build the Source.
+    match slog_scope::scope(
+        &slog_scope::logger().new(o!("fn" => "build_source()")),
+        || {
+            buildsource::build_source(
+                csl_source,
+                csl_id,
+                source_type,
+                footnote,
+                style,
+                house_style,
+            )
+        },
+    ) {
+        Ok(mut source) => {
+            // A source first cited in the body text has no footnotes yet.
+            if in_text {
+                source.all_footnotes.clear();
+                source.text_references.push(footnote);
+            }
+
+            trace!(
+                slog_scope::logger(),
+                "Adding source {}",
+                Color::Blue.paint(csl_id)
+            );
+            source_map.insert(csl_id, source);
+        }
+        Err(e) => {
+            warn!(
+                slog_scope::logger(),
+                "Cannot build citation for {}; {}",
+                Color::Blue.paint(csl_id),
+                e
+            );
+            eprintln!(
+                "  {} Cannot build citation for {}; {:?}",
+                Color::Yellow.paint("WARN"),
+                Color::Blue.paint(csl_id),
+                e
+            )
+        }
+    }
+}
+
 /// Check which sources need "hereinafter" short cites.
 ///
 /// Each `Source` contains a boolean `hereinafter` value for whether sources
@@ -330,6 +448,8 @@ fn start_source_map<'a>(csl_library: &'a [CSLSource], tree: &'a [Branch]) -> Sou
 /// *e.g.* "Smith", "Smith & Jones", or "Smith et al.". If a short cite is not
 /// in the author list, it's added to the list. If the short cite is in the
 /// list, the `hereinafter` value is changed to true.
+///
+/// A source with a `supra-hereinafter` in its note always gets a hereinafter.
 //fn check_hereinafters(mut source_map: SourceMap<'_>) -> SourceMap<'_> {
 fn check_hereinafters(source_map: &mut SourceMap<'_>) {
     debug!(slog_scope::logger(), "Checking \"hereinafter\" needs...");
@@ -365,7 +485,9 @@ fn check_hereinafters(source_map: &mut SourceMap<'_>) {
     for (_, source) in source_map.iter_mut() {
         let unformatted_author = source.short_author.as_ref().unwrap().replace("**", "");
 
-        if author_list[&unformatted_author].len() > 1 {
+        if author_list[&unformatted_author].len() > 1
+            || source.csl_source.supra.hereinafter.is_some()
+        {
             source.hereinafter = true;
         }
     }
@@ -374,7 +496,11 @@ fn check_hereinafters(source_map: &mut SourceMap<'_>) {
 }
 
 /// Add long cites to sources.
-fn add_long_cites<'a>(source_map: &mut SourceMap<'a>, user_journals: &'a Option<UserJournals>) {
+fn add_long_cites<'a>(
+    source_map: &mut SourceMap<'a>,
+    user_journals: &'a Option<UserJournals>,
+    house_style: &HouseStyle,
+) {
     debug!(slog_scope::logger(), "Adding long cites...");
 
     for (_, source) in source_map.iter_mut() {
@@ -384,6 +510,8 @@ fn add_long_cites<'a>(source_map: &mut SourceMap<'a>, user_journals: &'a Option<
                 source.source_type,
                 user_journals,
                 source.hereinafter,
+                source.style,
+                house_style,
             )
             .unwrap();
 
@@ -395,15 +523,19 @@ fn add_long_cites<'a>(source_map: &mut SourceMap<'a>, user_journals: &'a Option<
 }
 
 /// Add short cites to sources.
-fn add_short_cites(source_map: &mut SourceMap<'_>) {
+fn add_short_cites(source_map: &mut SourceMap<'_>, house_style: &HouseStyle) {
     debug!(slog_scope::logger(), "Adding short cites...");
 
     for (_, source) in source_map.iter_mut() {
         let (short_cite_no_pin, short_cite_w_pin) = buildsource::build_short_cite(
             source.csl_source,
             &source.source_type,
-            source.all_footnotes[0],
+            // A source cited only in the body text has no short cites, so the
+            // number doesn't matter.
+            source.all_footnotes.first().copied().unwrap_or_default(),
             source.hereinafter,
+            source.style,
+            house_style,
         );
         source.short_cite_w_pin = Some(short_cite_w_pin);
         source.short_cite_no_pin = Some(short_cite_no_pin);
@@ -1,12 +1,22 @@
 //! This module contains the functions for creating sources.
 
+mod aglc;
 mod build_types;
 mod citetools;
+mod courts;
 mod journalnames;
+mod oscola;
 mod replacements;
 
-use crate::pre::{
-    csljson::CSLSource, sourcemap::Source, sourcemap::SourceType, userjournals::UserJournals,
+pub use citetools::bluebook_ordinal;
+pub use oscola::{build_oscola_authors, join_oscola_names};
+
+use crate::{
+    config::Style,
+    pre::{
+        csljson::CSLSource, housestyle::HouseStyle, sourcemap::Source, sourcemap::SourceType,
+        userjournals::UserJournals,
+    },
 };
 use slog::{error, trace};
 
@@ -16,6 +26,8 @@ pub fn build_source<'a>(
     id: &'a str,
     source_type: SourceType,
     first_footnote: i32,
+    style: Style,
+    house_style: &HouseStyle,
 ) -> Result<Source<'a>, String> {
     trace!(
         slog_scope::logger(),
@@ -26,7 +38,13 @@ pub fn build_source<'a>(
     // A short author is necessary to check hereinafters.
     let mut short_author = String::new();
     if source_type != SourceType::Case {
-        citetools::add_short_author(csl_source, &source_type, &mut short_author);
+        citetools::add_short_author(
+            csl_source,
+            &source_type,
+            house_style,
+            style,
+            &mut short_author,
+        );
     }
 
     let all_footnotes = vec![first_footnote];
@@ -35,7 +53,9 @@ pub fn build_source<'a>(
         csl_source,
         id: id.to_string(),
         source_type,
+        style,
         all_footnotes,
+        text_references: Vec::new(),
         long_cite_no_pin: None,
         long_cite_w_pin: None,
         short_cite_no_pin: None,
@@ -53,12 +73,56 @@ pub fn build_source<'a>(
     Ok(new_source)
 }
 
+/// Build the name of a source cited in the body text.
+///
+/// A case is named by its short name. Any other source is named by its short
+/// author or, if the citation suppresses the author (`[-@smith2021]`) or there
+/// isn't one, its short title. Text uses ordinary type, so an author isn't in
+/// small caps and a title is italicized.
+pub fn build_narrative(source: &Source, suppress_author: bool) -> String {
+    let csl_source = source.csl_source;
+    let mut narrative = String::new();
+
+    if source.source_type == SourceType::Case {
+        match &csl_source.supra.short_form {
+            Some(name) => narrative.push_str(name),
+            None => citetools::add_short_title(
+                csl_source,
+                &source.source_type,
+                source.style,
+                &mut narrative,
+            ),
+        }
+        return narrative;
+    }
+
+    let author = source
+        .short_author
+        .as_deref()
+        .unwrap_or("")
+        .replace("**", "");
+    if !suppress_author && !author.is_empty() {
+        return author;
+    }
+
+    if let Some(title) = csl_source
+        .title_short
+        .as_ref()
+        .or(csl_source.title.as_ref())
+    {
+        narrative.push_str(&citetools::reverse_italicize(title));
+    }
+    narrative
+}
+
 /// Build the long cite---with and without a pin---for a source.
 pub fn build_long_cite(
     csl_source: &CSLSource,
     source_type: SourceType,
     user_journals: &Option<UserJournals>,
     hereinafter: bool,
+    style: Style,
+    house_style: &HouseStyle,
 ) -> Result<(String, String, String), String> {
     trace!(
         slog_scope::logger(),
@@ -76,21 +140,55 @@ pub fn build_long_cite(
         return Err(format!("{} does not have a title", &csl_source.id));
     }
 
+    if !style.is_bluebook() && source_type != SourceType::Other {
+        let (long_cite_no_pin, long_cite_pre_pin, long_cite_post_pin) = match style {
+            Style::Aglc => aglc::build_aglc_long(csl_source, &source_type),
+            _ => oscola::build_oscola_long(csl_source, &source_type, user_journals),
+        };
+
+        trace!(
+            slog_scope::logger(),
+            "Long cite built: {}",
+            long_cite_no_pin
+        );
+        return Ok((long_cite_no_pin, long_cite_pre_pin, long_cite_post_pin));
+    }
+
     let (long_cite_no_pin, long_cite_pre_pin, long_cite_post_pin) = match source_type {
-        SourceType::Book => build_types::build_book_long(csl_source, &source_type, hereinafter),
-        SourceType::Case => build_types::build_case_long(csl_source, &source_type, user_journals),
-        SourceType::Chapter => {
-            build_types::build_chapter_long(csl_source, &source_type, user_journals, hereinafter)
+        SourceType::Book => {
+            build_types::build_book_long(csl_source, &source_type, hereinafter, house_style, style)
         }
+        SourceType::Case => build_types::build_case_long(
+            csl_source,
+            &source_type,
+            user_journals,
+            house_style,
+            style,
+        ),
+        SourceType::Chapter => build_types::build_chapter_long(
+            csl_source,
+            &source_type,
+            user_journals,
+            hereinafter,
+            house_style,
+            style,
+        ),
         SourceType::JournalArticle => build_types::build_journal_article_long(
             csl_source,
             &source_type,
             user_journals,
             hereinafter,
+            house_style,
+            style,
+        ),
+        SourceType::Manuscript => build_types::build_manuscript_long(
+            csl_source,
+            &source_type,
+            user_journals,
+            hereinafter,
+            house_style,
+            style,
         ),
-        SourceType::Manuscript => {
-            build_types::build_manuscript_long(csl_source, &source_type, user_journals, hereinafter)
-        }
         SourceType::Other => {
             return Err("Cannot build cite for source type `Other`".to_string());
         }
@@ -110,11 +208,56 @@ pub fn build_short_cite(
     source_type: &SourceType,
     first_footnote: i32,
     hereinafter: bool,
+    style: Style,
+    house_style: &HouseStyle,
+) -> (String, String) {
+    match style {
+        Style::Oscola => {
+            return oscola::build_oscola_short(
+                csl_source,
+                source_type,
+                first_footnote,
+                hereinafter,
+                oscola::join_oscola_names,
+            )
+        }
+        Style::Aglc => {
+            return aglc::build_aglc_short(csl_source, source_type, first_footnote, hereinafter)
+        }
+        Style::Bluebook | Style::Bluepages | Style::Csl => {}
+    }
+
+    build_bluebook_short(
+        csl_source,
+        source_type,
+        first_footnote,
+        hereinafter,
+        style,
+        house_style,
+    )
+}
+
+/// Build the Bluebook short cite---with and without a pin--for a source.
+///
+/// The house style determines what follows "*supra*." A short form or
+/// hereinafter from the source's note replaces the usual name.
+fn build_bluebook_short(
+    csl_source: &CSLSource,
+    source_type: &SourceType,
+    first_footnote: i32,
+    hereinafter: bool,
+    style: Style,
+    house_style: &HouseStyle,
 ) -> (String, String) {
     let mut short_cite = String::with_capacity(256);
 
+    let note_name = citetools::note_short_name(csl_source, hereinafter);
+
     if source_type == &SourceType::Case {
-        citetools::add_short_title(csl_source, source_type, &mut short_cite);
+        match note_name {
+            Some(name) => short_cite.push_str(name),
+            None => citetools::add_short_title(csl_source, source_type, style, &mut short_cite),
+        }
         if csl_source.volume.is_some() {
             citetools::add_other_volume(csl_source, source_type, &mut short_cite);
         }
@@ -130,16 +273,26 @@ pub fn build_short_cite(
             (short_cite.clone(), short_cite)
         }
     } else {
-        if csl_source.author.is_some() {
-            citetools::add_short_author(csl_source, source_type, &mut short_cite);
+        if let Some(name) = note_name {
+            short_cite.push_str(name);
+        } else if csl_source.author.is_some() {
+            citetools::add_short_author(
+                csl_source,
+                source_type,
+                house_style,
+                style,
+                &mut short_cite,
+            );
         }
 
-        if hereinafter {
+        if hereinafter && note_name.is_none() {
             short_cite.push_str(", ");
-            citetools::add_short_title(csl_source, source_type, &mut short_cite);
+            citetools::add_short_title(csl_source, source_type, style, &mut short_cite);
         }
 
-        short_cite.push_str(", *supra* note ");
+        short_cite.push_str(", *supra* ");
+        short_cite.push_str(&house_style.supra_note);
+        short_cite.push(' ');
         short_cite.push_str(&first_footnote.to_string());
 
         (short_cite.clone(), short_cite)
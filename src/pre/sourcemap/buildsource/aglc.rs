@@ -0,0 +1,332 @@
+//! Functions for building AGLC-style sources.
+//!
+//! The fourth edition of the Australian Guide to Legal Citation (AGLC4) looks a
+//! lot like OSCOLA: no bold or small caps, quoted article titles, and short
+//! forms that refer back to the first footnote with "(n 12)." But journal names
+//! are given in full and italicized, publishers precede editions, and
+//! medium-neutral citations take priority for cases.
+
+use super::citetools::{
+    self, case_title, get_year, ordinal, quoted_title, remove_periods, NEUTRAL,
+};
+use super::oscola::build_oscola_short;
+use crate::pre::{
+    csljson::{CSLSource, NameVariable},
+    sourcemap::SourceType,
+};
+
+/// Build the long cite---with and without a pin---for an AGLC source.
+///
+/// Returns the cite without a pin, the portion of the cite before any pin, and
+/// the portion of the cite after any pin.
+pub fn build_aglc_long(
+    csl_source: &CSLSource,
+    source_type: &SourceType,
+) -> (String, String, String) {
+    let mut long_cite_pre_pin = String::with_capacity(1024);
+    let mut long_cite_post_pin = String::with_capacity(256);
+
+    match source_type {
+        SourceType::Book => {
+            if let Some(author) = &csl_source.author {
+                long_cite_pre_pin.push_str(&build_aglc_authors(author));
+                long_cite_pre_pin.push_str(", ");
+            } else if let Some(editor) = &csl_source.editor {
+                long_cite_pre_pin.push_str(&build_aglc_editors(editor));
+                long_cite_pre_pin.push_str(", ");
+            }
+
+            long_cite_pre_pin.push_str(&citetools::reverse_italicize(
+                csl_source.title.as_ref().unwrap(),
+            ));
+
+            if let Some(volume) = &csl_source.volume {
+                long_cite_pre_pin.push_str(" (vol ");
+                long_cite_pre_pin.push_str(volume);
+                long_cite_pre_pin.push(')');
+            }
+
+            if let Some(editor) = &csl_source.editor {
+                if csl_source.author.is_some() {
+                    long_cite_pre_pin.push_str(", ed ");
+                    long_cite_pre_pin.push_str(&build_aglc_authors(editor));
+                }
+            }
+
+            if let Some(translator) = &csl_source.translator {
+                long_cite_pre_pin.push_str(", tr ");
+                long_cite_pre_pin.push_str(&build_aglc_authors(translator));
+            }
+
+            add_publication_details(csl_source, &mut long_cite_pre_pin);
+        }
+        SourceType::Chapter => {
+            if let Some(author) = &csl_source.author {
+                long_cite_pre_pin.push_str(&build_aglc_authors(author));
+                long_cite_pre_pin.push_str(", ");
+            }
+
+            long_cite_pre_pin.push_str(&quoted_title(csl_source.title.as_ref().unwrap()));
+            long_cite_pre_pin.push_str(" in ");
+
+            if let Some(editor) = &csl_source.editor {
+                long_cite_pre_pin.push_str(&build_aglc_editors(editor));
+                long_cite_pre_pin.push_str(", ");
+            }
+
+            if let Some(container_title) = &csl_source.container_title {
+                long_cite_pre_pin.push_str(&citetools::reverse_italicize(container_title));
+            }
+
+            if let Some(volume) = &csl_source.volume {
+                long_cite_pre_pin.push_str(" (vol ");
+                long_cite_pre_pin.push_str(volume);
+                long_cite_pre_pin.push(')');
+            }
+
+            add_publication_details(csl_source, &mut long_cite_pre_pin);
+
+            if let Some(page) = &csl_source.page {
+                long_cite_pre_pin.push(' ');
+                long_cite_pre_pin.push_str(page);
+            }
+        }
+        SourceType::JournalArticle => {
+            if let Some(author) = &csl_source.author {
+                long_cite_pre_pin.push_str(&build_aglc_authors(author));
+                long_cite_pre_pin.push_str(", ");
+            }
+
+            long_cite_pre_pin.push_str(&quoted_title(csl_source.title.as_ref().unwrap()));
+            long_cite_pre_pin.push(' ');
+
+            // Journals that use the year as the volume put the year in square
+            // brackets and omit the volume.
+            let year = get_year(csl_source);
+            match &csl_source.volume {
+                Some(volume) if csl_source.volume != year => {
+                    if let Some(y) = &year {
+                        long_cite_pre_pin.push_str(&format!("({}) ", y));
+                    }
+                    long_cite_pre_pin.push_str(volume);
+                    long_cite_pre_pin.push(' ');
+                }
+                _ => {
+                    if let Some(y) = &year {
+                        long_cite_pre_pin.push_str(&format!("[{}] ", y));
+                    }
+                }
+            }
+
+            if let Some(container_title) = &csl_source.container_title {
+                long_cite_pre_pin.push_str(&format!("*{}*", container_title));
+            }
+
+            if let Some(page) = &csl_source.page {
+                long_cite_pre_pin.push(' ');
+                long_cite_pre_pin.push_str(page);
+            }
+        }
+        SourceType::Manuscript => {
+            if let Some(author) = &csl_source.author {
+                long_cite_pre_pin.push_str(&build_aglc_authors(author));
+                long_cite_pre_pin.push_str(", ");
+            }
+
+            long_cite_pre_pin.push_str(&quoted_title(csl_source.title.as_ref().unwrap()));
+
+            let year = get_year(csl_source);
+            if let Some(container_title) = &csl_source.container_title {
+                // A manuscript that has been placed with a journal.
+                if let Some(y) = &year {
+                    long_cite_pre_pin.push_str(&format!(" ({})", y));
+                }
+                if let Some(volume) = &csl_source.volume {
+                    long_cite_pre_pin.push(' ');
+                    long_cite_pre_pin.push_str(volume);
+                }
+                long_cite_pre_pin.push_str(&format!(" *{}* (forthcoming)", container_title));
+            } else if let Some(y) = &year {
+                long_cite_pre_pin.push_str(&format!(" (Unpublished manuscript, {})", y));
+            } else {
+                long_cite_pre_pin.push_str(" (Unpublished manuscript)");
+            }
+
+            if let Some(url) = &csl_source.url {
+                long_cite_post_pin.push_str(" <");
+                long_cite_post_pin.push_str(url);
+                long_cite_post_pin.push('>');
+            }
+        }
+        SourceType::Case => {
+            long_cite_pre_pin.push_str(&case_title(csl_source.title.as_ref().unwrap()));
+
+            let neutral = match &csl_source.number {
+                Some(number) => NEUTRAL.is_match(number),
+                None => false,
+            };
+
+            // The medium-neutral citation comes first, followed by any report.
+            if neutral {
+                long_cite_pre_pin.push(' ');
+                long_cite_pre_pin.push_str(csl_source.number.as_ref().unwrap());
+                if csl_source.container_title.is_some() {
+                    long_cite_pre_pin.push(';');
+                }
+            }
+
+            if let Some(container_title) = &csl_source.container_title {
+                // Square brackets when the year identifies the volume, round
+                // brackets when the report series has its own volume numbers.
+                if let Some(y) = get_year(csl_source) {
+                    if csl_source.volume.is_some() {
+                        long_cite_pre_pin.push_str(&format!(" ({})", y));
+                    } else {
+                        long_cite_pre_pin.push_str(&format!(" [{}]", y));
+                    }
+                }
+
+                if let Some(volume) = &csl_source.volume {
+                    long_cite_pre_pin.push(' ');
+                    long_cite_pre_pin.push_str(volume);
+                }
+
+                long_cite_pre_pin.push(' ');
+                long_cite_pre_pin.push_str(&remove_periods(container_title));
+
+                if let Some(page) = &csl_source.page {
+                    long_cite_pre_pin.push(' ');
+                    long_cite_pre_pin.push_str(page);
+                }
+            }
+
+            // The court is given only when the medium-neutral citation doesn't
+            // identify it.
+            if let Some(court) = citetools::court(csl_source) {
+                if !neutral {
+                    long_cite_post_pin.push_str(" (");
+                    long_cite_post_pin.push_str(&remove_periods(&court));
+                    long_cite_post_pin.push(')');
+                }
+            }
+        }
+        SourceType::Other => {}
+    }
+
+    let mut long_cite_no_pin = long_cite_pre_pin.clone();
+    long_cite_no_pin.push_str(&long_cite_post_pin);
+
+    (long_cite_no_pin, long_cite_pre_pin, long_cite_post_pin)
+}
+
+/// Build the short cite---with and without a pin---for an AGLC source.
+///
+/// AGLC4's subsequent references are the same as OSCOLA's, e.g., "Author (n
+/// 12)," except that more than three authors become "et al."
+pub fn build_aglc_short(
+    csl_source: &CSLSource,
+    source_type: &SourceType,
+    first_footnote: i32,
+    hereinafter: bool,
+) -> (String, String) {
+    build_oscola_short(
+        csl_source,
+        source_type,
+        first_footnote,
+        hereinafter,
+        join_aglc_names,
+    )
+}
+
+/// Builds the AGLC long author.
+fn build_aglc_authors(name_vector: &[NameVariable]) -> String {
+    let names: Vec<String> = name_vector.iter().map(citetools::build_full_name).collect();
+
+    join_aglc_names(&names)
+}
+
+/// Joins names AGLC-style.
+///
+/// Names are joined with commas and a final "and." More than three names
+/// become the first name and "et al."
+fn join_aglc_names(names: &[String]) -> String {
+    match names.len() {
+        0 => String::new(),
+        1 => names[0].clone(),
+        2 | 3 => format!(
+            "{} and {}",
+            names[..names.len() - 1].join(", "),
+            names[names.len() - 1]
+        ),
+        _ => format!("{} et al", names[0]),
+    }
+}
+
+/// Builds the editors with "(ed)" or "(eds)."
+fn build_aglc_editors(name_vector: &[NameVariable]) -> String {
+    let mut editors = build_aglc_authors(name_vector);
+    if name_vector.len() > 1 {
+        editors.push_str(" (eds)");
+    } else {
+        editors.push_str(" (ed)");
+    }
+
+    editors
+}
+
+/// Adds the parenthetical with the publisher, edition, and year.
+fn add_publication_details(csl_source: &CSLSource, cite: &mut String) {
+    let mut details: Vec<String> = Vec::new();
+
+    if let Some(publisher) = &csl_source.publisher {
+        details.push(publisher.to_string());
+    }
+
+    if let Some(edition) = &csl_source.edition {
+        details.push(format!("{} ed", ordinal(edition)));
+    }
+
+    if let Some(y) = get_year(csl_source) {
+        details.push(y);
+    }
+
+    if !details.is_empty() {
+        cite.push_str(" (");
+        cite.push_str(&details.join(", "));
+        cite.push(')');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authors() {
+        let names: Vec<NameVariable> = ["A", "B", "C", "D"]
+            .iter()
+            .map(|n| NameVariable {
+                family: Some(n.to_string()),
+                given: None,
+                non_dropping_particle: None,
+                suffix: None,
+                ..Default::default()
+            })
+            .collect();
+
+        assert_eq!(build_aglc_authors(&names[..1]), "A");
+        assert_eq!(build_aglc_authors(&names[..3]), "A, B and C");
+        assert_eq!(build_aglc_authors(&names), "A et al");
+        assert_eq!(build_aglc_editors(&names[..2]), "A and B (eds)");
+
+        let source = CSLSource {
+            author: Some(names),
+            title: Some("Title".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            build_aglc_short(&source, &SourceType::Book, 3, false).0,
+            "A et al (n 3)"
+        );
+    }
+}
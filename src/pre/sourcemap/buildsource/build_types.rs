@@ -1,7 +1,13 @@
 //! Functions for building different kinds of sources.
 
 use super::citetools;
-use crate::pre::{csljson::CSLSource, sourcemap::SourceType, userjournals::UserJournals};
+use crate::{
+    config::Style,
+    pre::{
+        csljson::CSLSource, housestyle::HouseStyle, sourcemap::SourceType,
+        userjournals::UserJournals,
+    },
+};
 
 /// Build the long cite---with and without a pin---for a book.
 pub fn build_book_long(
@@ -9,6 +15,8 @@ pub fn build_book_long(
     source_type: &SourceType,
     //user_journals: &Option<UserJournals>,
     hereinafter: bool,
+    house_style: &HouseStyle,
+    style: Style,
 ) -> (String, String, String) {
     // Start with a large capacity string to avoid repeatedly reallocating
     // memory. We're also starting with the long cite before any pincites. We'll
@@ -21,10 +29,16 @@ pub fn build_book_long(
     };
 
     if csl_source.author.is_some() {
-        citetools::add_authors(csl_source, source_type, &mut long_cite_pre_pin);
+        citetools::add_authors(
+            csl_source,
+            source_type,
+            house_style,
+            style,
+            &mut long_cite_pre_pin,
+        );
     }
 
-    citetools::add_title(csl_source, source_type, &mut long_cite_pre_pin);
+    citetools::add_title(csl_source, source_type, style, &mut long_cite_pre_pin);
 
     // Create another empty string for the post-pincite portion.
     let mut long_cite_post_pin = String::with_capacity(1024);
@@ -34,11 +48,22 @@ pub fn build_book_long(
         || csl_source.translator.is_some()
         || csl_source.issued.is_some()
     {
-        citetools::add_end_parenthetical(csl_source, source_type, &mut long_cite_post_pin);
+        citetools::add_end_parenthetical(
+            csl_source,
+            source_type,
+            house_style,
+            &mut long_cite_post_pin,
+        );
     }
 
     if hereinafter {
-        citetools::add_hereinafter(csl_source, source_type, &mut long_cite_post_pin);
+        citetools::add_hereinafter(
+            csl_source,
+            source_type,
+            house_style,
+            style,
+            &mut long_cite_post_pin,
+        );
     }
 
     let mut long_cite_no_pin = long_cite_pre_pin.clone();
@@ -52,6 +77,8 @@ pub fn build_case_long(
     csl_source: &CSLSource,
     source_type: &SourceType,
     user_journals: &Option<UserJournals>,
+    house_style: &HouseStyle,
+    style: Style,
 ) -> (String, String, String) {
     // Start with a large capacity string to avoid repeatedly reallocating
     // memory. We're also starting with the long cite before any pincites. We'll
@@ -59,7 +86,7 @@ pub fn build_case_long(
     // no-pincite long cite.
     let mut long_cite_pre_pin = String::with_capacity(1024);
 
-    citetools::add_title(csl_source, source_type, &mut long_cite_pre_pin);
+    citetools::add_title(csl_source, source_type, style, &mut long_cite_pre_pin);
 
     if csl_source.volume.is_some() {
         citetools::add_other_volume(csl_source, source_type, &mut long_cite_pre_pin);
@@ -70,6 +97,7 @@ pub fn build_case_long(
             csl_source,
             source_type,
             user_journals,
+            style,
             &mut long_cite_pre_pin,
         );
     }
@@ -81,8 +109,16 @@ pub fn build_case_long(
     // Create another empty string for the post-pincite portion.
     let mut long_cite_post_pin = String::with_capacity(1024);
 
-    if csl_source.authority.is_some() || csl_source.issued.is_some() {
-        citetools::add_end_parenthetical(csl_source, source_type, &mut long_cite_post_pin);
+    if csl_source.authority.is_some()
+        || csl_source.jurisdiction.is_some()
+        || csl_source.issued.is_some()
+    {
+        citetools::add_end_parenthetical(
+            csl_source,
+            source_type,
+            house_style,
+            &mut long_cite_post_pin,
+        );
     }
 
     if csl_source.references.is_some() {
@@ -101,6 +137,8 @@ pub fn build_chapter_long(
     source_type: &SourceType,
     user_journals: &Option<UserJournals>,
     hereinafter: bool,
+    house_style: &HouseStyle,
+    style: Style,
 ) -> (String, String, String) {
     // Start with a large capacity string to avoid repeatedly reallocating
     // memory. We're also starting with the long cite before any pincites. We'll
@@ -109,10 +147,16 @@ pub fn build_chapter_long(
     let mut long_cite_pre_pin = String::with_capacity(1024);
 
     if csl_source.author.is_some() {
-        citetools::add_authors(csl_source, source_type, &mut long_cite_pre_pin);
+        citetools::add_authors(
+            csl_source,
+            source_type,
+            house_style,
+            style,
+            &mut long_cite_pre_pin,
+        );
     }
 
-    citetools::add_title(csl_source, source_type, &mut long_cite_pre_pin);
+    citetools::add_title(csl_source, source_type, style, &mut long_cite_pre_pin);
 
     citetools::add_in(&mut long_cite_pre_pin);
 
@@ -125,6 +169,7 @@ pub fn build_chapter_long(
             csl_source,
             source_type,
             user_journals,
+            style,
             &mut long_cite_pre_pin,
         );
     }
@@ -141,11 +186,22 @@ pub fn build_chapter_long(
         || csl_source.translator.is_some()
         || csl_source.issued.is_some()
     {
-        citetools::add_end_parenthetical(csl_source, source_type, &mut long_cite_post_pin);
+        citetools::add_end_parenthetical(
+            csl_source,
+            source_type,
+            house_style,
+            &mut long_cite_post_pin,
+        );
     }
 
     if hereinafter {
-        citetools::add_hereinafter(csl_source, source_type, &mut long_cite_post_pin);
+        citetools::add_hereinafter(
+            csl_source,
+            source_type,
+            house_style,
+            style,
+            &mut long_cite_post_pin,
+        );
     }
 
     let mut long_cite_no_pin = long_cite_pre_pin.clone();
@@ -160,6 +216,8 @@ pub fn build_journal_article_long(
     source_type: &SourceType,
     user_journals: &Option<UserJournals>,
     hereinafter: bool,
+    house_style: &HouseStyle,
+    style: Style,
 ) -> (String, String, String) {
     // Start with a large capacity string to avoid repeatedly reallocating
     // memory. We're also starting with the long cite before any pincites. We'll
@@ -168,10 +226,16 @@ pub fn build_journal_article_long(
     let mut long_cite_pre_pin = String::with_capacity(1024);
 
     if csl_source.author.is_some() {
-        citetools::add_authors(csl_source, source_type, &mut long_cite_pre_pin);
+        citetools::add_authors(
+            csl_source,
+            source_type,
+            house_style,
+            style,
+            &mut long_cite_pre_pin,
+        );
     }
 
-    citetools::add_title(csl_source, source_type, &mut long_cite_pre_pin);
+    citetools::add_title(csl_source, source_type, style, &mut long_cite_pre_pin);
 
     if csl_source.volume.is_some() {
         citetools::add_other_volume(csl_source, source_type, &mut long_cite_pre_pin);
@@ -182,6 +246,7 @@ pub fn build_journal_article_long(
             csl_source,
             source_type,
             user_journals,
+            style,
             &mut long_cite_pre_pin,
         );
     }
@@ -199,11 +264,22 @@ pub fn build_journal_article_long(
             || csl_source.translator.is_some()
             || csl_source.issued.is_some())
     {
-        citetools::add_end_parenthetical(csl_source, source_type, &mut long_cite_post_pin);
+        citetools::add_end_parenthetical(
+            csl_source,
+            source_type,
+            house_style,
+            &mut long_cite_post_pin,
+        );
     }
 
     if hereinafter {
-        citetools::add_hereinafter(csl_source, source_type, &mut long_cite_post_pin);
+        citetools::add_hereinafter(
+            csl_source,
+            source_type,
+            house_style,
+            style,
+            &mut long_cite_post_pin,
+        );
     }
 
     let mut long_cite_no_pin = long_cite_pre_pin.clone();
@@ -218,6 +294,8 @@ pub fn build_manuscript_long(
     source_type: &SourceType,
     user_journals: &Option<UserJournals>,
     hereinafter: bool,
+    house_style: &HouseStyle,
+    style: Style,
 ) -> (String, String, String) {
     // Start with a large capacity string to avoid repeatedly reallocating
     // memory. We're also starting with the long cite before any pincites. We'll
@@ -226,10 +304,16 @@ pub fn build_manuscript_long(
     let mut long_cite_pre_pin = String::with_capacity(1024);
 
     if csl_source.author.is_some() {
-        citetools::add_authors(csl_source, source_type, &mut long_cite_pre_pin);
+        citetools::add_authors(
+            csl_source,
+            source_type,
+            house_style,
+            style,
+            &mut long_cite_pre_pin,
+        );
     }
 
-    citetools::add_title(csl_source, source_type, &mut long_cite_pre_pin);
+    citetools::add_title(csl_source, source_type, style, &mut long_cite_pre_pin);
 
     if csl_source.volume.is_some() {
         citetools::add_other_volume(csl_source, source_type, &mut long_cite_pre_pin);
@@ -240,6 +324,7 @@ pub fn build_manuscript_long(
             csl_source,
             source_type,
             user_journals,
+            style,
             &mut long_cite_pre_pin,
         );
     }
@@ -252,7 +337,13 @@ pub fn build_manuscript_long(
     let mut long_cite_post_pin = String::with_capacity(1024);
 
     if hereinafter {
-        citetools::add_hereinafter(csl_source, source_type, &mut long_cite_post_pin);
+        citetools::add_hereinafter(
+            csl_source,
+            source_type,
+            house_style,
+            style,
+            &mut long_cite_post_pin,
+        );
     }
 
     if csl_source.url.is_some() {
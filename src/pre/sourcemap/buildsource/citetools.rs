@@ -1,10 +1,14 @@
 //! This module contains tools for creating citations.
 
-use super::{journalnames, replacements};
-use crate::pre::{
-    csljson::{CSLSource, NameVariable},
-    sourcemap::SourceType,
-    userjournals::UserJournals,
+use super::{courts, journalnames, replacements};
+use crate::{
+    config::Style,
+    pre::{
+        csljson::{CSLSource, NameVariable},
+        housestyle::HouseStyle,
+        sourcemap::SourceType,
+        userjournals::UserJournals,
+    },
 };
 use ansi_term::Color;
 use lazy_static::lazy_static;
@@ -31,6 +35,9 @@ lazy_static! {
     ///
     /// Convert any <i> or </i> surrounded by non-whitespace characters to *.
     pub static ref ITALICS4: Regex = Regex::new(r"<i>|</i>").unwrap();
+
+    /// Regex for identifying a neutral citation, e.g., "[2021] UKSC 5".
+    pub static ref NEUTRAL: Regex = Regex::new(r"^\[\d{4}\] [A-Za-z ]+ \d+$").unwrap();
 }
 
 /// Add the volume at the beginning of a book that has one.
@@ -40,15 +47,21 @@ pub fn add_book_volume(csl_source: &CSLSource, cite: &mut String) {
 }
 
 /// Add the authors. If it's a book, also bold the authors.
-pub fn add_authors(csl_source: &CSLSource, source_type: &SourceType, cite: &mut String) {
+pub fn add_authors(
+    csl_source: &CSLSource,
+    source_type: &SourceType,
+    house_style: &HouseStyle,
+    style: Style,
+    cite: &mut String,
+) {
     let mut author = slog_scope::scope(
         &slog_scope::logger().new(o!("fn" => "build_long_author")),
-        || build_long_author(csl_source.author.as_ref().unwrap()),
+        || build_long_author(csl_source.author.as_ref().unwrap(), house_style),
     );
 
     if source_type == &SourceType::Book {
         author = slog_scope::scope(&slog_scope::logger().new(o!("fn" => "bold()")), || {
-            bold(&author)
+            bold(&author, style)
         });
     }
 
@@ -58,13 +71,19 @@ pub fn add_authors(csl_source: &CSLSource, source_type: &SourceType, cite: &mut
 }
 
 /// Add the short author for hereinafters and short cites.
-pub fn add_short_author(csl_source: &CSLSource, source_type: &SourceType, cite: &mut String) {
+pub fn add_short_author(
+    csl_source: &CSLSource,
+    source_type: &SourceType,
+    house_style: &HouseStyle,
+    style: Style,
+    cite: &mut String,
+) {
     let mut short_author = slog_scope::scope(
         &slog_scope::logger().new(o!("fn" => "build_short_author()")),
-        || build_short_author(csl_source.author.as_ref().unwrap()),
+        || build_short_author(csl_source.author.as_ref().unwrap(), house_style),
     );
     if source_type == &SourceType::Book {
-        short_author = bold(&short_author);
+        short_author = bold(&short_author, style);
     }
 
     cite.push_str(&short_author);
@@ -79,62 +98,41 @@ pub fn add_short_author(csl_source: &CSLSource, source_type: &SourceType, cite:
 /// the following order:
 ///
 /// * Given name
+/// * Dropping particle
 /// * Non-dropping particle
 /// * Family
 /// * Suffix
 ///
-/// There is currently no support for the `dropping-particle`, `comma-suffix`,
-/// `static-ordering`, `literal`, and `parse-name` fields from CSL JSON's
-/// `name-variable` object.
+/// A `literal` name is used as it is. The `comma-suffix`, `static-ordering`,
+/// and `parse-names` fields are kept with the name but not used: the Bluebook
+/// decides the comma before a suffix, and names are always given first.
 ///
-/// The function simply concatenates the existing fields with appropriate
-/// spaces. The only unique operation deals with the `suffix` field. If the
-/// `suffix` is "Jr." or "Sr.", a comma and space precede it. Any other `suffix`
-/// gets only a space.
+/// Each name is built by [`build_full_name`].
 ///
 /// The function then iterates through the vector of name strings, adding them
 /// together into an appropriate form. If there's only one author, the function
 /// simply clones that string. All but the last author are iterated through,
 /// with ", " added after each. Then, the last ", " is removed and the last
-/// author is added with a preceding " & ".
-pub fn build_long_author(name_vector: &[NameVariable]) -> String {
+/// author is added with a preceding " & " (or the house style's conjunction).
+///
+/// If the house style shortens long author lists, lists at or above its limit
+/// become the first author and " et al."
+pub fn build_long_author(name_vector: &[NameVariable], house_style: &HouseStyle) -> String {
     trace!(slog_scope::logger(), "name_vector: {:?}", name_vector);
 
-    let mut long_author = String::with_capacity(256);
-    let num_names = name_vector.len();
-    for (number, name) in name_vector.iter().enumerate() {
-        if name.given.is_some() {
-            long_author.push_str(name.given.as_ref().unwrap().as_str());
-            long_author.push(' ');
-        }
-        if name.non_dropping_particle.is_some() {
-            long_author.push_str(name.non_dropping_particle.as_ref().unwrap().as_str());
-            long_author.push(' ');
-        }
-        if name.family.is_some() {
-            long_author.push_str(name.family.as_ref().unwrap().as_str());
-        }
-        if name.suffix.is_some() {
-            if name.suffix.as_ref().unwrap() == "Jr." || name.suffix.as_ref().unwrap() == "Sr." {
-                long_author.push_str(", ");
-            } else {
-                long_author.push(' ');
-            }
-            long_author.push_str(name.suffix.as_ref().unwrap().as_str());
-        }
+    if let Some(long_et_al) = house_style.long_et_al {
+        if name_vector.len() >= long_et_al {
+            let mut long_author = build_full_name(&name_vector[0]);
+            long_author.push_str(" et al.");
 
-        // Note, subtracting 2 (not 1) from `num_names` is needed because
-        // `num_names` is the length of the vector but `number` is an index that
-        // starts at 0.
-        if num_names > 1 && number < num_names - 2 {
-            // Multiple authors, and not yet at the second to last.
-            long_author.push_str(", ");
-        } else if num_names > 1 && number == num_names - 2 {
-            // Multiple authors, and at the second to last.
-            long_author.push_str(" & ")
+            trace!(slog_scope::logger(), "long_author: {:?}", long_author);
+            return long_author;
         }
     }
 
+    let names: Vec<String> = name_vector.iter().map(build_full_name).collect();
+    let long_author = join_names(&names, &house_style.author_conjunction);
+
     trace!(slog_scope::logger(), "long_author: {:?}", long_author);
 
     long_author
@@ -145,60 +143,121 @@ pub fn build_long_author(name_vector: &[NameVariable]) -> String {
 ///
 /// This function returns a shortened author for short-form citations. It starts
 /// by compiling the last name of the first author (non-dropping particle +
-/// family name). If there are two authors, the function then adds an " & " and
-/// the short name of the second author. If there are three or more authors, the
-/// function simply adds " et al."
-pub fn build_short_author(name_vector: &[NameVariable]) -> String {
+/// family name). If there are fewer authors than the house style's limit (by
+/// default, three), the short names of the other authors are joined with an
+/// " & " (or the house style's conjunction). Otherwise, the function simply
+/// adds " et al."
+pub fn build_short_author(name_vector: &[NameVariable], house_style: &HouseStyle) -> String {
     trace!(slog_scope::logger(), "name_vector: {:?}", name_vector);
 
-    // Create a new string that will probably have sufficient capacity.
-    let mut short_author = String::with_capacity(128);
+    let short_author = if name_vector.len() >= house_style.short_et_al {
+        let mut short_author = build_family_name(&name_vector[0]);
+        short_author.push_str(" et al.");
+        short_author
+    } else {
+        let names: Vec<String> = name_vector.iter().map(build_family_name).collect();
+        join_names(&names, &house_style.author_conjunction)
+    };
+
+    trace!(slog_scope::logger(), "short_author: {:?}", short_author);
+
+    short_author
+}
 
-    // Create the short name for the first (and potentially only) author.
-    if name_vector[0].non_dropping_particle.is_some() {
-        short_author.push_str(name_vector[0].non_dropping_particle.as_ref().unwrap());
-        short_author.push(' ');
+/// Joins names with commas and a conjunction before the last name.
+fn join_names(names: &[String], conjunction: &str) -> String {
+    match names.len() {
+        0 => String::new(),
+        1 => names[0].clone(),
+        n => format!(
+            "{} {} {}",
+            names[..n - 1].join(", "),
+            conjunction,
+            names[n - 1]
+        ),
     }
-    if name_vector[0].family.is_some() {
-        short_author.push_str(name_vector[0].family.as_ref().unwrap());
+}
+
+/// Builds a single author's full name.
+///
+/// The name is built in the following order: given name, dropping particle,
+/// non-dropping particle, family name, and suffix. If the `suffix` is "Jr." or
+/// "Sr.", a comma and space precede it. Any other `suffix` gets only a space.
+/// A literal name is used as is.
+pub fn build_full_name(name: &NameVariable) -> String {
+    if let Some(literal) = &name.literal {
+        return literal.to_string();
     }
 
-    // Then determine what to do based on the number of other authors. If there
-    // are two, push on an ampersand and the short name for the second author.
-    // Otherwise use the first author's name and an "et al."
-    match name_vector.len() {
-        1 => {}
-        2 => {
-            short_author.push_str(" & ");
+    let mut full_name = String::with_capacity(64);
 
-            if name_vector[1].non_dropping_particle.is_some() {
-                short_author.push_str(name_vector[1].non_dropping_particle.as_ref().unwrap());
-                short_author.push(' ');
-            }
-            if name_vector[1].family.is_some() {
-                short_author.push_str(name_vector[1].family.as_ref().unwrap());
-            }
+    if let Some(given) = &name.given {
+        full_name.push_str(given);
+        full_name.push(' ');
+    }
+    if let Some(particle) = &name.dropping_particle {
+        full_name.push_str(particle);
+        full_name.push(' ');
+    }
+    if let Some(particle) = &name.non_dropping_particle {
+        full_name.push_str(particle);
+        full_name.push(' ');
+    }
+    if let Some(family) = &name.family {
+        full_name.push_str(family);
+    }
+    if let Some(suffix) = &name.suffix {
+        if suffix == "Jr." || suffix == "Sr." {
+            full_name.push_str(", ");
+        } else {
+            full_name.push(' ');
         }
-        _ => short_author.push_str(" et al."),
+        full_name.push_str(suffix);
     }
 
-    trace!(slog_scope::logger(), "short_author: {:?}", short_author);
+    full_name
+}
 
-    short_author
+/// Builds a single author's family name (non-dropping particle + family).
+///
+/// A literal name is used as is.
+pub fn build_family_name(name: &NameVariable) -> String {
+    if let Some(literal) = &name.literal {
+        return literal.to_string();
+    }
+
+    let mut family_name = String::with_capacity(64);
+
+    if let Some(particle) = &name.non_dropping_particle {
+        family_name.push_str(particle);
+        family_name.push(' ');
+    }
+    if let Some(family) = &name.family {
+        family_name.push_str(family);
+    }
+
+    family_name
 }
 
 /// Add the title.
 ///
-/// If it's a book, also bold it. If it's a chapter, journal, or manuscript,
-/// "reverse italicize" it. If it's a case, italicize `In re` and *ex rel*;
+/// If it's a book, also bold it (or, in the Bluepages, italicize it). If it's a
+/// chapter, journal, or manuscript, "reverse italicize" it. If it's a case,
+/// italicize `In re` and *ex rel*, or, in the Bluepages, the whole name;
 /// otherwise let it be.
 ///
 /// TODO: shorten words in case names?
-pub fn add_title(csl_source: &CSLSource, source_type: &SourceType, cite: &mut String) {
+pub fn add_title(
+    csl_source: &CSLSource,
+    source_type: &SourceType,
+    style: Style,
+    cite: &mut String,
+) {
     if source_type == &SourceType::Book {
-        let title = slog_scope::scope(&slog_scope::logger().new(o!("fn" => "bold()")), || {
-            bold(csl_source.title.as_ref().unwrap())
-        });
+        let title = slog_scope::scope(
+            &slog_scope::logger().new(o!("fn" => "book_title()")),
+            || book_title(csl_source.title.as_ref().unwrap(), style),
+        );
         cite.push_str(&title);
     } else if source_type == &SourceType::Chapter
         || source_type == &SourceType::JournalArticle
@@ -209,6 +268,8 @@ pub fn add_title(csl_source: &CSLSource, source_type: &SourceType, cite: &mut St
             || reverse_italicize(csl_source.title.as_ref().unwrap()),
         );
         cite.push_str(&title);
+    } else if source_type == &SourceType::Case && style == Style::Bluepages {
+        cite.push_str(&format!("*{}*", csl_source.title.as_ref().unwrap()));
     } else if source_type == &SourceType::Case {
         let mut title = csl_source.title.as_ref().unwrap().clone();
 
@@ -228,11 +289,16 @@ pub fn add_title(csl_source: &CSLSource, source_type: &SourceType, cite: &mut St
 ///
 /// This adds the shortened title for a source. If there isn't one, it uses the
 /// long title and warns the user.
-pub fn add_short_title(csl_source: &CSLSource, source_type: &SourceType, cite: &mut String) {
+pub fn add_short_title(
+    csl_source: &CSLSource,
+    source_type: &SourceType,
+    style: Style,
+    cite: &mut String,
+) {
     let short_title;
     if csl_source.title_short.is_some() {
         if source_type == &SourceType::Book {
-            short_title = bold(csl_source.title_short.as_ref().unwrap());
+            short_title = book_title(csl_source.title_short.as_ref().unwrap(), style);
         } else if source_type == &SourceType::Case {
             short_title = format!("*{}*", &csl_source.title_short.as_ref().unwrap());
         } else {
@@ -243,7 +309,7 @@ pub fn add_short_title(csl_source: &CSLSource, source_type: &SourceType, cite: &
         }
     } else {
         short_title = if source_type == &SourceType::Book {
-            bold(csl_source.title.as_ref().unwrap())
+            book_title(csl_source.title.as_ref().unwrap(), style)
         } else if source_type == &SourceType::Case {
             format!("*{}*", csl_source.title.as_ref().unwrap())
         } else {
@@ -335,20 +401,21 @@ pub fn add_container_name(
     csl_source: &CSLSource,
     source_type: &SourceType,
     user_journals: &Option<UserJournals>,
+    style: Style,
     cite: &mut String,
 ) {
     let container_title;
     if source_type == &SourceType::Chapter {
-        container_title =
-            slog_scope::scope(&slog_scope::logger().new(o!("fn" => "bold()")), || {
-                bold(csl_source.container_title.as_ref().unwrap())
-            });
+        container_title = slog_scope::scope(
+            &slog_scope::logger().new(o!("fn" => "book_title()")),
+            || book_title(csl_source.container_title.as_ref().unwrap(), style),
+        );
     } else if source_type == &SourceType::Case {
         container_title = csl_source.container_title.as_ref().unwrap().to_string();
     } else if csl_source.container_title_short.is_some() {
         container_title =
             slog_scope::scope(&slog_scope::logger().new(o!("fn" => "bold()")), || {
-                bold(csl_source.container_title_short.as_ref().unwrap())
+                bold(csl_source.container_title_short.as_ref().unwrap(), style)
             });
     } else {
         let short_journal = slog_scope::scope(
@@ -357,7 +424,7 @@ pub fn add_container_name(
         );
         container_title =
             slog_scope::scope(&slog_scope::logger().new(o!("fn" => "bold()")), || {
-                bold(&short_journal)
+                bold(&short_journal, style)
             });
     }
 
@@ -473,16 +540,20 @@ pub fn add_first_page(csl_source: &CSLSource, cite: &mut String) {
 
 /// Add the ending parenthetical with court, edition, editors, translators, and
 /// year.
-pub fn add_end_parenthetical(csl_source: &CSLSource, source_type: &SourceType, cite: &mut String) {
+pub fn add_end_parenthetical(
+    csl_source: &CSLSource,
+    source_type: &SourceType,
+    house_style: &HouseStyle,
+    cite: &mut String,
+) {
     cite.push_str(" (");
 
-    // If it's a case, add the authority.
-    if source_type == &SourceType::Case
-        && csl_source.authority.is_some()
-        && csl_source.authority.as_ref().unwrap() != "U.S. Supreme Court"
-    {
-        cite.push_str(csl_source.authority.as_ref().unwrap());
-        cite.push(' ');
+    // If it's a case, add the court.
+    if source_type == &SourceType::Case {
+        if let Some(court) = court(csl_source) {
+            cite.push_str(&court);
+            cite.push(' ');
+        }
     }
 
     // Add the edition.
@@ -507,7 +578,7 @@ pub fn add_end_parenthetical(csl_source: &CSLSource, source_type: &SourceType, c
     {
         let editors = slog_scope::scope(
             &slog_scope::logger().new(o!("fn" => "build_long_author")),
-            || build_long_author(csl_source.editor.as_ref().unwrap()),
+            || build_long_author(csl_source.editor.as_ref().unwrap(), house_style),
         );
         cite.push_str(&editors);
         if csl_source.editor.as_ref().unwrap().len() > 1 {
@@ -523,7 +594,7 @@ pub fn add_end_parenthetical(csl_source: &CSLSource, source_type: &SourceType, c
     {
         let translators = slog_scope::scope(
             &slog_scope::logger().new(o!("fn" => "build_long_author")),
-            || build_long_author(csl_source.translator.as_ref().unwrap()),
+            || build_long_author(csl_source.translator.as_ref().unwrap(), house_style),
         );
         cite.push_str(&translators);
         cite.push_str(" trans., ");
@@ -547,6 +618,24 @@ pub fn add_end_parenthetical(csl_source: &CSLSource, source_type: &SourceType, c
     cite.push(')');
 }
 
+/// Get the court for a case's parenthetical.
+///
+/// Juris-M's jurisdiction and court codes are abbreviated, and a court that
+/// isn't known is left out. Without a jurisdiction, `authority` is used as it
+/// is. The U.S. Supreme Court is omitted.
+pub fn court(csl_source: &CSLSource) -> Option<String> {
+    // With a jurisdiction, `authority` is a court code rather than a court.
+    if csl_source.jurisdiction.is_some() {
+        return courts::abbreviate_court(csl_source).filter(|c| !c.is_empty());
+    }
+
+    csl_source
+        .authority
+        .as_ref()
+        .filter(|a| *a != "U.S. Supreme Court")
+        .cloned()
+}
+
 /// Add the weight of authority to a case.
 pub fn add_weight(csl_source: &CSLSource, cite: &mut String) {
     cite.push(' ');
@@ -554,12 +643,46 @@ pub fn add_weight(csl_source: &CSLSource, cite: &mut String) {
 }
 
 /// Add the "hereinafter" to sources that need it.
-pub fn add_hereinafter(csl_source: &CSLSource, source_type: &SourceType, cite: &mut String) {
-    cite.push_str(" [hereinafter ");
-    add_short_author(csl_source, source_type, cite);
-    cite.push_str(", ");
-    add_short_title(csl_source, source_type, cite);
-    cite.push(']');
+///
+/// The hereinafter is in brackets unless the house style calls for
+/// parentheses. A `supra-hereinafter` in the source's note replaces the usual
+/// short author and title.
+pub fn add_hereinafter(
+    csl_source: &CSLSource,
+    source_type: &SourceType,
+    house_style: &HouseStyle,
+    style: Style,
+    cite: &mut String,
+) {
+    let (open, close) = if house_style.hereinafter_parentheses {
+        ('(', ')')
+    } else {
+        ('[', ']')
+    };
+
+    cite.push(' ');
+    cite.push(open);
+    cite.push_str("hereinafter ");
+    if let Some(hereinafter) = &csl_source.supra.hereinafter {
+        cite.push_str(hereinafter);
+    } else {
+        add_short_author(csl_source, source_type, house_style, style, cite);
+        cite.push_str(", ");
+        add_short_title(csl_source, source_type, style, cite);
+    }
+    cite.push(close);
+}
+
+/// Get the name a source's note gives for its short cites.
+///
+/// A `supra-short-form` always replaces the name. A `supra-hereinafter`
+/// replaces it only when the source uses a hereinafter.
+pub fn note_short_name(csl_source: &CSLSource, hereinafter: bool) -> Option<&str> {
+    csl_source.supra.short_form.as_deref().or(if hereinafter {
+        csl_source.supra.hereinafter.as_deref()
+    } else {
+        None
+    })
 }
 
 /// Add the url.
@@ -568,11 +691,84 @@ pub fn add_url(csl_source: &CSLSource, cite: &mut String) {
     cite.push_str(csl_source.url.as_ref().unwrap());
 }
 
-/// Adds markdown bolding to a string.
-pub fn bold(in_string: &str) -> String {
+/// Adds markdown bolding (large and small caps) to a string.
+///
+/// The Bluepages use ordinary roman type instead, so the string is left as it
+/// is.
+pub fn bold(in_string: &str, style: Style) -> String {
     trace!(slog_scope::logger(), "in_string: {}", in_string);
 
-    format!("**{}**", in_string)
+    match style {
+        Style::Bluepages => in_string.to_string(),
+        _ => format!("**{}**", in_string),
+    }
+}
+
+/// Sets a book title in the book typeface: bold (large and small caps) in law
+/// reviews, italics in the Bluepages.
+pub fn book_title(title: &str, style: Style) -> String {
+    match style {
+        Style::Bluepages => format!("*{}*", title),
+        _ => bold(title, style),
+    }
+}
+
+/// Gets the year as a string.
+pub fn get_year(csl_source: &CSLSource) -> Option<String> {
+    csl_source
+        .issued
+        .as_ref()
+        .and_then(|i| i.date_parts.as_ref())
+        .and_then(|d| d.first())
+        .and_then(|d| d.first())
+        .map(|y| y.to_string())
+}
+
+/// Puts a title in single quotation marks. Anything already italicized stays
+/// italicized.
+pub fn quoted_title(title: &str) -> String {
+    format!("'{}'", ITALICS4.replace_all(title, "*"))
+}
+
+/// Italicizes a case name and drops the period from "v."
+pub fn case_title(title: &str) -> String {
+    format!("*{}*", title.replace(" v. ", " v "))
+}
+
+/// Removes the periods from abbreviations, e.g., "F.3d" becomes "F3d."
+pub fn remove_periods(abbreviation: &str) -> String {
+    abbreviation.replace('.', "")
+}
+
+/// The suffix of an ordinal number, *e.g.*, "nd" for 22.
+fn ordinal_suffix(n: u32) -> &'static str {
+    match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// Converts a numeric edition to an ordinal (e.g., "4" to "4th" or "2d" to
+/// "2nd"). Non-numeric editions are returned as they are.
+pub fn ordinal(edition: &str) -> String {
+    let digits: String = edition.chars().take_while(|c| c.is_ascii_digit()).collect();
+
+    match digits.parse::<u32>() {
+        Ok(n) => format!("{}{}", n, ordinal_suffix(n)),
+        Err(_) => edition.to_string(),
+    }
+}
+
+/// Converts a number to the Bluebook's ordinal (rule 6.2(b)), which uses "d"
+/// for "nd" and "rd," *e.g.*, 2 to "2d" and 23 to "23d".
+pub fn bluebook_ordinal(n: u32) -> String {
+    match ordinal_suffix(n) {
+        "nd" | "rd" => format!("{}d", n),
+        suffix => format!("{}{}", n, suffix),
+    }
 }
 
 #[cfg(test)]
@@ -589,9 +785,10 @@ mod tests {
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let test_vec = vec![name_one];
-            let long_author = build_long_author(&test_vec);
+            let long_author = build_long_author(&test_vec, &HouseStyle::default());
 
             assert_eq!(long_author, "Given Non Dropping Particle Family Suffix");
         }
@@ -603,15 +800,17 @@ mod tests {
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let name_two = NameVariable {
                 family: Some("Family".to_string()),
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let test_vec = vec![name_one, name_two];
-            let long_author = build_long_author(&test_vec);
+            let long_author = build_long_author(&test_vec, &HouseStyle::default());
 
             assert_eq!(
             long_author,
@@ -626,21 +825,24 @@ mod tests {
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let name_two = NameVariable {
                 family: Some("Family".to_string()),
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let name_three = NameVariable {
                 family: Some("Family".to_string()),
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let test_vec = vec![name_one, name_two, name_three];
-            let long_author = build_long_author(&test_vec);
+            let long_author = build_long_author(&test_vec, &HouseStyle::default());
 
             assert_eq!(
             long_author,
@@ -655,9 +857,10 @@ mod tests {
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let test_vec = vec![name_one];
-            let short_author = build_short_author(&test_vec);
+            let short_author = build_short_author(&test_vec, &HouseStyle::default());
 
             assert_eq!(short_author, "Non Dropping Particle Family");
         }
@@ -669,15 +872,17 @@ mod tests {
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let name_two = NameVariable {
                 family: Some("Family".to_string()),
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let test_vec = vec![name_one, name_two];
-            let short_author = build_short_author(&test_vec);
+            let short_author = build_short_author(&test_vec, &HouseStyle::default());
 
             assert_eq!(
                 short_author,
@@ -692,21 +897,24 @@ mod tests {
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let name_two = NameVariable {
                 family: Some("Family".to_string()),
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let name_three = NameVariable {
                 family: Some("Family".to_string()),
                 given: Some("Given".to_string()),
                 non_dropping_particle: Some("Non Dropping Particle".to_string()),
                 suffix: Some("Suffix".to_string()),
+                ..Default::default()
             };
             let test_vec = vec![name_one, name_two, name_three];
-            let short_author = build_short_author(&test_vec);
+            let short_author = build_short_author(&test_vec, &HouseStyle::default());
 
             assert_eq!(short_author, "Non Dropping Particle Family et al.");
         }
@@ -718,22 +926,25 @@ mod tests {
                 given: Some("Ludwig".to_string()),
                 non_dropping_particle: None,
                 suffix: None,
+                ..Default::default()
             };
             let name_two = NameVariable {
                 family: Some("Johnson".to_string()),
                 given: Some("Sam".to_string()),
                 non_dropping_particle: None,
                 suffix: Some("Jr.".to_string()),
+                ..Default::default()
             };
             let name_three = NameVariable {
                 family: Some("Smith".to_string()),
                 given: Some("Jane".to_string()),
                 non_dropping_particle: None,
                 suffix: None,
+                ..Default::default()
             };
             let test_vec = vec![name_one, name_two, name_three];
-            let long_author = build_long_author(&test_vec);
-            let short_author = build_short_author(&test_vec);
+            let long_author = build_long_author(&test_vec, &HouseStyle::default());
+            let short_author = build_short_author(&test_vec, &HouseStyle::default());
 
             assert_eq!(
                 long_author,
@@ -749,10 +960,11 @@ mod tests {
                 given: Some("Lindsay".to_string()),
                 non_dropping_particle: Some("de".to_string()),
                 suffix: None,
+                ..Default::default()
             };
             let test_vec = vec![name_one];
-            let long_author = build_long_author(&test_vec);
-            let short_author = build_short_author(&test_vec);
+            let long_author = build_long_author(&test_vec, &HouseStyle::default());
+            let short_author = build_short_author(&test_vec, &HouseStyle::default());
 
             assert_eq!(long_author, "Lindsay de Stefan");
             assert_eq!(short_author, "de Stefan");
@@ -902,4 +1114,37 @@ mod tests {
             assert_eq!(&short_name, "J. Stuff Won't Abbrev.");
         }
     }
+
+    mod style_tests {
+        use super::*;
+
+        #[test]
+        fn ordinals() {
+            assert_eq!(ordinal("1"), "1st");
+            assert_eq!(ordinal("2"), "2nd");
+            assert_eq!(ordinal("3"), "3rd");
+            assert_eq!(ordinal("4"), "4th");
+            assert_eq!(ordinal("11"), "11th");
+            assert_eq!(ordinal("22"), "22nd");
+            assert_eq!(ordinal("5th"), "5th");
+            assert_eq!(ordinal("2d"), "2nd");
+            assert_eq!(ordinal("Revised"), "Revised");
+        }
+
+        #[test]
+        fn bluebook_ordinals() {
+            assert_eq!(bluebook_ordinal(1), "1st");
+            assert_eq!(bluebook_ordinal(2), "2d");
+            assert_eq!(bluebook_ordinal(3), "3d");
+            assert_eq!(bluebook_ordinal(12), "12th");
+            assert_eq!(bluebook_ordinal(23), "23d");
+        }
+
+        #[test]
+        fn neutral_citations() {
+            assert!(NEUTRAL.is_match("[2021] UKSC 5"));
+            assert!(NEUTRAL.is_match("[2019] EWCA Civ 1234"));
+            assert!(!NEUTRAL.is_match("21-12345"));
+        }
+    }
 }
@@ -0,0 +1,353 @@
+//! This module contains the tables and functions for abbreviating Juris-M
+//! courts.
+//!
+//! Juris-M identifies a court with a `jurisdiction` code (*e.g.*, `us:c9` or
+//! `us:c2:ny.sd`) and a court code in `authority` (*e.g.*, `court.appeals` or
+//! `district.court`). From those, Supra builds the Bluebook's court
+//! abbreviation rather than using whatever string `authority` holds.
+
+use super::citetools::bluebook_ordinal;
+use crate::pre::csljson::CSLSource;
+use phf::phf_map;
+
+// State and territory abbreviations (Bluebook table T10), keyed by Juris-M's
+// jurisdiction code.
+pub static STATES: phf::Map<&'static str, &'static str> = phf_map! {
+    "al" => "Ala.",
+    "ak" => "Alaska",
+    "az" => "Ariz.",
+    "ar" => "Ark.",
+    "ca" => "Cal.",
+    "co" => "Colo.",
+    "ct" => "Conn.",
+    "de" => "Del.",
+    "dc" => "D.C.",
+    "fl" => "Fla.",
+    "ga" => "Ga.",
+    "gu" => "Guam",
+    "hi" => "Haw.",
+    "id" => "Idaho",
+    "il" => "Ill.",
+    "in" => "Ind.",
+    "ia" => "Iowa",
+    "ks" => "Kan.",
+    "ky" => "Ky.",
+    "la" => "La.",
+    "me" => "Me.",
+    "md" => "Md.",
+    "ma" => "Mass.",
+    "mi" => "Mich.",
+    "mn" => "Minn.",
+    "ms" => "Miss.",
+    "mo" => "Mo.",
+    "mt" => "Mont.",
+    "ne" => "Neb.",
+    "nv" => "Nev.",
+    "nh" => "N.H.",
+    "nj" => "N.J.",
+    "nm" => "N.M.",
+    "ny" => "N.Y.",
+    "nc" => "N.C.",
+    "nd" => "N.D.",
+    "oh" => "Ohio",
+    "ok" => "Okla.",
+    "or" => "Or.",
+    "pa" => "Pa.",
+    "pr" => "P.R.",
+    "ri" => "R.I.",
+    "sc" => "S.C.",
+    "sd" => "S.D.",
+    "tn" => "Tenn.",
+    "tx" => "Tex.",
+    "ut" => "Utah",
+    "vt" => "Vt.",
+    "vi" => "V.I.",
+    "va" => "Va.",
+    "wa" => "Wash.",
+    "wv" => "W. Va.",
+    "wi" => "Wis.",
+    "wy" => "Wyo.",
+};
+
+// State courts (Bluebook table T1), keyed by Juris-M's jurisdiction and court
+// codes.
+pub static STATE_COURTS: phf::Map<&'static str, &'static str> = phf_map! {
+    "al:supreme.court" => "Ala.",
+    "al:court.civil.appeals" => "Ala. Civ. App.",
+    "al:court.criminal.appeals" => "Ala. Crim. App.",
+    "ak:supreme.court" => "Alaska",
+    "ak:court.appeals" => "Alaska Ct. App.",
+    "az:supreme.court" => "Ariz.",
+    "az:court.appeals" => "Ariz. Ct. App.",
+    "ar:supreme.court" => "Ark.",
+    "ar:court.appeals" => "Ark. Ct. App.",
+    "ca:supreme.court" => "Cal.",
+    "ca:court.appeals" => "Cal. Ct. App.",
+    "co:supreme.court" => "Colo.",
+    "co:court.appeals" => "Colo. App.",
+    "ct:supreme.court" => "Conn.",
+    "ct:appellate.court" => "Conn. App. Ct.",
+    "de:supreme.court" => "Del.",
+    "de:superior.court" => "Del. Super. Ct.",
+    "de:court.chancery" => "Del. Ch.",
+    "dc:court.appeals" => "D.C.",
+    "fl:supreme.court" => "Fla.",
+    "fl:district.court.appeals" => "Fla. Dist. Ct. App.",
+    "ga:supreme.court" => "Ga.",
+    "ga:court.appeals" => "Ga. Ct. App.",
+    "gu:supreme.court" => "Guam",
+    "hi:supreme.court" => "Haw.",
+    "hi:intermediate.court.appeals" => "Haw. Ct. App.",
+    "id:supreme.court" => "Idaho",
+    "id:court.appeals" => "Idaho Ct. App.",
+    "il:supreme.court" => "Ill.",
+    "il:appellate.court" => "Ill. App. Ct.",
+    "in:supreme.court" => "Ind.",
+    "in:court.appeals" => "Ind. Ct. App.",
+    "ia:supreme.court" => "Iowa",
+    "ia:court.appeals" => "Iowa Ct. App.",
+    "ks:supreme.court" => "Kan.",
+    "ks:court.appeals" => "Kan. Ct. App.",
+    "ky:supreme.court" => "Ky.",
+    "ky:court.appeals" => "Ky. Ct. App.",
+    "la:supreme.court" => "La.",
+    "la:court.appeals" => "La. Ct. App.",
+    "me:supreme.judicial.court" => "Me.",
+    "md:supreme.court" => "Md.",
+    "md:court.appeals" => "Md.",
+    "md:court.special.appeals" => "Md. Ct. Spec. App.",
+    "md:appellate.court" => "Md. App. Ct.",
+    "ma:supreme.judicial.court" => "Mass.",
+    "ma:appeals.court" => "Mass. App. Ct.",
+    "mi:supreme.court" => "Mich.",
+    "mi:court.appeals" => "Mich. Ct. App.",
+    "mn:supreme.court" => "Minn.",
+    "mn:court.appeals" => "Minn. Ct. App.",
+    "ms:supreme.court" => "Miss.",
+    "ms:court.appeals" => "Miss. Ct. App.",
+    "mo:supreme.court" => "Mo.",
+    "mo:court.appeals" => "Mo. Ct. App.",
+    "mt:supreme.court" => "Mont.",
+    "ne:supreme.court" => "Neb.",
+    "ne:court.appeals" => "Neb. Ct. App.",
+    "nv:supreme.court" => "Nev.",
+    "nv:court.appeals" => "Nev. App.",
+    "nh:supreme.court" => "N.H.",
+    "nj:supreme.court" => "N.J.",
+    "nj:superior.court" => "N.J. Super. Ct.",
+    "nj:appellate.division" => "N.J. Super. Ct. App. Div.",
+    "nm:supreme.court" => "N.M.",
+    "nm:court.appeals" => "N.M. Ct. App.",
+    "ny:court.appeals" => "N.Y.",
+    "ny:appellate.division" => "N.Y. App. Div.",
+    "ny:appellate.term" => "N.Y. App. Term",
+    "ny:supreme.court" => "N.Y. Sup. Ct.",
+    "nc:supreme.court" => "N.C.",
+    "nc:court.appeals" => "N.C. Ct. App.",
+    "nd:supreme.court" => "N.D.",
+    "nd:court.appeals" => "N.D. Ct. App.",
+    "oh:supreme.court" => "Ohio",
+    "oh:court.appeals" => "Ohio Ct. App.",
+    "ok:supreme.court" => "Okla.",
+    "ok:court.criminal.appeals" => "Okla. Crim. App.",
+    "ok:court.civil.appeals" => "Okla. Civ. App.",
+    "or:supreme.court" => "Or.",
+    "or:court.appeals" => "Or. Ct. App.",
+    "pa:supreme.court" => "Pa.",
+    "pa:superior.court" => "Pa. Super. Ct.",
+    "pa:commonwealth.court" => "Pa. Commw. Ct.",
+    "pr:supreme.court" => "P.R.",
+    "ri:supreme.court" => "R.I.",
+    "sc:supreme.court" => "S.C.",
+    "sc:court.appeals" => "S.C. Ct. App.",
+    "sd:supreme.court" => "S.D.",
+    "tn:supreme.court" => "Tenn.",
+    "tn:court.appeals" => "Tenn. Ct. App.",
+    "tn:court.criminal.appeals" => "Tenn. Crim. App.",
+    "tx:supreme.court" => "Tex.",
+    "tx:court.criminal.appeals" => "Tex. Crim. App.",
+    "tx:court.appeals" => "Tex. App.",
+    "ut:supreme.court" => "Utah",
+    "ut:court.appeals" => "Utah Ct. App.",
+    "vt:supreme.court" => "Vt.",
+    "vi:supreme.court" => "V.I.",
+    "va:supreme.court" => "Va.",
+    "va:court.appeals" => "Va. Ct. App.",
+    "wa:supreme.court" => "Wash.",
+    "wa:court.appeals" => "Wash. Ct. App.",
+    "wv:supreme.court" => "W. Va.",
+    "wi:supreme.court" => "Wis.",
+    "wi:court.appeals" => "Wis. Ct. App.",
+    "wy:supreme.court" => "Wyo.",
+};
+
+// Federal circuits other than the numbered ones.
+pub static CIRCUITS: phf::Map<&'static str, &'static str> = phf_map! {
+    "cdc" => "D.C. Cir.",
+    "cfed" => "Fed. Cir.",
+};
+
+// Federal district-court divisions.
+pub static DISTRICTS: phf::Map<&'static str, &'static str> = phf_map! {
+    "d" => "D.",
+    "nd" => "N.D.",
+    "ed" => "E.D.",
+    "sd" => "S.D.",
+    "wd" => "W.D.",
+    "cd" => "C.D.",
+    "md" => "M.D.",
+};
+
+/// Abbreviates the court of a case from its Juris-M metadata.
+///
+/// Returns `None` if the source has no `jurisdiction` or the combination of
+/// codes isn't known. Returns an empty string for the U.S. Supreme Court, which
+/// the Bluebook omits. A `division` is joined to the court with an em dash,
+/// *e.g.*, "Tex. App.—Houston [1st Dist.]".
+pub fn abbreviate_court(csl_source: &CSLSource) -> Option<String> {
+    let jurisdiction = csl_source.jurisdiction.as_ref()?.to_lowercase();
+    let court = csl_source.authority.as_deref().unwrap_or("").to_lowercase();
+    let parts: Vec<&str> = jurisdiction.split(':').collect();
+
+    if parts[0] != "us" {
+        return None;
+    }
+
+    let mut abbreviation = match (&parts[1..], court.as_str()) {
+        ([], "supreme.court") => return Some(String::new()),
+        ([circuit], "court.appeals" | "") if !STATES.contains_key(circuit) => {
+            circuit_name(circuit)?
+        }
+        ([_, district], "district.court" | "") => district_name(district)?,
+        ([_, district], "bankruptcy.court") => format!("Bankr. {}", district_name(district)?),
+        ([state], _) => STATE_COURTS
+            .get(format!("{}:{}", state, court).as_str())?
+            .to_string(),
+        _ => return None,
+    };
+
+    if let Some(division) = &csl_source.division {
+        abbreviation.push('—');
+        abbreviation.push_str(division);
+    }
+
+    Some(abbreviation)
+}
+
+/// Abbreviates a federal circuit code, *e.g.*, `c9` to "9th Cir."
+fn circuit_name(circuit: &str) -> Option<String> {
+    if let Some(name) = CIRCUITS.get(circuit) {
+        return Some(name.to_string());
+    }
+
+    let number: u32 = circuit.strip_prefix('c')?.parse().ok()?;
+
+    Some(format!("{} Cir.", bluebook_ordinal(number)))
+}
+
+/// Abbreviates a federal district code, *e.g.*, `ny.sd` to "S.D.N.Y." or
+/// `ca.cd` to "C.D. Cal."
+///
+/// The district and state are closed up when the state is abbreviated with
+/// single capitals.
+fn district_name(district: &str) -> Option<String> {
+    let (state, division) = district.split_once('.').unwrap_or((district, "d"));
+    let state = STATES.get(state)?;
+    let division = DISTRICTS.get(division)?;
+
+    let closed_up = state
+        .split('.')
+        .filter(|p| !p.is_empty())
+        .all(|p| p.len() == 1);
+
+    if closed_up {
+        Some(format!("{}{}", division, state))
+    } else {
+        Some(format!("{} {}", division, state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(jurisdiction: &str, authority: &str) -> CSLSource {
+        CSLSource {
+            jurisdiction: Some(jurisdiction.to_string()),
+            authority: Some(authority.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn federal_courts() {
+        assert_eq!(
+            abbreviate_court(&case("us", "supreme.court")).as_deref(),
+            Some("")
+        );
+        assert_eq!(
+            abbreviate_court(&case("us:c9", "court.appeals")).as_deref(),
+            Some("9th Cir.")
+        );
+        assert_eq!(
+            abbreviate_court(&case("us:c2", "court.appeals")).as_deref(),
+            Some("2d Cir.")
+        );
+        assert_eq!(
+            abbreviate_court(&case("us:cdc", "court.appeals")).as_deref(),
+            Some("D.C. Cir.")
+        );
+        assert_eq!(
+            abbreviate_court(&case("us:c2:ny.sd", "district.court")).as_deref(),
+            Some("S.D.N.Y.")
+        );
+        assert_eq!(
+            abbreviate_court(&case("us:c9:ca.cd", "district.court")).as_deref(),
+            Some("C.D. Cal.")
+        );
+        assert_eq!(
+            abbreviate_court(&case("us:c1:ma", "bankruptcy.court")).as_deref(),
+            Some("Bankr. D. Mass.")
+        );
+    }
+
+    #[test]
+    fn state_courts() {
+        assert_eq!(
+            abbreviate_court(&case("us:ca", "supreme.court")).as_deref(),
+            Some("Cal.")
+        );
+        assert_eq!(
+            abbreviate_court(&case("us:ca", "court.appeals")).as_deref(),
+            Some("Cal. Ct. App.")
+        );
+        assert_eq!(
+            abbreviate_court(&case("us:ny", "court.appeals")).as_deref(),
+            Some("N.Y.")
+        );
+        assert_eq!(
+            abbreviate_court(&case("us:ny", "supreme.court")).as_deref(),
+            Some("N.Y. Sup. Ct.")
+        );
+        assert_eq!(
+            abbreviate_court(&case("us:il", "appellate.court")).as_deref(),
+            Some("Ill. App. Ct.")
+        );
+
+        let mut divided = case("us:tx", "court.appeals");
+        divided.division = Some("Houston [1st Dist.]".to_string());
+        assert_eq!(
+            abbreviate_court(&divided).as_deref(),
+            Some("Tex. App.—Houston [1st Dist.]")
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(abbreviate_court(&case("gb", "supreme.court")), None);
+        assert_eq!(abbreviate_court(&case("us:zz", "supreme.court")), None);
+        assert_eq!(abbreviate_court(&case("us:ny", "court.chancery")), None);
+        assert_eq!(abbreviate_court(&case("us:ca", "")), None);
+        assert_eq!(abbreviate_court(&CSLSource::default()), None);
+    }
+}
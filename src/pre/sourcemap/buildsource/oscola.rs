@@ -0,0 +1,384 @@
+//! Functions for building OSCOLA-style sources.
+//!
+//! OSCOLA (the Oxford University Standard for the Citation of Legal
+//! Authorities) differs from the Bluebook in most of the details: no bold or
+//! small caps, abbreviations without periods, quoted article titles, square- or
+//! round-bracketed years for cases, and short forms that refer back to the
+//! first footnote with "(n 12)".
+
+use super::citetools;
+use crate::pre::{
+    csljson::{CSLSource, NameVariable},
+    sourcemap::SourceType,
+    userjournals::UserJournals,
+};
+use citetools::{case_title, get_year, ordinal, quoted_title, remove_periods, NEUTRAL};
+use slog::o;
+
+/// Build the long cite---with and without a pin---for an OSCOLA source.
+///
+/// Returns the cite without a pin, the portion of the cite before any pin, and
+/// the portion of the cite after any pin.
+pub fn build_oscola_long(
+    csl_source: &CSLSource,
+    source_type: &SourceType,
+    user_journals: &Option<UserJournals>,
+) -> (String, String, String) {
+    let mut long_cite_pre_pin = String::with_capacity(1024);
+    let mut long_cite_post_pin = String::with_capacity(256);
+
+    match source_type {
+        SourceType::Book => {
+            if let Some(author) = &csl_source.author {
+                long_cite_pre_pin.push_str(&build_oscola_authors(author));
+                long_cite_pre_pin.push_str(", ");
+            } else if let Some(editor) = &csl_source.editor {
+                long_cite_pre_pin.push_str(&build_oscola_editors(editor));
+                long_cite_pre_pin.push_str(", ");
+            }
+
+            long_cite_pre_pin.push_str(&italic_title(csl_source.title.as_ref().unwrap()));
+
+            if let Some(volume) = &csl_source.volume {
+                long_cite_pre_pin.push_str(", vol ");
+                long_cite_pre_pin.push_str(volume);
+            }
+
+            add_publication_details(csl_source, source_type, &mut long_cite_pre_pin);
+        }
+        SourceType::Chapter => {
+            if let Some(author) = &csl_source.author {
+                long_cite_pre_pin.push_str(&build_oscola_authors(author));
+                long_cite_pre_pin.push_str(", ");
+            }
+
+            long_cite_pre_pin.push_str(&quoted_title(csl_source.title.as_ref().unwrap()));
+            long_cite_pre_pin.push_str(" in ");
+
+            if let Some(editor) = &csl_source.editor {
+                long_cite_pre_pin.push_str(&build_oscola_editors(editor));
+                long_cite_pre_pin.push_str(", ");
+            }
+
+            if let Some(container_title) = &csl_source.container_title {
+                long_cite_pre_pin.push_str(&italic_title(container_title));
+            }
+
+            if let Some(volume) = &csl_source.volume {
+                long_cite_pre_pin.push_str(", vol ");
+                long_cite_pre_pin.push_str(volume);
+            }
+
+            add_publication_details(csl_source, source_type, &mut long_cite_pre_pin);
+        }
+        SourceType::JournalArticle => {
+            if let Some(author) = &csl_source.author {
+                long_cite_pre_pin.push_str(&build_oscola_authors(author));
+                long_cite_pre_pin.push_str(", ");
+            }
+
+            long_cite_pre_pin.push_str(&quoted_title(csl_source.title.as_ref().unwrap()));
+            long_cite_pre_pin.push(' ');
+
+            // Journals that use the year as the volume put the year in square
+            // brackets and omit the volume.
+            let year = get_year(csl_source);
+            match &csl_source.volume {
+                Some(volume) if csl_source.volume != year => {
+                    if let Some(y) = &year {
+                        long_cite_pre_pin.push_str(&format!("({}) ", y));
+                    }
+                    long_cite_pre_pin.push_str(volume);
+                    long_cite_pre_pin.push(' ');
+                }
+                _ => {
+                    if let Some(y) = &year {
+                        long_cite_pre_pin.push_str(&format!("[{}] ", y));
+                    }
+                }
+            }
+
+            if csl_source.container_title.is_some() {
+                long_cite_pre_pin.push_str(&build_oscola_journal(csl_source, user_journals));
+            }
+
+            if let Some(page) = &csl_source.page {
+                long_cite_pre_pin.push(' ');
+                long_cite_pre_pin.push_str(page);
+            }
+        }
+        SourceType::Manuscript => {
+            if let Some(author) = &csl_source.author {
+                long_cite_pre_pin.push_str(&build_oscola_authors(author));
+                long_cite_pre_pin.push_str(", ");
+            }
+
+            long_cite_pre_pin.push_str(&quoted_title(csl_source.title.as_ref().unwrap()));
+
+            let year = get_year(csl_source);
+            if csl_source.container_title.is_some() {
+                // A manuscript that has been placed with a journal.
+                if let Some(y) = &year {
+                    long_cite_pre_pin.push_str(&format!(" ({})", y));
+                }
+                if let Some(volume) = &csl_source.volume {
+                    long_cite_pre_pin.push(' ');
+                    long_cite_pre_pin.push_str(volume);
+                }
+                long_cite_pre_pin.push(' ');
+                long_cite_pre_pin.push_str(&build_oscola_journal(csl_source, user_journals));
+                long_cite_pre_pin.push_str(" (forthcoming)");
+            } else if let Some(y) = &year {
+                long_cite_pre_pin.push_str(&format!(" (forthcoming {})", y));
+            }
+
+            if let Some(url) = &csl_source.url {
+                long_cite_post_pin.push_str(" <");
+                long_cite_post_pin.push_str(url);
+                long_cite_post_pin.push('>');
+            }
+        }
+        SourceType::Case => {
+            long_cite_pre_pin.push_str(&case_title(csl_source.title.as_ref().unwrap()));
+
+            let year = get_year(csl_source);
+            let neutral = match &csl_source.number {
+                Some(number) => NEUTRAL.is_match(number),
+                None => false,
+            };
+
+            if neutral {
+                long_cite_pre_pin.push(' ');
+                long_cite_pre_pin.push_str(csl_source.number.as_ref().unwrap());
+                if csl_source.container_title.is_some() {
+                    long_cite_pre_pin.push(',');
+                }
+            }
+
+            if let Some(container_title) = &csl_source.container_title {
+                // Square brackets when the year identifies the volume, round
+                // brackets when the report series has its own volume numbers.
+                if let Some(y) = &year {
+                    if csl_source.volume.is_some() {
+                        long_cite_pre_pin.push_str(&format!(" ({})", y));
+                    } else {
+                        long_cite_pre_pin.push_str(&format!(" [{}]", y));
+                    }
+                }
+
+                if let Some(volume) = &csl_source.volume {
+                    long_cite_pre_pin.push(' ');
+                    long_cite_pre_pin.push_str(volume);
+                }
+
+                long_cite_pre_pin.push(' ');
+                long_cite_pre_pin.push_str(&remove_periods(container_title));
+
+                if let Some(page) = &csl_source.page {
+                    long_cite_pre_pin.push(' ');
+                    long_cite_pre_pin.push_str(page);
+                }
+            }
+
+            // Courts are given only when there's no neutral citation.
+            if let Some(court) = citetools::court(csl_source) {
+                if !neutral {
+                    long_cite_post_pin.push_str(" (");
+                    long_cite_post_pin.push_str(&remove_periods(&court));
+                    long_cite_post_pin.push(')');
+                }
+            }
+        }
+        SourceType::Other => {}
+    }
+
+    let mut long_cite_no_pin = long_cite_pre_pin.clone();
+    long_cite_no_pin.push_str(&long_cite_post_pin);
+
+    (long_cite_no_pin, long_cite_pre_pin, long_cite_post_pin)
+}
+
+/// Build the short cite---with and without a pin---for an OSCOLA source.
+///
+/// Secondary sources use the author's surname and a reference back to the
+/// first footnote, e.g., "Author (n 12)." If two sources by the same author are
+/// cited, the short title follows the surname. Cases use the short case name
+/// and the reference back to the first footnote. A short form or hereinafter
+/// from the source's note replaces the surname or case name.
+///
+/// The surnames are joined with `join_names`, so styles that share OSCOLA's
+/// short cites can shorten long author lists their own way.
+pub fn build_oscola_short(
+    csl_source: &CSLSource,
+    source_type: &SourceType,
+    first_footnote: i32,
+    hereinafter: bool,
+    join_names: fn(&[String]) -> String,
+) -> (String, String) {
+    let mut short_cite = String::with_capacity(256);
+
+    let short_title = csl_source
+        .title_short
+        .as_ref()
+        .unwrap_or_else(|| csl_source.title.as_ref().unwrap());
+
+    if let Some(name) = citetools::note_short_name(csl_source, hereinafter) {
+        short_cite.push_str(name);
+    } else if source_type == &SourceType::Case {
+        short_cite.push_str(&case_title(short_title));
+    } else {
+        if let Some(author) = &csl_source.author {
+            short_cite.push_str(&build_oscola_short_author(author, join_names));
+        }
+
+        if hereinafter || csl_source.author.is_none() {
+            if !short_cite.is_empty() {
+                short_cite.push_str(", ");
+            }
+            if source_type == &SourceType::Book {
+                short_cite.push_str(&italic_title(short_title));
+            } else {
+                short_cite.push_str(&quoted_title(short_title));
+            }
+        }
+    }
+
+    short_cite.push_str(" (n ");
+    short_cite.push_str(&first_footnote.to_string());
+    short_cite.push(')');
+
+    (short_cite.clone(), short_cite)
+}
+
+/// Builds the OSCOLA long author.
+///
+/// Authors' full names are joined with commas and a final "and." More than
+/// three authors become the first author and "and others."
+pub fn build_oscola_authors(name_vector: &[NameVariable]) -> String {
+    let names: Vec<String> = name_vector.iter().map(citetools::build_full_name).collect();
+
+    join_oscola_names(&names)
+}
+
+/// Builds the OSCOLA short author from the authors' surnames.
+fn build_oscola_short_author(
+    name_vector: &[NameVariable],
+    join_names: fn(&[String]) -> String,
+) -> String {
+    let names: Vec<String> = name_vector
+        .iter()
+        .map(citetools::build_family_name)
+        .collect();
+
+    join_names(&names)
+}
+
+/// Builds the editors with "(ed)" or "(eds)."
+fn build_oscola_editors(name_vector: &[NameVariable]) -> String {
+    let mut editors = build_oscola_authors(name_vector);
+    if name_vector.len() > 1 {
+        editors.push_str(" (eds)");
+    } else {
+        editors.push_str(" (ed)");
+    }
+
+    editors
+}
+
+/// Joins names OSCOLA-style.
+pub fn join_oscola_names(names: &[String]) -> String {
+    match names.len() {
+        0 => String::new(),
+        1 => names[0].clone(),
+        2 | 3 => format!(
+            "{} and {}",
+            names[..names.len() - 1].join(", "),
+            names[names.len() - 1]
+        ),
+        _ => format!("{} and others", names[0]),
+    }
+}
+
+/// Adds the parenthetical with the editors (for authored books), translators,
+/// edition, publisher, and year.
+fn add_publication_details(csl_source: &CSLSource, source_type: &SourceType, cite: &mut String) {
+    let mut details: Vec<String> = Vec::new();
+
+    if let Some(editor) = &csl_source.editor {
+        if source_type == &SourceType::Book && csl_source.author.is_some() {
+            details.push(format!("{} ed", build_oscola_authors(editor)));
+        }
+    }
+
+    if let Some(translator) = &csl_source.translator {
+        details.push(format!("{} tr", build_oscola_authors(translator)));
+    }
+
+    if let Some(edition) = &csl_source.edition {
+        details.push(format!("{} edn", ordinal(edition)));
+    }
+
+    let mut publication = String::new();
+    if let Some(publisher) = &csl_source.publisher {
+        publication.push_str(publisher);
+    }
+    if let Some(y) = get_year(csl_source) {
+        if !publication.is_empty() {
+            publication.push(' ');
+        }
+        publication.push_str(&y);
+    }
+    if !publication.is_empty() {
+        details.push(publication);
+    }
+
+    if !details.is_empty() {
+        cite.push_str(" (");
+        cite.push_str(&details.join(", "));
+        cite.push(')');
+    }
+}
+
+/// Builds the journal abbreviation, without periods.
+fn build_oscola_journal(csl_source: &CSLSource, user_journals: &Option<UserJournals>) -> String {
+    let journal = if let Some(container_title_short) = &csl_source.container_title_short {
+        container_title_short.to_string()
+    } else {
+        slog_scope::scope(
+            &slog_scope::logger().new(o!("fn" => "build_short_journal()")),
+            || {
+                citetools::build_short_journal(
+                    csl_source.container_title.as_ref().unwrap(),
+                    user_journals,
+                )
+            },
+        )
+    };
+
+    remove_periods(&journal)
+}
+
+/// Italicizes a book title. Anything already italicized becomes roman.
+fn italic_title(title: &str) -> String {
+    citetools::reverse_italicize(title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        let names = vec![
+            "A".to_string(),
+            "B".to_string(),
+            "C".to_string(),
+            "D".to_string(),
+        ];
+
+        assert_eq!(join_oscola_names(&names[..1]), "A");
+        assert_eq!(join_oscola_names(&names[..2]), "A and B");
+        assert_eq!(join_oscola_names(&names[..3]), "A, B and C");
+        assert_eq!(join_oscola_names(&names), "A and others");
+    }
+}